@@ -0,0 +1,17 @@
+use std::process::Command;
+
+// bakes `git describe` of the tree we're built from into the binary as SOLSOLVER_GIT_DESCRIBE, so a
+// bug report (see bundle.rs) says which version it came from. "unknown" if we're not in a checkout
+fn main() {
+    let describe = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SOLSOLVER_GIT_DESCRIBE={}", describe);
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/index");
+}
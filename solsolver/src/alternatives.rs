@@ -0,0 +1,81 @@
+use crate::board::{Board, Move, Solution};
+use crate::encoding::{FIELD_SEPARATOR, FROM_TO_SEPARATOR};
+use crate::relaxed::RelaxedBounds;
+use crate::rules::Rules;
+use crate::search::Heuristic;
+use std::cmp::Reverse;
+
+// for each move of a solution, the best of the other moves from the same board. the solution's
+// for the board as we read it, and when someone's playing it by hand and the screen's a little
+// different (a card's somewhere else, say) the move it asks for might not be there. these are what
+// to try instead without solving again. "best" is only the heuristic's say on the board each one
+// leaves, so they're hints and not solutions: nothing says the rest of the solution still works
+// after one
+
+// how many alternatives we give per move
+pub const NUM_ALTERNATIVES: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alternative {
+    pub moov: Move,
+    // the heuristic's value on the board the move leaves, lower is better
+    pub estimate: usize,
+    // see Move::lookahead, higher is better
+    pub lookahead: usize,
+}
+
+impl Alternative {
+    // FROM-TO@ESTIMATE@LOOKAHEAD@HUMAN_READABLE
+    pub fn serialize(&self) -> String {
+        format!(
+            "{}{}{}{}{}{}{}{}{}",
+            self.moov.from.serialize(),
+            FROM_TO_SEPARATOR,
+            self.moov.to.serialize(),
+            FIELD_SEPARATOR,
+            self.estimate,
+            FIELD_SEPARATOR,
+            self.lookahead,
+            FIELD_SEPARATOR,
+            self.moov
+        )
+    }
+}
+
+// one list per move of the solution, best first, with up to NUM_ALTERNATIVES in each
+pub fn annotate(
+    board: &Board,
+    solution: &Solution,
+    heuristic: Heuristic,
+    rules: &Rules,
+) -> Vec<Vec<Alternative>> {
+    let relaxed_bounds = RelaxedBounds::new();
+    let estimate = |b: &Board| match heuristic {
+        Heuristic::CardsRemaining => b.num_cards_remaining(),
+        Heuristic::RelaxedLowerBound => relaxed_bounds.lower_bound(b),
+    };
+    let mut board = board.clone();
+    solution
+        .moves
+        .iter()
+        .map(|chosen| {
+            let mut alternatives: Vec<Alternative> = board
+                .next_boards(rules)
+                .into_iter()
+                .filter(|(_, moov)| {
+                    !(moov.from.same_place(&chosen.from) && moov.to.same_place(&chosen.to))
+                })
+                .map(|(next_board, moov)| Alternative {
+                    estimate: estimate(&next_board),
+                    lookahead: moov.lookahead(&next_board),
+                    moov,
+                })
+                .collect();
+            alternatives
+                .sort_by_key(|alternative| (alternative.estimate, Reverse(alternative.lookahead)));
+            alternatives.truncate(NUM_ALTERNATIVES);
+            board = board.play(chosen.from, chosen.to, rules).unwrap().0;
+            alternatives
+        })
+        .collect()
+}
@@ -0,0 +1,1192 @@
+use crate::card::{Card, CardId, CardSet, MajorValue, MinorValue, Suit, NUM_SUITS};
+use crate::encoding::{
+    self, DEPTH_SEPARATOR, FIELD_SEPARATOR, FROM_TO_SEPARATOR, POSITION_SEPARATOR, SUCK_ARROW,
+    SUCK_SEPARATOR,
+};
+use crate::error::BoardError;
+use crate::rules::Rules;
+use crate::warnings::{warn, Warning};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::{Display, Formatter};
+use core::hash::Hash;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MoveLocation {
+    BlockMinorPiles,
+    PlayingArea { pile: usize, depth: usize },
+    // only with Rules::explicit_foundation_moves, Rules::optional_sucks or
+    // Rules::optional_major_sucks, the rest of the time cards reach the foundations by being sucked
+    MajorLower,
+    MajorHigher,
+    MinorFoundation(Suit),
+}
+
+impl Display for MoveLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MoveLocation::BlockMinorPiles => f.write_str(encoding::BLOCK),
+            MoveLocation::PlayingArea { pile, depth: _ } => Display::fmt(pile, f),
+            MoveLocation::MajorLower => f.write_str(encoding::MAJOR_LOWER),
+            MoveLocation::MajorHigher => f.write_str(encoding::MAJOR_HIGHER),
+            MoveLocation::MinorFoundation(suit) => Display::fmt(suit, f),
+        }
+    }
+}
+
+// where a sucked card ends up. there are six of these, and on the wire they're called MAJ_LO,
+// MAJ_HI, SWO, WAN, CUP and STA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Foundation {
+    // the majors collected upwards from 0
+    MajorLower,
+    // the majors collected downwards from 21
+    MajorHigher,
+    Minor(Suit),
+}
+
+impl Foundation {
+    pub fn serialize(&self) -> String {
+        match self {
+            Foundation::MajorLower => encoding::MAJOR_LOWER.to_string(),
+            Foundation::MajorHigher => encoding::MAJOR_HIGHER.to_string(),
+            Foundation::Minor(suit) => suit.serialize().to_string(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            encoding::MAJOR_LOWER => Some(Foundation::MajorLower),
+            encoding::MAJOR_HIGHER => Some(Foundation::MajorHigher),
+            _ => encoding::suit_from_wire(s).map(Foundation::Minor),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Suck {
+    pub card: Card,
+    pub foundation: Foundation,
+    // where in the foundation the card lands, counting from the bottom like MoveLocation's depth.
+    // the minor piles start with their ace at depth 0
+    pub depth: usize,
+}
+
+impl Suck {
+    // minor cards can only go to the collection pile for their suit
+    fn new(card: Card, depth: usize) -> Self {
+        let foundation = match card {
+            Card::Minor { suit, .. } => Foundation::Minor(suit),
+            Card::Major(_) => unreachable!("majors go to one of the two major piles"),
+        };
+        Self {
+            card,
+            foundation,
+            depth,
+        }
+    }
+
+    pub fn serialize(&self, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::V1 | Protocol::V2 => format!(
+                "{}{}{}",
+                self.card.serialize(),
+                SUCK_ARROW,
+                self.foundation.serialize()
+            ),
+            Protocol::V3 => format!(
+                "{}{}{}{}{}",
+                self.card.serialize(),
+                SUCK_ARROW,
+                self.foundation.serialize(),
+                DEPTH_SEPARATOR,
+                self.depth
+            ),
+        }
+    }
+
+    // the inverse of serialize. V1 and V2 don't say how deep, so that comes back as 0
+    pub fn parse(s: &str) -> Self {
+        let (card, foundation) = s.split_once(SUCK_ARROW).unwrap();
+        let (foundation, depth) = match foundation.split_once(DEPTH_SEPARATOR) {
+            Some((foundation, depth)) => (foundation, depth.parse().unwrap()),
+            None => (foundation, 0),
+        };
+        Self {
+            card: Card::parse(card),
+            foundation: Foundation::parse(foundation)
+                .unwrap_or_else(|| panic!("Invalid foundation: {}", foundation)),
+            depth,
+        }
+    }
+}
+
+// versions of the line-per-move format we print to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    // FROM-TO@NUM_SUCKS@HUMAN_READABLE
+    V1,
+    // FROM-TO@NUM_SUCKS@SUCKS@HUMAN_READABLE, where SUCKS is a comma separated list of
+    // CARD>FOUNDATION in the order the cards get collected
+    V2,
+    // same as V2, but each suck is CARD>FOUNDATION:DEPTH, so the foundations can be tracked exactly
+    V3,
+}
+
+impl Protocol {
+    pub const NEWEST: Protocol = Protocol::V3;
+
+    pub fn number(&self) -> u8 {
+        match self {
+            Protocol::V1 => 1,
+            Protocol::V2 => 2,
+            Protocol::V3 => 3,
+        }
+    }
+
+    // how many fields a move line has, counting FROM-TO and the Display at the end
+    pub fn num_fields(&self) -> usize {
+        match self {
+            Protocol::V1 => 3,
+            Protocol::V2 | Protocol::V3 => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Move {
+    pub from: MoveLocation,
+    pub to: MoveLocation,
+    pub card: Card,
+    // the cards that get sucked into the collection piles after this move, in order. we count the
+    // number of sucks, so that in the GUI automation side, we know how long to wait before the next
+    // move, and which cards fly where so it can check the screen
+    pub sucks: Vec<Suck>,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(encoding::MOVE_CARD)?;
+        Display::fmt(&self.card, f)?;
+        f.write_str(encoding::MOVE_FROM)?;
+        Display::fmt(&self.from, f)?;
+        f.write_str(encoding::MOVE_TO)?;
+        Display::fmt(&self.to, f)
+    }
+}
+
+impl Move {
+    // raises a BoardError if this move doesn't make sense on the board it's about to be played on.
+    // a bad depth turns into a click on the wrong spot over on the GUI side, so we'd much rather
+    // find out here
+    pub fn validate(&self, board: &Board) {
+        let fail = |why: &'static str| -> ! {
+            BoardError::InvalidMove {
+                fingerprint: board.fingerprint_hex(),
+                moov: self.serialize(Protocol::V3),
+                why,
+            }
+            .raise()
+        };
+        match self.from {
+            MoveLocation::PlayingArea { pile, depth } => {
+                let Some(stack) = board.playing_area.get(pile) else {
+                    fail("no such source pile");
+                };
+                if depth + 1 != stack.len() {
+                    fail("source depth isn't the top of the pile");
+                }
+                if stack[depth].card() != self.card {
+                    fail("card isn't at the source");
+                }
+            }
+            MoveLocation::BlockMinorPiles => {
+                if board.minor_collection_blocked.map(CardId::card) != Some(self.card) {
+                    fail("card isn't on the block");
+                }
+            }
+            _ => fail("can't move a card off a foundation"),
+        }
+        match self.to {
+            MoveLocation::PlayingArea { pile, depth } => {
+                let Some(stack) = board.playing_area.get(pile) else {
+                    fail("no such destination pile");
+                };
+                if depth != stack.len() {
+                    fail("destination depth isn't just past the top of the pile");
+                }
+            }
+            MoveLocation::BlockMinorPiles => {
+                if board.minor_collection_blocked.is_some() {
+                    fail("block is already taken");
+                }
+            }
+            MoveLocation::MajorLower
+            | MoveLocation::MajorHigher
+            | MoveLocation::MinorFoundation(_) => {}
+        }
+    }
+
+    // where a move from serialize (any protocol) goes from and to. that's all it takes to play it
+    // again, see Board::play
+    pub fn parse_locations(s: &str) -> (MoveLocation, MoveLocation) {
+        let from_to = s.split(FIELD_SEPARATOR).next().unwrap();
+        let (from, to) = from_to.split_once(FROM_TO_SEPARATOR).unwrap();
+        (MoveLocation::parse(from), MoveLocation::parse(to))
+    }
+
+    // the inverse of serialize, as far as it goes: V1 only says how many sucks there were, so they
+    // come back empty, and V2 doesn't say how deep, see Suck::parse
+    pub fn parse(s: &str, protocol: Protocol) -> Self {
+        let (from, to) = Self::parse_locations(s);
+        let fields: Vec<&str> = s.splitn(protocol.num_fields(), FIELD_SEPARATOR).collect();
+        let displayed = fields.last().unwrap();
+        let card = displayed
+            .strip_prefix(encoding::MOVE_CARD)
+            .and_then(|rest| rest.split_once(encoding::MOVE_FROM))
+            .and_then(|(card, _)| Card::parse_displayed(card))
+            .unwrap_or_else(|| panic!("Invalid move: {}", s));
+        let sucks = match protocol {
+            Protocol::V1 => vec![],
+            Protocol::V2 | Protocol::V3 => fields[2]
+                .split_terminator(SUCK_SEPARATOR)
+                .map(Suck::parse)
+                .collect(),
+        };
+        Self {
+            from,
+            to,
+            card,
+            sucks,
+        }
+    }
+
+    pub fn num_sucks(&self) -> usize {
+        self.sucks.len()
+    }
+
+    // cards this move got onto the foundations, whether by sucking them or by putting one there
+    pub fn num_cards_home(&self) -> usize {
+        let by_hand = matches!(
+            self.to,
+            MoveLocation::MajorLower | MoveLocation::MajorHigher | MoveLocation::MinorFoundation(_)
+        );
+        self.num_sucks() + by_hand as usize
+    }
+
+    // the cards this move gets home, plus the ones it leaves a move away from being sucked on the
+    // board it leads to (see Board::num_sucks_in_reach). a move that sucks nothing but sets up a
+    // chain of sucks looks just like a useless one to num_cards_remaining, so the searches that
+    // go by that try the one with more of these first
+    pub fn lookahead(&self, next_board: &Board) -> usize {
+        self.num_cards_home() + next_board.num_sucks_in_reach()
+    }
+
+    pub fn serialize(&self, protocol: Protocol) -> String {
+        match protocol {
+            Protocol::V1 => format!(
+                "{}{}{}{}{}{}{}",
+                self.from.serialize(),
+                FROM_TO_SEPARATOR,
+                self.to.serialize(),
+                FIELD_SEPARATOR,
+                self.num_sucks(),
+                FIELD_SEPARATOR,
+                self,
+            ),
+            Protocol::V2 | Protocol::V3 => format!(
+                "{}{}{}{}{}{}{}{}{}",
+                self.from.serialize(),
+                FROM_TO_SEPARATOR,
+                self.to.serialize(),
+                FIELD_SEPARATOR,
+                self.num_sucks(),
+                FIELD_SEPARATOR,
+                self.sucks
+                    .iter()
+                    .map(|suck| suck.serialize(protocol))
+                    .collect::<Vec<_>>()
+                    .join(&SUCK_SEPARATOR.to_string()),
+                FIELD_SEPARATOR,
+                self,
+            ),
+        }
+    }
+}
+
+impl MoveLocation {
+    // the inverse of serialize
+    pub fn parse(s: &str) -> Self {
+        match s {
+            encoding::BLOCK => MoveLocation::BlockMinorPiles,
+            encoding::MAJOR_LOWER => MoveLocation::MajorLower,
+            encoding::MAJOR_HIGHER => MoveLocation::MajorHigher,
+            _ => match s.split_once(DEPTH_SEPARATOR) {
+                Some((pile, depth)) => MoveLocation::PlayingArea {
+                    pile: pile.parse().unwrap(),
+                    depth: depth.parse().unwrap(),
+                },
+                None => MoveLocation::MinorFoundation(Suit::parse(s)),
+            },
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        match self {
+            MoveLocation::BlockMinorPiles => encoding::BLOCK.to_string(),
+            MoveLocation::PlayingArea { pile, depth } => {
+                format!("{}{}{}", pile, DEPTH_SEPARATOR, depth)
+            }
+            MoveLocation::MajorLower => Foundation::MajorLower.serialize(),
+            MoveLocation::MajorHigher => Foundation::MajorHigher.serialize(),
+            MoveLocation::MinorFoundation(suit) => Foundation::Minor(*suit).serialize(),
+        }
+    }
+}
+
+pub const NUM_PLAYING_STACKS: usize = 11;
+
+const FIRST_MAJOR: CardId = CardId::from_card(Card::Major(MajorValue::first()));
+const LAST_MAJOR: CardId = CardId::from_card(Card::Major(MajorValue::last()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Board {
+    pub major_lower_stack: Vec<CardId>,
+    pub major_higher_stack: Vec<CardId>,
+    pub minor_collection_piles: [Vec<CardId>; NUM_SUITS],
+    pub minor_collection_blocked: Option<CardId>,
+    pub playing_area: [Vec<CardId>; NUM_PLAYING_STACKS],
+    // every card in playing_area, so we can ask if a card's still out there without looking
+    // through the piles. it's worked out from playing_area, so it can't make two boards that'd
+    // otherwise be equal different
+    pub tableau: CardSet,
+    // for each pile, how many cards from the top are a run, i.e. each one is next or previous to
+    // the one under it. same deal as tableau, it's derived from playing_area
+    pub top_runs: [u8; NUM_PLAYING_STACKS],
+}
+
+fn scan_top_run(stack: &[CardId]) -> u8 {
+    if stack.is_empty() {
+        return 0;
+    }
+    let num_links = stack
+        .windows(2)
+        .rev()
+        .take_while(|pair| pair[0].is_next_or_prev(pair[1]))
+        .count();
+    num_links as u8 + 1
+}
+
+// the lines after the piles, see Board::serialize
+fn is_position_line(line: &&str) -> bool {
+    line.trim()
+        .split_once(POSITION_SEPARATOR)
+        .map(|(key, _)| key == encoding::BLOCK || Foundation::parse(key).is_some())
+        .unwrap_or(false)
+}
+
+// the lines of a board's text that are piles, in order, as Board::parse sees them
+pub fn pile_lines(s: &str) -> impl Iterator<Item = &str> {
+    s.lines()
+        .filter(|line| !is_position_line(line))
+        .take(NUM_PLAYING_STACKS)
+}
+
+// while this is on, every thread keeps count (in SUCK_NANOS) of how long it's spent sucking cards,
+// for search::SearchProfile. it's a thread local so each search can count just its own
+#[cfg(feature = "std")]
+pub static TIME_SUCKS: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    pub static SUCK_NANOS: core::cell::Cell<u64> = const { core::cell::Cell::new(0) };
+}
+
+// turns on the move sanity checks (see Move::validate) in release builds, they're always on in
+// debug
+pub static PARANOID: AtomicBool = AtomicBool::new(false);
+
+impl Board {
+    fn successor(&self, new_board: Board, moov: Move) -> (Board, Move) {
+        if cfg!(debug_assertions) || PARANOID.load(Ordering::Relaxed) {
+            moov.validate(self);
+            assert_eq!(
+                new_board.tableau,
+                new_board.playing_area.iter().flatten().copied().collect(),
+                "tableau out of sync after {:?}",
+                moov
+            );
+            assert_eq!(
+                new_board.top_runs,
+                new_board
+                    .playing_area
+                    .each_ref()
+                    .map(|stack| scan_top_run(stack)),
+                "top_runs out of sync after {:?}",
+                moov
+            );
+        }
+        (new_board, moov)
+    }
+
+    // the only ways cards should come and go from the playing area, so tableau stays right
+    pub fn pop_card(&mut self, pile: usize) -> CardId {
+        let Some(card) = self.playing_area[pile].pop() else {
+            BoardError::EmptyPile {
+                fingerprint: self.fingerprint_hex(),
+                pile,
+            }
+            .raise()
+        };
+        self.tableau.remove(card);
+        self.top_runs[pile] = match self.top_runs[pile] {
+            // the run ended at the card we took, so we don't know where the next one stops
+            0 | 1 => scan_top_run(&self.playing_area[pile]),
+            run => run - 1,
+        };
+        card
+    }
+
+    pub fn push_card(&mut self, pile: usize, card: CardId) {
+        let extends_run = self.playing_area[pile]
+            .last()
+            .map(|top| top.is_next_or_prev(card))
+            .unwrap_or(false);
+        self.top_runs[pile] = if extends_run {
+            self.top_runs[pile] + 1
+        } else {
+            1
+        };
+        self.playing_area[pile].push(card);
+        self.tableau.insert(card);
+    }
+
+    // for fixing up a card that was read wrong
+    pub fn replace_card(&mut self, pile: usize, depth: usize, card: CardId) {
+        let old = core::mem::replace(&mut self.playing_area[pile][depth], card);
+        if !self
+            .playing_area
+            .iter()
+            .flatten()
+            .any(|&other| other == old)
+        {
+            self.tableau.remove(old);
+        }
+        self.tableau.insert(card);
+        self.top_runs[pile] = scan_top_run(&self.playing_area[pile]);
+    }
+
+    // number of cards at the top of the pile that are in order
+    pub fn top_run(&self, pile: usize) -> usize {
+        self.top_runs[pile] as usize
+    }
+
+    // is this card still in one of the piles (i.e. not on the block or a foundation)
+    pub fn is_in_tableau(&self, card: Card) -> bool {
+        self.tableau.contains(card.into())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.playing_area.iter().all(|pile| pile.is_empty())
+    }
+
+    // the game's as good as over: every pile is a single run, so nothing's buried, and there's a
+    // way to clear the rest where every move sucks something
+    pub fn is_trivially_won(&self, rules: &Rules) -> bool {
+        self.auto_finish(rules).is_some()
+    }
+
+    // plays out a trivially won endgame, returning the moves and the (empty) board they end on.
+    // greedy, so it's quick but not always the fewest moves
+    pub fn auto_finish(&self, rules: &Rules) -> Option<(Vec<Move>, Board)> {
+        if !(0..NUM_PLAYING_STACKS).all(|pile| self.top_run(pile) == self.playing_area[pile].len())
+        {
+            return None;
+        }
+        let mut board = self.clone();
+        let mut moves = vec![];
+        // every move takes at least one card off the table, so this always stops
+        while !board.is_done() {
+            let (next_board, moov) = board
+                .next_boards(rules)
+                .into_iter()
+                .filter(|(_, moov)| moov.num_cards_home() > 0)
+                .max_by_key(|(_, moov)| moov.num_cards_home())?;
+            moves.push(moov);
+            board = next_board;
+        }
+        Some((moves, board))
+    }
+
+    // a short summary of the position that's the same from run to run, build to build and machine
+    // to machine (unlike Hash), for cache keys, database keys, and checking that a board we've been
+    // handed is the one we expected. don't change how it's worked out, there are journals and
+    // caches out there keyed by it. it's 128 bit FNV-1a over these bytes:
+    // - each pile from the bottom up, a byte per card (its CardId), then 0xff
+    // - the card on the block (its CardId), or 0xfe if there isn't one
+    // - how many cards are on each foundation: MAJ_LO, MAJ_HI, SWO, WAN, CUP, STA
+    // and the hash is big endian
+    pub fn fingerprint(&self) -> [u8; 16] {
+        const PILE_END: u8 = 0xff;
+        const EMPTY_BLOCK: u8 = 0xfe;
+        const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+        const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+        let foundations = [&self.major_lower_stack, &self.major_higher_stack]
+            .into_iter()
+            .chain(&self.minor_collection_piles)
+            .map(|pile| pile.len() as u8);
+        let bytes = self
+            .playing_area
+            .iter()
+            .flat_map(|pile| pile.iter().map(|card| card.0).chain([PILE_END]))
+            .chain([self
+                .minor_collection_blocked
+                .map_or(EMPTY_BLOCK, |card| card.0)])
+            .chain(foundations);
+        bytes
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u128).wrapping_mul(FNV_PRIME)
+            })
+            .to_be_bytes()
+    }
+
+    // the fingerprint as 32 lowercase hex digits, which is how it's written everywhere
+    pub fn fingerprint_hex(&self) -> String {
+        self.fingerprint()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    // the inverse of fingerprint_hex
+    pub fn parse_fingerprint(hex: &str) -> [u8; 16] {
+        let mut fingerprint = [0; 16];
+        for (i, byte) in fingerprint.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        fingerprint
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut playing_area = [
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        ];
+        // the KEY:CARD lines say where a game's got to (see serialize), everything else is a pile
+        let (positions, piles): (Vec<&str>, Vec<&str>) = s.lines().partition(is_position_line);
+        for (line, stack_to_fill) in piles.iter().zip(playing_area.iter_mut()) {
+            for card in line.trim().split_terminator(',') {
+                if card.trim().is_empty() {
+                    warn(Warning::InputCorrected {
+                        what: format!("skipped an empty card in {:?}", line),
+                    });
+                    continue;
+                }
+                // a card can come with how sure the screen reader was of it, e.g. 10_CUP:0.62, see
+                // confidence.rs. we don't need that here
+                let card = card.trim().split(':').next().unwrap();
+                stack_to_fill.push(Card::parse(card).into());
+            }
+        }
+        let num_extra_lines = piles
+            .iter()
+            .skip(NUM_PLAYING_STACKS)
+            .filter(|line| !line.trim().is_empty())
+            .count();
+        if num_extra_lines > 0 {
+            warn(Warning::InputCorrected {
+                what: format!(
+                    "ignored {} lines past the {} piles",
+                    num_extra_lines, NUM_PLAYING_STACKS
+                ),
+            });
+        }
+        let mut board = Self {
+            major_higher_stack: vec![],
+            major_lower_stack: vec![],
+            minor_collection_piles: [
+                vec![Card::Minor {
+                    suit: Suit::Sword,
+                    value: MinorValue(1),
+                }
+                .into()],
+                vec![Card::Minor {
+                    suit: Suit::Wand,
+                    value: MinorValue(1),
+                }
+                .into()],
+                vec![Card::Minor {
+                    suit: Suit::Cup,
+                    value: MinorValue(1),
+                }
+                .into()],
+                vec![Card::Minor {
+                    suit: Suit::Star,
+                    value: MinorValue(1),
+                }
+                .into()],
+            ],
+            minor_collection_blocked: None,
+            tableau: playing_area.iter().flatten().copied().collect(),
+            top_runs: playing_area.each_ref().map(|stack| scan_top_run(stack)),
+            playing_area,
+        };
+        for line in positions {
+            let (key, card) = line.trim().split_once(POSITION_SEPARATOR).unwrap();
+            let card = CardId::from(Card::parse(card));
+            match (key, card.card()) {
+                (encoding::BLOCK, _) => board.minor_collection_blocked = Some(card),
+                (encoding::MAJOR_LOWER, Card::Major(value)) => {
+                    board.major_lower_stack = (MajorValue::first().0..=value.0)
+                        .map(|value| Card::Major(MajorValue(value)).into())
+                        .collect()
+                }
+                (encoding::MAJOR_HIGHER, Card::Major(value)) => {
+                    board.major_higher_stack = (value.0..=MajorValue::last().0)
+                        .rev()
+                        .map(|value| Card::Major(MajorValue(value)).into())
+                        .collect()
+                }
+                (
+                    suit,
+                    Card::Minor {
+                        suit: card_suit,
+                        value,
+                    },
+                ) if Suit::parse(suit) == card_suit => {
+                    board.minor_collection_piles[card_suit as usize] = (1..=value.0)
+                        .map(|value| {
+                            Card::Minor {
+                                suit: card_suit,
+                                value: MinorValue(value),
+                            }
+                            .into()
+                        })
+                        .collect()
+                }
+                _ => panic!("Invalid position line: {}", line),
+            }
+        }
+        board
+    }
+
+    // every card in the deck that isn't anywhere on this board. a board read off the screen should
+    // have all of them, so anything here probably got misread as some other card
+    pub fn missing_cards(&self) -> CardSet {
+        let present: CardSet = self
+            .playing_area
+            .iter()
+            .chain([&self.major_lower_stack, &self.major_higher_stack])
+            .chain(&self.minor_collection_piles)
+            .flatten()
+            .chain(&self.minor_collection_blocked)
+            .copied()
+            .collect();
+        CardSet::DECK.difference(present)
+    }
+
+    // the inverse of parse: a line per pile, then a KEY:CARD line for the card on the block and the
+    // top of each foundation, if they've moved on from the start of the game
+    pub fn serialize(&self) -> String {
+        let mut lines: Vec<String> = self
+            .playing_area
+            .iter()
+            .map(|pile| {
+                pile.iter()
+                    .map(|card| card.card().serialize())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        if let Some(card) = self.minor_collection_blocked {
+            lines.push(format!(
+                "{}{}{}",
+                encoding::BLOCK,
+                POSITION_SEPARATOR,
+                card.card().serialize()
+            ));
+        }
+        let foundations = [
+            (Foundation::MajorLower, &self.major_lower_stack, 0),
+            (Foundation::MajorHigher, &self.major_higher_stack, 0),
+        ]
+        .into_iter()
+        .chain(
+            self.minor_collection_piles
+                .iter()
+                .zip(Suit::ALL)
+                // the minor piles start with their ace
+                .map(|(pile, suit)| (Foundation::Minor(suit), pile, 1)),
+        );
+        for (foundation, pile, num_at_start) in foundations {
+            if pile.len() > num_at_start {
+                lines.push(format!(
+                    "{}{}{}",
+                    foundation.serialize(),
+                    POSITION_SEPARATOR,
+                    pile.last().unwrap().card().serialize()
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    pub fn num_cards_remaining(&self) -> usize {
+        self.playing_area
+            .iter()
+            .map(|stack| stack.len())
+            .sum::<usize>()
+        // TODO: sometimes commenting this bottom part out makes it so we complete instead of not completing ???
+        + self.minor_collection_blocked.is_some() as usize
+    }
+
+    // how many cards would get sucked as soon as the card on top of them moved anywhere: the card
+    // just under each pile's top, if a foundation's waiting for it. only looks at the pile tops, so
+    // it's cheap enough to work out for every successor
+    pub fn num_sucks_in_reach(&self) -> usize {
+        self.playing_area
+            .iter()
+            .filter_map(|stack| stack.len().checked_sub(2).map(|i| stack[i]))
+            .filter(|&card| match self.foundation_accepting(card) {
+                Some(MoveLocation::MinorFoundation(_)) => self.minor_collection_blocked.is_none(),
+                Some(_) => true,
+                None => false,
+            })
+            .count()
+    }
+
+    pub fn suck_readies_into_receptacles(&mut self) -> Vec<Suck> {
+        self.timed_sucks(true)
+    }
+
+    // the sucks onto the minor foundations only, the majors left where they are for the player to
+    // send home or not, see Rules::optional_major_sucks
+    fn suck_minor_readies(&mut self) -> Vec<Suck> {
+        self.timed_sucks(false)
+    }
+
+    fn timed_sucks(&mut self, majors: bool) -> Vec<Suck> {
+        #[cfg(feature = "std")]
+        if TIME_SUCKS.load(Ordering::Relaxed) {
+            let start = std::time::Instant::now();
+            let sucked_cards = self.suck_readies(majors);
+            SUCK_NANOS.with(|nanos| nanos.set(nanos.get() + start.elapsed().as_nanos() as u64));
+            return sucked_cards;
+        }
+        self.suck_readies(majors)
+    }
+
+    fn suck_readies(&mut self, majors: bool) -> Vec<Suck> {
+        let mut sucked_cards = vec![];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (playing_area_index, last_card) in
+                self.last_card_of_every_stack_mut().into_iter().enumerate()
+            {
+                let Some(last_card) = last_card else {
+                    continue;
+                };
+
+                // see if we can suck into minor collection pile
+                if self.minor_collection_blocked.is_none() {
+                    // only the pile for the card's own suit can ever take it
+                    if let Some(suit) = (0..NUM_SUITS)
+                        .find(|&suit| self.minor_foundation_top(suit).is_next_card(last_card))
+                    {
+                        let card = self.pop_card(playing_area_index);
+                        let minor_collection_pile = &mut self.minor_collection_piles[suit];
+                        minor_collection_pile.push(card);
+                        sucked_cards.push(Suck::new(card.card(), minor_collection_pile.len() - 1));
+                        changed = true;
+                    }
+                }
+
+                // see if we can suck into one of the major collection piles
+                //
+                // a minor can't land on a major, so the only card that loses anywhere to go is the
+                // major one step further along, and that's now next for the same foundation. for
+                // games that let you hold them back anyway, see Rules::optional_major_sucks
+                if !majors {
+                    continue;
+                }
+                if self
+                    .major_lower_stack
+                    .last()
+                    .map(|card| card.is_next_card(last_card))
+                    .unwrap_or(false)
+                    || (self.major_lower_stack.is_empty() && last_card == FIRST_MAJOR)
+                {
+                    let card = self.pop_card(playing_area_index);
+                    self.major_lower_stack.push(card);
+                    sucked_cards.push(Suck {
+                        card: card.card(),
+                        foundation: Foundation::MajorLower,
+                        depth: self.major_lower_stack.len() - 1,
+                    });
+                    changed = true;
+                } else if self
+                    .major_higher_stack
+                    .last()
+                    .map(|card| card.is_prev_card(last_card))
+                    .unwrap_or(false)
+                    || (self.major_higher_stack.is_empty() && last_card == LAST_MAJOR)
+                {
+                    let card = self.pop_card(playing_area_index);
+                    self.major_higher_stack.push(card);
+                    sucked_cards.push(Suck {
+                        card: card.card(),
+                        foundation: Foundation::MajorHigher,
+                        depth: self.major_higher_stack.len() - 1,
+                    });
+                    changed = true;
+                }
+            }
+
+            if let (Some(blocking_card), true) = (self.minor_collection_blocked, majors) {
+                // TODO: slight duplication with above logic
+                if self
+                    .major_lower_stack
+                    .last()
+                    .map(|card| card.is_next_card(blocking_card))
+                    .unwrap_or(false)
+                {
+                    self.major_lower_stack.push(blocking_card);
+                    self.minor_collection_blocked = None;
+                    sucked_cards.push(Suck {
+                        card: blocking_card.card(),
+                        foundation: Foundation::MajorLower,
+                        depth: self.major_lower_stack.len() - 1,
+                    });
+                    changed = true;
+                } else if self
+                    .major_higher_stack
+                    .last()
+                    .map(|card| card.is_prev_card(blocking_card))
+                    .unwrap_or(false)
+                {
+                    self.major_higher_stack.push(blocking_card);
+                    self.minor_collection_blocked = None;
+                    sucked_cards.push(Suck {
+                        card: blocking_card.card(),
+                        foundation: Foundation::MajorHigher,
+                        depth: self.major_higher_stack.len() - 1,
+                    });
+                    changed = true;
+                }
+            }
+        }
+
+        sucked_cards
+    }
+
+    // the sucks that happen by themselves after a move: all of them, unless they're up to the
+    // player (see Rules::optional_sucks), in which case none, or only the minors (see
+    // Rules::optional_major_sucks)
+    pub(crate) fn auto_sucks(&mut self, rules: &Rules) -> Vec<Suck> {
+        if rules.optional_sucks {
+            vec![]
+        } else if rules.optional_major_sucks {
+            self.suck_minor_readies()
+        } else {
+            self.suck_readies_into_receptacles()
+        }
+    }
+
+    // what suck_readies_into_receptacles would do, without doing it. the GUI side uses this to
+    // predict the animations after a human has moved cards around by hand
+    pub fn simulate_sucks(&self) -> SuckReport {
+        let mut board = self.clone();
+        let sucked = board.suck_readies_into_receptacles();
+        SuckReport { sucked, board }
+    }
+
+    pub fn last_card_of_every_stack_mut(&mut self) -> [Option<CardId>; 11] {
+        let mut last_cards = [None; NUM_PLAYING_STACKS];
+        for (stack, last_card) in self.playing_area.iter().zip(last_cards.iter_mut()) {
+            if let Some(card) = stack.last().copied() {
+                *last_card = Some(card);
+            }
+        }
+        last_cards
+    }
+
+    // every minor foundation starts the game with its ace on it, and cards never leave
+    fn minor_foundation_top(&self, suit: usize) -> CardId {
+        match self.minor_collection_piles[suit].last() {
+            Some(&card) => card,
+            None => BoardError::EmptyFoundation {
+                fingerprint: self.fingerprint_hex(),
+                suit: Suit::ALL[suit],
+            }
+            .raise(),
+        }
+    }
+
+    // the foundation that'd take this card by hand right now, if any
+    pub(crate) fn foundation_accepting(&self, card: CardId) -> Option<MoveLocation> {
+        match card.card() {
+            Card::Major(_) => {
+                if self
+                    .major_lower_stack
+                    .last()
+                    .map(|last| last.is_next_card(card))
+                    .unwrap_or(card == FIRST_MAJOR)
+                {
+                    Some(MoveLocation::MajorLower)
+                } else if self
+                    .major_higher_stack
+                    .last()
+                    .map(|last| last.is_prev_card(card))
+                    .unwrap_or(card == LAST_MAJOR)
+                {
+                    Some(MoveLocation::MajorHigher)
+                } else {
+                    None
+                }
+            }
+            Card::Minor { suit, .. } => self
+                .minor_foundation_top(suit as usize)
+                .is_next_card(card)
+                .then_some(MoveLocation::MinorFoundation(suit)),
+        }
+    }
+
+    fn push_onto_foundation(&mut self, card: CardId, foundation: MoveLocation) {
+        match foundation {
+            MoveLocation::MajorLower => self.major_lower_stack.push(card),
+            MoveLocation::MajorHigher => self.major_higher_stack.push(card),
+            MoveLocation::MinorFoundation(suit) => {
+                self.minor_collection_piles[suit as usize].push(card)
+            }
+            _ => unreachable!("{} isn't a foundation", foundation),
+        }
+    }
+
+    // plays the move from one place to another on this board, with the depths and the sucks worked
+    // out for this board. None if there's no such move here
+    pub fn play(
+        &self,
+        from: MoveLocation,
+        to: MoveLocation,
+        rules: &Rules,
+    ) -> Option<(Self, Move)> {
+        self.next_boards(rules)
+            .into_iter()
+            .find(|(_, moov)| moov.from.same_place(&from) && moov.to.same_place(&to))
+    }
+
+    // every move from here. pruning lines that aren't going anywhere is up to the search, see
+    // search::is_stalled
+    pub fn next_boards(&self, rules: &Rules) -> Vec<(Self, Move)> {
+        let mut boards = vec![];
+
+        for (src_index, src_stack) in self.playing_area.iter().enumerate() {
+            let Some(&src_card) = src_stack.last() else {
+                continue;
+            };
+
+            if self.minor_collection_blocked.is_none() && !rules.no_block {
+                // // filters out a useless move: there is never any reason to block the minor pile
+                // // from a stack that only has one card
+                if src_stack.len() == 1 {
+                    continue;
+                }
+
+                let mut new_board = self.clone();
+                let card = new_board.pop_card(src_index);
+                new_board.minor_collection_blocked = Some(card);
+                let sucked_cards = new_board.auto_sucks(rules);
+                let moov = Move {
+                    from: MoveLocation::PlayingArea {
+                        pile: src_index,
+                        depth: self.playing_area[src_index].len() - 1,
+                    },
+                    to: MoveLocation::BlockMinorPiles,
+                    card: card.card(),
+                    sucks: sucked_cards,
+                };
+                boards.push(self.successor(new_board, moov));
+            }
+
+            for (dst_index, dst_stack) in self.playing_area.iter().enumerate() {
+                // moving a card to its own stack isn't a move
+                if src_index == dst_index {
+                    continue;
+                }
+                // filters out a non-progress move: moving a card from a 1-stack to another 1-stack
+                if src_stack.len() == 1 && dst_stack.is_empty() {
+                    continue;
+                }
+                if dst_stack
+                    .last()
+                    .is_none_or(|top| top.is_next_or_prev(src_card))
+                {
+                    let mut new_board = self.clone();
+                    let src_card = new_board.pop_card(src_index);
+                    new_board.push_card(dst_index, src_card);
+                    let sucked_cards = new_board.auto_sucks(rules);
+                    let moov = Move {
+                        from: MoveLocation::PlayingArea {
+                            pile: src_index,
+                            depth: self.playing_area[src_index].len() - 1,
+                        },
+                        to: MoveLocation::PlayingArea {
+                            pile: dst_index,
+                            depth: self.playing_area[dst_index].len(),
+                        },
+                        card: src_card.card(),
+                        sucks: sucked_cards,
+                    };
+                    boards.push(self.successor(new_board, moov));
+                }
+            }
+        }
+
+        // unblock the minor collection piles
+        if let Some(card) = self.minor_collection_blocked {
+            // TODO: this is duplicated from above, we could consolidate if need be
+            for (dst_index, dst_stack) in self.playing_area.iter().enumerate() {
+                if dst_stack.last().is_none_or(|top| top.is_next_or_prev(card)) {
+                    let mut new_board = self.clone();
+                    new_board.minor_collection_blocked = None;
+                    new_board.push_card(dst_index, card);
+                    let sucked_cards = new_board.auto_sucks(rules);
+                    let moov = Move {
+                        from: MoveLocation::BlockMinorPiles,
+                        to: MoveLocation::PlayingArea {
+                            pile: dst_index,
+                            depth: self.playing_area[dst_index].len(),
+                        },
+                        card: card.card(),
+                        sucks: sucked_cards,
+                    };
+                    boards.push(self.successor(new_board, moov));
+                }
+            }
+        }
+
+        if rules.explicit_foundation_moves || rules.optional_sucks || rules.optional_major_sucks {
+            let tops = self
+                .playing_area
+                .iter()
+                .enumerate()
+                .filter_map(|(pile, stack)| {
+                    let from = MoveLocation::PlayingArea {
+                        pile,
+                        depth: stack.len().checked_sub(1)?,
+                    };
+                    Some((from, *stack.last()?))
+                });
+            let block = self
+                .minor_collection_blocked
+                .map(|card| (MoveLocation::BlockMinorPiles, card));
+            for (from, card) in tops.chain(block) {
+                let Some(to) = self.foundation_accepting(card) else {
+                    continue;
+                };
+                // the block covers the minor piles, so only the card on it can go on them by hand
+                if matches!(to, MoveLocation::MinorFoundation(_))
+                    && self.minor_collection_blocked.is_some()
+                    && from != MoveLocation::BlockMinorPiles
+                {
+                    continue;
+                }
+                // an optional suck is still a suck, which only takes the cards it would have taken
+                // by itself
+                if rules.optional_sucks
+                    && !rules.explicit_foundation_moves
+                    && matches!(to, MoveLocation::MinorFoundation(_))
+                    && (from == MoveLocation::BlockMinorPiles
+                        || self.minor_collection_blocked.is_some())
+                {
+                    continue;
+                }
+                // the minors still go by themselves
+                if !rules.explicit_foundation_moves
+                    && !rules.optional_sucks
+                    && matches!(to, MoveLocation::MinorFoundation(_))
+                {
+                    continue;
+                }
+                let mut new_board = self.clone();
+                match from {
+                    MoveLocation::PlayingArea { pile, .. } => {
+                        new_board.pop_card(pile);
+                    }
+                    _ => new_board.minor_collection_blocked = None,
+                }
+                new_board.push_onto_foundation(card, to);
+                let sucked_cards = new_board.auto_sucks(rules);
+                let moov = Move {
+                    from,
+                    to,
+                    card: card.card(),
+                    sucks: sucked_cards,
+                };
+                boards.push(self.successor(new_board, moov));
+            }
+        }
+
+        boards
+    }
+}
+
+impl MoveLocation {
+    // the same place, not counting how deep into the pile. a pile's depth depends on what's been
+    // played before, the pile itself doesn't
+    pub fn same_place(&self, other: &MoveLocation) -> bool {
+        match (self, other) {
+            (
+                MoveLocation::PlayingArea { pile, .. },
+                MoveLocation::PlayingArea {
+                    pile: other_pile, ..
+                },
+            ) => pile == other_pile,
+            _ => self == other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SuckReport {
+    // in the order they'd fly off to their collection piles
+    pub sucked: Vec<Suck>,
+    // what the board would look like afterwards
+    pub board: Board,
+}
+
+// the moves to play, in order, from the starting board to an empty playing area
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Solution {
+    pub moves: Vec<Move>,
+}
+
+impl Solution {
+    pub fn from_path(path: &[(Board, Option<Move>)]) -> Self {
+        Self {
+            moves: path.iter().filter_map(|(_, moov)| moov.clone()).collect(),
+        }
+    }
+}
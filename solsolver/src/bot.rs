@@ -0,0 +1,271 @@
+use crate::board::{Board, Move};
+use crate::card::{Card, CardId, Suit};
+use crate::rules::Rules;
+use crate::search::{self, Heuristic};
+use crate::share::ShareCode;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+// answers the "is this deal winnable?" posts in the community's Discord. talking to Discord itself
+// takes a gateway client with TLS, which this crate doesn't have, so the bot is in two halves: a
+// small relay (any Discord library will do) that passes each post in the channel to `solsolver
+// bot` and posts back what it says, and this, which works out the answer. over stdin/stdout, one
+// message per line:
+//
+// relay to us:
+//   MESSAGE <id>       followed by the post's text, then END. <id> is letters and digits that
+//                      the relay wants back with the reply (the Discord message id, say). it
+//                      names the picture's file, so anything else is turned away
+//   QUIT
+// us to relay:
+//   REPLY <id>         followed by the text to post, then END. a post that doesn't have a deal in
+//                      it gets no REPLY
+//   IMAGE <id> <path>  an SVG of the deal, to attach to the reply. comes before its REPLY
+//
+// a post has a deal in it if it's in the usual format (see Board::parse), in a ``` block or not.
+// with a line `code: <share code>` as well, we check the code instead of solving (see share.rs)
+
+// what Discord takes in one message
+pub const MAX_REPLY_CHARS: usize = 2000;
+
+const SHARE_PREFIX: &str = "code:";
+
+pub struct Reply {
+    pub text: String,
+    pub board: Board,
+}
+
+// the deal's text and the share code, if the post has them. None if there's no deal
+pub fn read_post(post: &str) -> Option<(String, Option<String>)> {
+    // inside the code block if there is one, so the chat around it doesn't get in the way
+    let body = match post.split("```").nth(1) {
+        Some(block) => block.trim_start_matches(|c: char| c.is_alphanumeric()),
+        None => post,
+    };
+    let code = post
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(SHARE_PREFIX))
+        .map(|code| code.trim().to_string());
+    let lines: Vec<&str> = body
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with(SHARE_PREFIX))
+        .collect();
+    // the piles run from the first line with a card on it to the last, with the empty ones
+    // in between as blank lines
+    let is_cards = |line: &&str| line.contains('_') && line.contains(',');
+    let first = lines.iter().position(is_cards)?;
+    let last = lines.iter().rposition(is_cards)?;
+    Some((lines[first..=last].join("\n") + "\n", code))
+}
+
+fn moves_text(moves: &[Move], room: usize) -> String {
+    let mut text = String::new();
+    for (i, moov) in moves.iter().enumerate() {
+        let line = format!("{}. {}\n", i + 1, moov);
+        let rest = format!("... and {} more\n", moves.len() - i);
+        if text.len() + line.len() + rest.len() > room {
+            text.push_str(&rest);
+            break;
+        }
+        text.push_str(&line);
+    }
+    text
+}
+
+fn cards_left_after(board: &Board, moves: &[Move], rules: &Rules) -> usize {
+    let mut board = board.clone();
+    for moov in moves {
+        board = board.play(moov.from, moov.to, rules).unwrap().0;
+    }
+    board.num_cards_remaining()
+}
+
+fn answer(deal: &str, code: Option<&str>, rules: &Rules, budget_ms: u64) -> Reply {
+    let mut board = Board::parse(deal);
+    board.suck_readies_into_receptacles();
+    let text = match code {
+        Some(code) => match ShareCode::parse(code).replay(&board) {
+            Some(solution) => {
+                let header = format!("That code wins in {} moves:\n", solution.moves.len());
+                let room = MAX_REPLY_CHARS - header.len();
+                header + &moves_text(&solution.moves, room)
+            }
+            None => "That code isn't for this deal.".to_string(),
+        },
+        None => match search::anytime(&board, Heuristic::CardsRemaining, rules, budget_ms) {
+            Ok(solution) => {
+                let code = ShareCode::new(&board, &solution, rules).serialize();
+                let header = format!(
+                    "Winnable, in {} moves. {} {}\n",
+                    solution.moves.len(),
+                    SHARE_PREFIX,
+                    code
+                );
+                let room = MAX_REPLY_CHARS - header.len();
+                header + &moves_text(&solution.moves, room)
+            }
+            Err(best_line) => format!(
+                "No win found in {}s. The best line I found gets down to {} cards left, which \
+                 doesn't mean it can't be done.",
+                budget_ms / 1000,
+                cards_left_after(&board, &best_line, rules)
+            ),
+        },
+    };
+    Reply { text, board }
+}
+
+// the reply to a post, if it has a deal in it. a deal we can't make sense of gets a reply saying so
+// rather than taking the bot down
+pub fn respond(post: &str, rules: &Rules, budget_ms: u64) -> Option<Result<Reply, String>> {
+    let (deal, code) = read_post(post)?;
+    let answered = panic::catch_unwind(AssertUnwindSafe(|| {
+        answer(&deal, code.as_deref(), rules, budget_ms)
+    }));
+    Some(answered.map_err(|_| "I couldn't read that deal.".to_string()))
+}
+
+const CARD_WIDTH: usize = 56;
+const CARD_HEIGHT: usize = 24;
+const GAP: usize = 8;
+
+fn fill(card: Card) -> &'static str {
+    match card {
+        Card::Major(_) => "#c9b3e6",
+        Card::Minor { suit, .. } => match suit {
+            Suit::Sword => "#b3dde6",
+            Suit::Wand => "#bfe6b3",
+            Suit::Cup => "#e6b3b3",
+            Suit::Star => "#e6dcb3",
+        },
+    }
+}
+
+fn card_svg(card: CardId, x: usize, y: usize) -> String {
+    let card = card.card();
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" stroke=\"#333\"/>\
+         <text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"14\">{}</text>\n",
+        x,
+        y,
+        CARD_WIDTH,
+        CARD_HEIGHT,
+        fill(card),
+        x + 6,
+        y + 17,
+        card
+    )
+}
+
+// the board as a picture: the foundations and the block along the top (just their top cards),
+// then the piles, bottom card at the top like on screen
+pub fn render_svg(board: &Board) -> String {
+    let column = |i: usize| GAP + i * (CARD_WIDTH + GAP);
+    let mut cards = String::new();
+    let tops = [
+        board.major_lower_stack.last(),
+        board.major_higher_stack.last(),
+        board.minor_collection_blocked.as_ref(),
+    ]
+    .into_iter()
+    .chain(board.minor_collection_piles.iter().map(|pile| pile.last()));
+    for (i, top) in tops.enumerate() {
+        if let Some(&card) = top {
+            cards.push_str(&card_svg(card, column(i), GAP));
+        }
+    }
+    let piles_y = 2 * GAP + CARD_HEIGHT + GAP;
+    for (i, pile) in board.playing_area.iter().enumerate() {
+        for (depth, &card) in pile.iter().enumerate() {
+            cards.push_str(&card_svg(card, column(i), piles_y + depth * CARD_HEIGHT));
+        }
+    }
+    let tallest = board.playing_area.iter().map(Vec::len).max().unwrap_or(0);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#2d5a3d\"/>\n{}</svg>\n",
+        column(board.playing_area.len()),
+        piles_y + tallest * CARD_HEIGHT + GAP,
+        cards
+    )
+}
+
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+// the post after a MESSAGE line, up to its END. None if the relay's gone
+fn read_post_lines(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut post = String::new();
+    loop {
+        match read_line(input)? {
+            Some(line) if line == "END" => return Ok(Some(post)),
+            Some(line) => {
+                post.push_str(&line);
+                post.push('\n');
+            }
+            None => return Ok(None),
+        }
+    }
+}
+
+// answers posts until the relay says QUIT or goes away. the pictures go in image_dir if we've got
+// one
+pub fn run(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    rules: &Rules,
+    budget_ms: u64,
+    image_dir: Option<&Path>,
+) -> io::Result<()> {
+    while let Some(line) = read_line(input)? {
+        let id = match line.split_once(' ') {
+            Some(("MESSAGE", id)) => {
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("message ids are letters and digits, got {:?}", id),
+                    ));
+                }
+                id.to_string()
+            }
+            _ if line == "QUIT" => return Ok(()),
+            _ if line.is_empty() => continue,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected MESSAGE, got {:?}", line),
+                ))
+            }
+        };
+        let Some(post) = read_post_lines(input)? else {
+            return Ok(());
+        };
+        let text = match respond(&post, rules, budget_ms) {
+            None => continue,
+            Some(Ok(reply)) => {
+                if let Some(dir) = image_dir {
+                    let path = dir.join(format!("{}.svg", id));
+                    fs::write(&path, render_svg(&reply.board))?;
+                    writeln!(output, "IMAGE {} {}", id, path.display())?;
+                }
+                reply.text
+            }
+            Some(Err(why)) => why,
+        };
+        writeln!(output, "REPLY {}", id)?;
+        for line in text.lines() {
+            writeln!(output, "{}", line)?;
+        }
+        writeln!(output, "END")?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::provenance;
+use crate::unknown;
+use crate::warnings;
+use std::any::Any;
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// when something goes badly wrong (a panic, running low on memory, running out of time), everything
+// needed to look into it in one file that can be attached to an issue: the deal, how we were run,
+// the seed, how things were going, and the last few warnings. it's plain text, with a --- line
+// before each part:
+//   reason: ...
+//   version: see provenance.rs
+//   args: ...
+//   seed: ...
+//   --- deal
+//   --- stats
+//   --- log
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+// the deal we're working on, and the latest stats line, as whoever has them hands them over
+static DEAL: Mutex<Option<String>> = Mutex::new(None);
+static STATS: Mutex<Option<String>> = Mutex::new(None);
+
+std::thread_local! {
+    // set while contain's running something, which writes its own bundle if that panics
+    static CONTAINED: Cell<bool> = const { Cell::new(false) };
+}
+
+// writes a bundle into dir whenever something goes wrong from now on, for --bug-report-dir. panics
+// still go to stderr like they always did
+pub fn enable(dir: PathBuf) {
+    if DIR.set(dir).is_err() {
+        panic!("bug reports are already on");
+    }
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if !CONTAINED.with(Cell::get) {
+            report(&format!("panic: {}", info));
+        }
+    }));
+}
+
+// runs f, and if it panics, writes a bundle saying what (a description of f) failed and carries
+// on. the panic message comes back as the error
+pub fn contain<T>(what: &str, f: impl FnOnce() -> T) -> Result<T, String> {
+    let was_contained = CONTAINED.with(|contained| contained.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CONTAINED.with(|contained| contained.set(was_contained));
+    result.map_err(|payload| {
+        let why = panic_message(payload.as_ref());
+        report(&format!("{} failed: internal error: {}", what, why));
+        why
+    })
+}
+
+// what a panic said, if it said it with a string
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "a panic that didn't say why".to_string())
+}
+
+pub fn note_deal(deal: &str) {
+    *DEAL.lock().unwrap_or_else(|e| e.into_inner()) = Some(deal.to_string());
+}
+
+pub fn note_stats(stats: &str) {
+    *STATS.lock().unwrap_or_else(|e| e.into_inner()) = Some(stats.to_string());
+}
+
+// writes a bundle, if they're on. we're usually already on the way down when this gets called, so
+// it doesn't fail, it just says on stderr if it couldn't write one
+pub fn report(reason: &str) {
+    let Some(dir) = DIR.get() else {
+        return;
+    };
+    match write(dir, reason) {
+        Ok(path) => eprintln!("wrote a bug report to {}", path.display()),
+        Err(e) => eprintln!("couldn't write a bug report to {}: {}", dir.display(), e),
+    }
+}
+
+fn write(dir: &PathBuf, reason: &str) -> io::Result<PathBuf> {
+    // a panic while holding one of these still leaves what we want in it
+    let deal = DEAL.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let args: Vec<String> = std::env::args().collect();
+    let mut s = format!(
+        "reason: {}\nversion: {}\nargs: {}\nseed: {}\n",
+        reason.replace('\n', " "),
+        provenance::version(),
+        args.join(" "),
+        unknown::seed()
+    );
+    s.push_str("--- deal\n");
+    s.push_str(deal.as_deref().unwrap_or(""));
+    s.push_str("--- stats\n");
+    if let Some(stats) = stats {
+        s.push_str(&stats);
+        s.push('\n');
+    }
+    s.push_str("--- log\n");
+    for line in warnings::recent() {
+        s.push_str(&line);
+        s.push('\n');
+    }
+
+    fs::create_dir_all(dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let path = dir.join(format!("bug-{}.txt", millis));
+    fs::write(&path, s)?;
+    Ok(path)
+}
@@ -0,0 +1,333 @@
+use crate::encoding::{self, CARD_SEPARATOR};
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Debug, Display, Formatter};
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// 1 (the ace) to 13 (the king), written as in encoding::MINOR_VALUES
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MinorValue(pub u8);
+
+impl Debug for MinorValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match encoding::minor_value_name(self.0) {
+            Some(name) => f.write_str(name),
+            None => Debug::fmt(&self.0, f),
+        }
+    }
+}
+
+impl Display for MinorValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl MinorValue {
+    pub fn parse(s: &str) -> Self {
+        match encoding::minor_value_from_name(s) {
+            Some(value) => MinorValue(value),
+            None => panic!("Invalid minor value: {}", s),
+        }
+    }
+}
+
+// from 0 to 21
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MajorValue(pub u8);
+
+impl MajorValue {
+    pub fn parse(s: &str) -> Self {
+        MajorValue(s.parse().unwrap())
+    }
+
+    pub const fn first() -> Self {
+        MajorValue(0)
+    }
+
+    pub const fn last() -> Self {
+        MajorValue(21)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(usize)]
+pub enum Suit {
+    Sword,
+    Wand,
+    Cup,
+    Star,
+}
+
+pub const NUM_SUITS: usize = core::mem::variant_count::<Suit>();
+
+// draws the suits as Suit::letter rather than emoji everywhere they're displayed, which includes
+// the human readable end of each line on the wire. for logs and consoles that can't take emoji
+pub static ASCII: AtomicBool = AtomicBool::new(false);
+
+impl Display for Suit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if ASCII.load(Ordering::Relaxed) {
+            return f.write_str(self.letter());
+        }
+        f.write_str(encoding::suit_names(*self).emoji)
+    }
+}
+
+impl Suit {
+    // in the same order as the minor collection piles
+    pub const ALL: [Suit; NUM_SUITS] = [Suit::Sword, Suit::Wand, Suit::Cup, Suit::Star];
+
+    // S, W, C and T, for Sword, Wand, Cup and sTar
+    pub fn letter(&self) -> &'static str {
+        encoding::suit_names(*self).letter
+    }
+
+    pub fn serialize(&self) -> &'static str {
+        encoding::suit_names(*self).wire
+    }
+
+    pub fn parse(s: &str) -> Self {
+        encoding::suit_from_wire(s).unwrap_or_else(|| panic!("Invalid suit: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Card {
+    Major(MajorValue),
+    Minor { suit: Suit, value: MinorValue },
+}
+
+impl Display for Card {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Card::Major(value) => Display::fmt(&value.0, f),
+            Card::Minor { suit, value } => {
+                Display::fmt(value, f)?;
+                Display::fmt(suit, f)
+            }
+        }
+    }
+}
+
+impl Card {
+    // the inverse of parse, e.g. 10_MAJ or Q_CUP
+    pub fn serialize(&self) -> String {
+        match self {
+            Card::Major(value) => format!("{}{}{}", value.0, CARD_SEPARATOR, encoding::MAJOR),
+            Card::Minor { suit, value } => {
+                format!("{}{}{}", value, CARD_SEPARATOR, suit.serialize())
+            }
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut split = s.split(CARD_SEPARATOR);
+        let value = split.next().unwrap();
+        let suit = split.next().unwrap();
+        if suit == encoding::MAJOR {
+            Card::Major(MajorValue::parse(value))
+        } else {
+            Card::Minor {
+                suit: Suit::parse(suit),
+                value: MinorValue::parse(value),
+            }
+        }
+    }
+
+    // the inverse of Display, with the suits drawn either way
+    pub fn parse_displayed(s: &str) -> Option<Self> {
+        if let Ok(value) = s.parse() {
+            return Some(Card::Major(MajorValue(value)));
+        }
+        let (value, suit) = encoding::split_displayed_suit(s)?;
+        Some(Card::Minor {
+            suit,
+            value: MinorValue(encoding::minor_value_from_name(value)?),
+        })
+    }
+
+    pub fn is_next_card(self, next_card: Self) -> bool {
+        match (self, next_card) {
+            (Card::Major(this_value), Card::Major(next_value)) => this_value.0 + 1 == next_value.0,
+            (
+                Card::Minor {
+                    suit,
+                    value: this_value,
+                },
+                Card::Minor {
+                    suit: next_suit,
+                    value: next_value,
+                },
+            ) => suit == next_suit && this_value.0 + 1 == next_value.0,
+            _ => false,
+        }
+    }
+
+    pub fn is_prev_card(self, prev_card: Self) -> bool {
+        match (self, prev_card) {
+            (Card::Major(MajorValue(this_val)), Card::Major(MajorValue(prev_val))) => {
+                this_val == prev_val + 1
+            }
+            (
+                Card::Minor {
+                    suit,
+                    value: this_val,
+                },
+                Card::Minor {
+                    suit: prev_suit,
+                    value: prev_val,
+                },
+            ) => suit == prev_suit && this_val.0 == prev_val.0 + 1,
+            _ => false,
+        }
+    }
+
+    pub fn is_next_or_prev(self, other: Self) -> bool {
+        self.is_next_card(other) || self.is_prev_card(other)
+    }
+}
+
+const NUM_MAJORS: usize = MajorValue::last().0 as usize + 1;
+const NUM_MINOR_VALUES: usize = 13;
+
+// every card in the deck (aces included, even though they start on the collection piles) as a
+// single byte: the majors are 0 to 21, then the minors suit by suit, ace to king. boards are
+// mostly piles of cards, so this keeps them small and quick to hash and compare
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CardId(pub u8);
+
+pub const NUM_CARD_IDS: usize = NUM_MAJORS + NUM_SUITS * NUM_MINOR_VALUES;
+
+const CARDS: [Card; NUM_CARD_IDS] = {
+    let mut cards = [Card::Major(MajorValue::first()); NUM_CARD_IDS];
+    let mut id = 0;
+    while id < NUM_CARD_IDS {
+        cards[id] = if id < NUM_MAJORS {
+            Card::Major(MajorValue(id as u8))
+        } else {
+            Card::Minor {
+                suit: Suit::ALL[(id - NUM_MAJORS) / NUM_MINOR_VALUES],
+                value: MinorValue(((id - NUM_MAJORS) % NUM_MINOR_VALUES) as u8 + 1),
+            }
+        };
+        id += 1;
+    }
+    cards
+};
+
+impl CardId {
+    pub const fn from_card(card: Card) -> Self {
+        match card {
+            Card::Major(value) => CardId(value.0),
+            Card::Minor { suit, value } => {
+                CardId((NUM_MAJORS + suit as usize * NUM_MINOR_VALUES + value.0 as usize - 1) as u8)
+            }
+        }
+    }
+
+    pub const fn card(self) -> Card {
+        CARDS[self.0 as usize]
+    }
+
+    // the majors are one family, and each minor suit is another
+    pub const fn family(self) -> usize {
+        if (self.0 as usize) < NUM_MAJORS {
+            NUM_SUITS
+        } else {
+            (self.0 as usize - NUM_MAJORS) / NUM_MINOR_VALUES
+        }
+    }
+
+    pub const fn is_major(self) -> bool {
+        (self.0 as usize) < NUM_MAJORS
+    }
+
+    // ids run in order within a family, so these are the same as Card's, just cheaper
+    pub fn is_next_card(self, next_card: Self) -> bool {
+        self.0 + 1 == next_card.0 && self.family() == next_card.family()
+    }
+
+    pub fn is_prev_card(self, prev_card: Self) -> bool {
+        prev_card.is_next_card(self)
+    }
+
+    pub fn is_next_or_prev(self, other: Self) -> bool {
+        self.is_next_card(other) || self.is_prev_card(other)
+    }
+}
+
+impl From<Card> for CardId {
+    fn from(card: Card) -> Self {
+        CardId::from_card(card)
+    }
+}
+
+impl Debug for CardId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(&self.card(), f)
+    }
+}
+
+impl Display for CardId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.card(), f)
+    }
+}
+
+// a set of cards, one bit per CardId
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CardSet(pub u128);
+
+impl CardSet {
+    // all 74 cards
+    pub const DECK: CardSet = CardSet((1 << NUM_CARD_IDS) - 1);
+
+    pub fn contains(self, card: CardId) -> bool {
+        self.0 & (1 << card.0) != 0
+    }
+
+    pub fn insert(&mut self, card: CardId) {
+        self.0 |= 1 << card.0;
+    }
+
+    pub fn remove(&mut self, card: CardId) {
+        self.0 &= !(1 << card.0);
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = CardId> {
+        (0..NUM_CARD_IDS as u8)
+            .map(CardId)
+            .filter(move |&card| self.contains(card))
+    }
+}
+
+impl FromIterator<CardId> for CardSet {
+    fn from_iter<I: IntoIterator<Item = CardId>>(cards: I) -> Self {
+        let mut set = CardSet::default();
+        for card in cards {
+            set.insert(card);
+        }
+        set
+    }
+}
@@ -0,0 +1,52 @@
+use crate::provenance;
+
+// for --certificate: what an exact search knows when it finds a solution, written out so that
+// someone else can check a claim of "this is the shortest there is" instead of taking our word
+// for it. exact means moves cost 1 and the heuristic's the relaxed solution's length, which never
+// overestimates (see relaxed.rs), with no pruning, no --epsilon, and the closed set keeping whole
+// boards
+//
+// A* only takes a board off the open list when nothing left on it has a lower g + h, and with h
+// never over, some board on every shortest line is always on the open list with g + h no more than
+// that line's length. so when the search stops at a finished board, its g + h is a lower bound on
+// every solution there is. a solution that long is the shortest. the search is deterministic, so
+// the same build (the version line) run on the same deal should expand and keep the same number of
+// boards, e.g.
+//
+//   certificate v3.2-14-gab12cd3 rules=1 protocol=3 features=std,parallel,memcap
+//   board=0f3a...
+//   assumes heuristic=relaxed move_cost=1 pruning=none epsilon=0 closed=boards
+//   expanded=120483 closed=388812
+//   shortest>=61 moves=61 optimal=yes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    // Board::fingerprint_hex of the board the search started from
+    pub fingerprint: String,
+    pub num_expanded: usize,
+    // boards in the closed set when the search stopped
+    pub num_closed: usize,
+    // g + h of the board the search stopped at
+    pub lower_bound: usize,
+}
+
+impl Certificate {
+    // num_moves is the solution's length, which can be more than the lower bound if the search
+    // stopped somewhere the tablebase finished off
+    pub fn serialize(&self, num_moves: usize) -> String {
+        format!(
+            "certificate {}\nboard={}\nassumes heuristic=relaxed move_cost=1 pruning=none \
+             epsilon=0 closed=boards\nexpanded={} closed={}\nshortest>={} moves={} optimal={}\n",
+            provenance::version(),
+            self.fingerprint,
+            self.num_expanded,
+            self.num_closed,
+            self.lower_bound,
+            num_moves,
+            if num_moves <= self.lower_bound {
+                "yes"
+            } else {
+                "unknown"
+            }
+        )
+    }
+}
@@ -0,0 +1,217 @@
+use crate::board::Board;
+use crate::card::{Card, CardId, Suit, NUM_SUITS};
+use crate::warnings::{warn, Warning};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// the boards a search has already been to. normally that's the boards themselves, but a board's a
+// few hundred bytes and a 16 byte Board::fingerprint is far less, so with HASH_ONLY we keep just
+// the fingerprint. the search's nodes still hold every board it reaches (it needs them to expand
+// and to play back the line to one), so this only saves the closed set's copy of each, roughly half
+// of what a search takes. the catch is that two boards with the same fingerprint look like one,
+// and the second gets skipped as already seen, which could lose us the only way through
+//
+// how likely that is is what CHECK_COLLISIONS is for: alongside each fingerprint we keep a checksum
+// from a different hash (std's SipHash), and a board whose fingerprint's there but whose checksum
+// isn't is a collision. it's counted and warned about (once per board), and kept in a short list
+// of its own next to the first board with that fingerprint, so both of them are still looked up
+// right. with both hashes at once it's still possible to miss one, but it'd take two independent
+// collisions on the same pair
+//
+// nothing in the rules cares which suit is which, so a board with the suits swapped round (on the
+// piles, the block and the foundations alike) plays out the same as the original. with
+// CANONICAL_SUITS a board's looked up as its canonical relabeling (see canonical_suits), and a
+// board that's only a relabeling of one we've seen counts as seen. the search still plays the
+// board it actually got to, so the moves it comes back with are real ones
+
+pub static HASH_ONLY: AtomicBool = AtomicBool::new(false);
+pub static CHECK_COLLISIONS: AtomicBool = AtomicBool::new(false);
+pub static CANONICAL_SUITS: AtomicBool = AtomicBool::new(false);
+
+// every collision CHECK_COLLISIONS has caught, over every search so far
+static NUM_COLLISIONS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn num_collisions() -> usize {
+    NUM_COLLISIONS.load(Ordering::Relaxed)
+}
+
+fn checksum(board: &Board) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    board.hash(&mut hasher);
+    hasher.finish()
+}
+
+// the board with its suits renamed in the order they first turn up, going through the piles from
+// the bottom up and then the block, so that every relabeling of a board comes out the same. a suit
+// that's nowhere to be seen is all on its foundation (unless some cards are missing), and those go
+// last, in order of how many cards they have home
+pub fn canonical_suits(board: &Board) -> Board {
+    let mut order: Vec<Suit> = Vec::with_capacity(NUM_SUITS);
+    for card in board
+        .playing_area
+        .iter()
+        .flatten()
+        .chain(&board.minor_collection_blocked)
+    {
+        if let Card::Minor { suit, .. } = card.card() {
+            if !order.contains(&suit) {
+                order.push(suit);
+            }
+        }
+    }
+    let mut unseen: Vec<Suit> = Suit::ALL
+        .into_iter()
+        .filter(|suit| !order.contains(suit))
+        .collect();
+    unseen.sort_by_key(|&suit| board.minor_collection_piles[suit as usize].len());
+    order.extend(unseen);
+    // what each suit becomes
+    let mut renamed = Suit::ALL;
+    for (i, &suit) in order.iter().enumerate() {
+        renamed[suit as usize] = Suit::ALL[i];
+    }
+    let rename = |card: &CardId| match card.card() {
+        Card::Minor { suit, value } => CardId::from_card(Card::Minor {
+            suit: renamed[suit as usize],
+            value,
+        }),
+        Card::Major(_) => *card,
+    };
+    let playing_area = board
+        .playing_area
+        .each_ref()
+        .map(|pile| pile.iter().map(rename).collect::<Vec<_>>());
+    Board {
+        major_lower_stack: board.major_lower_stack.clone(),
+        major_higher_stack: board.major_higher_stack.clone(),
+        minor_collection_piles: core::array::from_fn(|i| {
+            board.minor_collection_piles[order[i] as usize]
+                .iter()
+                .map(rename)
+                .collect()
+        }),
+        minor_collection_blocked: board.minor_collection_blocked.as_ref().map(rename),
+        tableau: playing_area.iter().flatten().copied().collect(),
+        // whether one card's next to another doesn't depend on what its suit's called
+        top_runs: board.top_runs,
+        playing_area,
+    }
+}
+
+// a map from board to whatever the search keeps about it, see above
+pub(crate) enum Closed<V> {
+    Boards(HashMap<Board, V>),
+    // the checksum's 0 if we're not checking
+    Fingerprints {
+        entries: HashMap<[u8; 16], (u64, V)>,
+        // when we are, the boards after the first with each fingerprint, by checksum
+        collided: HashMap<[u8; 16], Vec<(u64, V)>>,
+        check: bool,
+    },
+}
+
+// what a board's looked up by
+fn key(board: &Board) -> Cow<'_, Board> {
+    if CANONICAL_SUITS.load(Ordering::Relaxed) {
+        Cow::Owned(canonical_suits(board))
+    } else {
+        Cow::Borrowed(board)
+    }
+}
+
+impl<V> Closed<V> {
+    pub(crate) fn new() -> Self {
+        if !HASH_ONLY.load(Ordering::Relaxed) {
+            return Closed::Boards(HashMap::new());
+        }
+        Closed::Fingerprints {
+            entries: HashMap::new(),
+            collided: HashMap::new(),
+            check: CHECK_COLLISIONS.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn get(&self, board: &Board) -> Option<&V> {
+        let board = &*key(board);
+        match self {
+            Closed::Boards(boards) => boards.get(board),
+            Closed::Fingerprints {
+                entries,
+                collided,
+                check,
+            } => {
+                let fingerprint = board.fingerprint();
+                let (sum, value) = entries.get(&fingerprint)?;
+                if !*check {
+                    return Some(value);
+                }
+                let sum_wanted = checksum(board);
+                if *sum == sum_wanted {
+                    return Some(value);
+                }
+                collided
+                    .get(&fingerprint)?
+                    .iter()
+                    .find(|(sum, _)| *sum == sum_wanted)
+                    .map(|(_, value)| value)
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Closed::Boards(boards) => boards.len(),
+            Closed::Fingerprints {
+                entries, collided, ..
+            } => entries.len() + collided.values().map(Vec::len).sum::<usize>(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, board: Board, value: V) {
+        let board = match key(&board) {
+            Cow::Owned(canonical) => canonical,
+            Cow::Borrowed(_) => board,
+        };
+        match self {
+            Closed::Boards(boards) => {
+                boards.insert(board, value);
+            }
+            Closed::Fingerprints {
+                entries,
+                collided,
+                check,
+            } => {
+                let fingerprint = board.fingerprint();
+                if !*check {
+                    entries.insert(fingerprint, (0, value));
+                    return;
+                }
+                let sum = checksum(&board);
+                let first = match entries.get_mut(&fingerprint) {
+                    None => {
+                        entries.insert(fingerprint, (sum, value));
+                        return;
+                    }
+                    Some(first) => first,
+                };
+                if first.0 == sum {
+                    first.1 = value;
+                    return;
+                }
+                let others = collided.entry(fingerprint).or_default();
+                if let Some(other) = others.iter_mut().find(|(other, _)| *other == sum) {
+                    other.1 = value;
+                    return;
+                }
+                NUM_COLLISIONS.fetch_add(1, Ordering::Relaxed);
+                warn(Warning::HashCollision {
+                    fingerprint: board.fingerprint_hex(),
+                });
+                others.push((sum, value));
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::board::{Move, MoveLocation};
+use crate::card::{Card, Suit};
+use crate::i18n::{self, fill};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+// how the moves we print to stderr for people look: cards tinted by suit, and the suits drawn as
+// emoji or as letters. some terminals get the emoji's widths wrong, which wrecks anything that's
+// meant to line up. none of this touches stdout, that's for the driver
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Choice {
+    // when stderr's a terminal and NO_COLOR isn't set (see https://no-color.org)
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    // unless card::ASCII is on
+    Emoji,
+    // see Suit::letter
+    Letters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    color: bool,
+    glyphs: Glyphs,
+}
+
+const PLAIN: Palette = Palette {
+    color: false,
+    glyphs: Glyphs::Emoji,
+};
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+// picks the palette for the rest of the run. only the first call counts
+pub fn set(choice: Choice, glyphs: Glyphs) {
+    let color = match choice {
+        Choice::Always => true,
+        Choice::Never => false,
+        Choice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && io::stderr().is_terminal()
+        }
+    };
+    let _ = PALETTE.set(Palette { color, glyphs });
+}
+
+// plain, emoji suits if set hasn't been called
+pub fn palette() -> Palette {
+    PALETTE.get().copied().unwrap_or(PLAIN)
+}
+
+// the ANSI foreground colour for each suit, with the majors last
+fn code(card: Card) -> &'static str {
+    match card {
+        Card::Major(_) => "35",
+        Card::Minor { suit, .. } => match suit {
+            Suit::Sword => "36",
+            Suit::Wand => "32",
+            Suit::Cup => "31",
+            Suit::Star => "33",
+        },
+    }
+}
+
+impl Palette {
+    // the same words and glyphs, for somewhere colour codes would get in the way
+    pub fn without_color(self) -> Self {
+        Self {
+            color: false,
+            ..self
+        }
+    }
+
+    fn paint(&self, code: &str, s: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, s)
+        } else {
+            s.to_string()
+        }
+    }
+
+    pub fn suit(&self, suit: Suit) -> String {
+        if let Some(name) = i18n::messages().suit(suit) {
+            return name.to_string();
+        }
+        match self.glyphs {
+            Glyphs::Emoji => suit.to_string(),
+            Glyphs::Letters => suit.letter().to_string(),
+        }
+    }
+
+    pub fn card(&self, card: Card) -> String {
+        let s = match card {
+            Card::Major(value) => value.0.to_string(),
+            Card::Minor { suit, value } => format!(
+                "{}{}",
+                i18n::messages().minor_value(value.0),
+                self.suit(suit)
+            ),
+        };
+        self.paint(code(card), &s)
+    }
+
+    // for the less important part of a line, like the notes after a move
+    pub fn dim(&self, s: &str) -> String {
+        self.paint("2", s)
+    }
+
+    fn location(&self, location: MoveLocation) -> String {
+        let messages = i18n::messages();
+        match location {
+            MoveLocation::BlockMinorPiles => messages.get("block").to_string(),
+            MoveLocation::PlayingArea { pile, .. } => pile.to_string(),
+            MoveLocation::MajorLower => messages.get("maj_lo").to_string(),
+            MoveLocation::MajorHigher => messages.get("maj_hi").to_string(),
+            MoveLocation::MinorFoundation(suit) => self.suit(suit),
+        }
+    }
+
+    // Move's Display, in colour and in the language from i18n
+    pub fn moov(&self, moov: &Move) -> String {
+        fill(
+            i18n::messages().get("move"),
+            &[
+                ("card", &self.card(moov.card)),
+                ("from", &self.location(moov.from)),
+                ("to", &self.location(moov.to)),
+            ],
+        )
+    }
+}
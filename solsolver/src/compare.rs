@@ -0,0 +1,293 @@
+use crate::board::{Board, Protocol, Solution};
+use crate::provenance;
+use crate::shuffle;
+use crate::warnings::escape;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+// what two solvers (two builds, or one build with two sets of settings) make of the same deals, so
+// a heuristic change can be judged by more than the deal it was made for. a run is saved as JSON,
+// one deal to a line:
+//
+//   {"version":"v3.2-14-gab12cd3 rules=1 protocol=3 features=std","deals":[
+//   {"name":"solvable","solved":true,"moves":96,"ms":812,"first_move":"8:6-BLOCK@0@..."},
+//   ...
+//   ]}
+//
+// the version's the build that made the run, see provenance.rs. the first move's in the newest
+// protocol, and missing if there wasn't a solution. only compare
+// reads these back, so parse only has to cope with what serialize writes
+//
+// a deal counts against the new run if it's no longer solved, or the solution's longer, or it's
+// taken a lot longer (see SLOWER_FACTOR). a different first move isn't a regression, just worth a
+// look: the solution might be as good, but the plan's changed
+
+// timings are noisy, so a deal's only slower if it's taken this many times as long, and at least
+// SLOWER_MIN_MS more
+const SLOWER_FACTOR: f64 = 2.0;
+const SLOWER_MIN_MS: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub name: String,
+    pub solved: bool,
+    // 0 if it wasn't solved
+    pub moves: usize,
+    pub ms: u64,
+    pub first_move: Option<String>,
+}
+
+impl Run {
+    fn serialize(&self) -> String {
+        let first_move = match &self.first_move {
+            Some(moov) => format!(r#","first_move":"{}""#, escape(moov)),
+            None => String::new(),
+        };
+        format!(
+            r#"{{"name":"{}","solved":{},"moves":{},"ms":{}{}}}"#,
+            escape(&self.name),
+            self.solved,
+            self.moves,
+            self.ms,
+            first_move
+        )
+    }
+
+    fn parse(line: &str) -> Option<Run> {
+        Some(Run {
+            name: string_field(line, "name")?,
+            solved: field(line, "solved")?.parse().ok()?,
+            moves: field(line, "moves")?.parse().ok()?,
+            ms: field(line, "ms")?.parse().ok()?,
+            first_move: string_field(line, "first_move"),
+        })
+    }
+}
+
+pub fn serialize(runs: &[Run]) -> String {
+    let lines: Vec<String> = runs.iter().map(Run::serialize).collect();
+    format!(
+        "{{\"version\":\"{}\",\"deals\":[\n{}\n]}}\n",
+        escape(&provenance::version()),
+        lines.join(",\n")
+    )
+}
+
+pub fn parse(s: &str) -> Result<Vec<Run>, String> {
+    s.lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| line.starts_with("{\"name\""))
+        .map(|line| Run::parse(line).ok_or_else(|| format!("bad line {:?}", line)))
+        .collect()
+}
+
+// the build that made the run, if it says
+pub fn parse_version(s: &str) -> Option<String> {
+    string_field(s.lines().next()?, "version")
+}
+
+// the value after "key": up to the next , or }, as is
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let rest = &line[start..];
+    Some(&rest[..rest.find([',', '}'])?])
+}
+
+// a quoted value, unescaped
+fn string_field(line: &str, key: &str) -> Option<String> {
+    let start = line.find(&format!("\"{}\":\"", key))? + key.len() + 4;
+    let mut value = String::new();
+    let mut chars = line[start..].chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+// every DIR/NAME.deal, by name. a corpus directory will do, the index is ignored
+pub fn load_deals(dir: &Path) -> io::Result<Vec<(String, String)>> {
+    let mut deals = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "deal")
+        {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            deals.push((name, shuffle::expand(&fs::read_to_string(&path)?)));
+        }
+    }
+    deals.sort();
+    Ok(deals)
+}
+
+// solves each deal with this build, however it's been set up
+pub fn run(deals: &[(String, String)], solve: &dyn Fn(&Board) -> Option<Solution>) -> Vec<Run> {
+    deals
+        .iter()
+        .map(|(name, deal)| {
+            let mut board = Board::parse(deal);
+            board.suck_readies_into_receptacles();
+            let start = Instant::now();
+            let solution = solve(&board);
+            let ms = start.elapsed().as_millis() as u64;
+            Run {
+                name: name.clone(),
+                solved: solution.is_some(),
+                moves: solution.as_ref().map_or(0, |solution| solution.moves.len()),
+                ms,
+                first_move: solution
+                    .and_then(|solution| solution.moves.first().map(|m| m.serialize(Protocol::V3))),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    // solved before, not now
+    Lost,
+    Gained,
+    Longer { old: usize, new: usize },
+    Shorter { old: usize, new: usize },
+    Slower { old_ms: u64, new_ms: u64 },
+    FirstMove { old: String, new: String },
+}
+
+impl Change {
+    pub fn is_regression(&self) -> bool {
+        matches!(
+            self,
+            Change::Lost | Change::Longer { .. } | Change::Slower { .. }
+        )
+    }
+
+    pub fn serialize(&self) -> String {
+        match self {
+            Change::Lost => "LOST".to_string(),
+            Change::Gained => "GAINED".to_string(),
+            Change::Longer { old, new } => format!("LONGER {} -> {}", old, new),
+            Change::Shorter { old, new } => format!("SHORTER {} -> {}", old, new),
+            Change::Slower { old_ms, new_ms } => format!("SLOWER {}ms -> {}ms", old_ms, new_ms),
+            Change::FirstMove { old, new } => format!("FIRST_MOVE {} -> {}", old, new),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    // deals in both runs
+    pub num_deals: usize,
+    pub old_solved: usize,
+    pub new_solved: usize,
+    // over the deals both runs solved
+    pub old_moves: usize,
+    pub new_moves: usize,
+    pub old_ms: u64,
+    pub new_ms: u64,
+    // by deal name
+    pub changes: Vec<(String, Change)>,
+    // in one run but not the other, so left out
+    pub unmatched: Vec<String>,
+}
+
+impl Comparison {
+    pub fn num_regressions(&self) -> usize {
+        self.changes
+            .iter()
+            .filter(|(_, change)| change.is_regression())
+            .count()
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "solved old={} new={} of={}",
+                self.old_solved, self.new_solved, self.num_deals
+            ),
+            format!("moves old={} new={}", self.old_moves, self.new_moves),
+            format!("ms old={} new={}", self.old_ms, self.new_ms),
+            format!("regressions={}", self.num_regressions()),
+        ];
+        for (name, change) in &self.changes {
+            lines.push(format!("{} {}", change.serialize(), name));
+        }
+        for name in &self.unmatched {
+            lines.push(format!("UNMATCHED {}", name));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+pub fn compare(old: &[Run], new: &[Run]) -> Comparison {
+    let mut comparison = Comparison::default();
+    for old in old {
+        let Some(new) = new.iter().find(|new| new.name == old.name) else {
+            comparison.unmatched.push(old.name.clone());
+            continue;
+        };
+        comparison.num_deals += 1;
+        comparison.old_solved += usize::from(old.solved);
+        comparison.new_solved += usize::from(new.solved);
+        let mut changes = vec![];
+        match (old.solved, new.solved) {
+            (true, false) => changes.push(Change::Lost),
+            (false, true) => changes.push(Change::Gained),
+            (false, false) => {}
+            (true, true) => {
+                comparison.old_moves += old.moves;
+                comparison.new_moves += new.moves;
+                comparison.old_ms += old.ms;
+                comparison.new_ms += new.ms;
+                let (old_moves, new_moves) = (old.moves, new.moves);
+                if new_moves > old_moves {
+                    changes.push(Change::Longer {
+                        old: old_moves,
+                        new: new_moves,
+                    });
+                } else if new_moves < old_moves {
+                    changes.push(Change::Shorter {
+                        old: old_moves,
+                        new: new_moves,
+                    });
+                }
+                if new.ms as f64 > old.ms as f64 * SLOWER_FACTOR && new.ms >= old.ms + SLOWER_MIN_MS
+                {
+                    changes.push(Change::Slower {
+                        old_ms: old.ms,
+                        new_ms: new.ms,
+                    });
+                }
+                if let (Some(old), Some(new)) = (&old.first_move, &new.first_move) {
+                    if old != new {
+                        changes.push(Change::FirstMove {
+                            old: old.clone(),
+                            new: new.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        comparison
+            .changes
+            .extend(changes.into_iter().map(|change| (old.name.clone(), change)));
+    }
+    comparison.unmatched.extend(
+        new.iter()
+            .filter(|new| !old.iter().any(|old| old.name == new.name))
+            .map(|new| new.name.clone()),
+    );
+    comparison
+}
@@ -0,0 +1,57 @@
+use crate::board::{pile_lines, Board};
+use crate::card::Card;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// the screen reader can say how sure it was of each card, as CARD:CONFIDENCE in place of the card
+// (e.g. 10_CUP:0.62, from 0 to 1). Board::parse ignores these, they're for when things go wrong:
+// the least confident cards are the first suspects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Confidence {
+    pub pile: usize,
+    pub depth: usize,
+    pub card: Card,
+    pub confidence: f32,
+}
+
+// the confidence of every card that has one, least confident first
+pub fn read_confidences(s: &str) -> Vec<Confidence> {
+    let mut confidences = vec![];
+    for (pile, line) in pile_lines(s).enumerate() {
+        let tokens = line
+            .trim()
+            .split_terminator(',')
+            .map(str::trim)
+            .filter(|token| !token.is_empty());
+        for (depth, token) in tokens.enumerate() {
+            if let Some((card, confidence)) = token.split_once(':') {
+                confidences.push(Confidence {
+                    pile,
+                    depth,
+                    card: Card::parse(card),
+                    confidence: confidence.parse().unwrap(),
+                });
+            }
+        }
+    }
+    confidences.sort_by(|a, b| a.confidence.total_cmp(&b.confidence));
+    confidences
+}
+
+// the board with the suspect card swapped for each card that should be there but isn't, since a
+// misread card usually shows up as a duplicate of something else. the board's as it was read, the
+// readies not sucked in yet, or the suspect may have gone home already (and then there's nothing)
+pub fn substitutions(board: &Board, suspect: &Confidence) -> Vec<(Card, Board)> {
+    if suspect.depth >= board.playing_area[suspect.pile].len() {
+        return vec![];
+    }
+    board
+        .missing_cards()
+        .iter()
+        .map(|card| {
+            let mut board = board.clone();
+            board.replace_card(suspect.pile, suspect.depth, card);
+            (card.card(), board)
+        })
+        .collect()
+}
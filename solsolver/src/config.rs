@@ -0,0 +1,289 @@
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// settings that don't fit comfortably on a command line any more. any option can also come from an
+// environment variable or a config file, and which one wins goes command line, then environment,
+// then file, then the option's default:
+// - the environment variable for an option is SOLSOLVER_ and its name in capitals, e.g.
+//   SOLSOLVER_HEURISTIC=relaxed, or SOLSOLVER_SESSION_BUDGET_MS for a subcommand's options. a flag
+//   is on if it's true or 1
+// - the file (solsolver.toml, or --config) has the options by name at the top, and a table per
+//   subcommand for that subcommand's options, e.g.
+//     heuristic = "relaxed"
+//     no_block = true
+//     cpus = [2, 3]
+//     [session]
+//     budget_ms = 3000
+//
+// the session and interactive subcommands keep an eye on the file (see Watcher), and a deal that
+// comes in after it's changed gets the new settings
+//
+// it's all done by adding the options from the environment and the file to the command line before
+// clap sees it, so they're checked exactly like they would be there
+
+pub const DEFAULT_FILE: &str = "solsolver.toml";
+
+// the options we leave alone: they say where the settings come from, so they can't be settings
+const NOT_SETTINGS: [&str; 4] = ["help", "version", "config", "print_config"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    CommandLine,
+    Env,
+    File,
+    Default,
+}
+
+impl Source {
+    pub fn serialize(&self) -> &'static str {
+        match self {
+            Source::CommandLine => "command line",
+            Source::Env => "environment",
+            Source::File => "config file",
+            Source::Default => "default",
+        }
+    }
+}
+
+// the command line with the settings from the environment and the file added in, and where each
+// setting we added came from (by subcommand, "" for the top level, then option)
+pub struct Layered {
+    pub args: Vec<String>,
+    pub sources: HashMap<(String, String), Source>,
+    // the config file we looked for, whether it was there or not
+    pub file: PathBuf,
+}
+
+// a missing file is no settings, unless we were told to read it
+fn load(path: &Path, required: bool) -> Result<toml::Table, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => text
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(toml::Table::new()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+// a setting's values as they'd be typed, or None for a flag that's off
+fn from_file(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::Boolean(on) => on.then(Vec::new),
+        toml::Value::String(s) => Some(vec![s.clone()]),
+        toml::Value::Integer(n) => Some(vec![n.to_string()]),
+        toml::Value::Float(x) => Some(vec![x.to_string()]),
+        toml::Value::Array(values) => Some(values.iter().filter_map(from_file).flatten().collect()),
+        toml::Value::Datetime(datetime) => Some(vec![datetime.to_string()]),
+        toml::Value::Table(_) => None,
+    }
+}
+
+fn from_env(value: &str, takes_values: bool) -> Option<Vec<String>> {
+    if takes_values {
+        Some(vec![value.to_string()])
+    } else {
+        (value == "true" || value == "1").then(Vec::new)
+    }
+}
+
+// the settings for one command (the top level or a subcommand) that weren't on the command line,
+// as arguments
+fn layer_command(
+    command: &Command,
+    matches: &ArgMatches,
+    subcommand: &str,
+    table: Option<&toml::Table>,
+    sources: &mut HashMap<(String, String), Source>,
+) -> Vec<String> {
+    let mut args = vec![];
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        if NOT_SETTINGS.contains(&id) {
+            continue;
+        }
+        let key = (subcommand.to_string(), id.to_string());
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            sources.insert(key, Source::CommandLine);
+            continue;
+        }
+        let var = match subcommand {
+            "" => format!("SOLSOLVER_{}", id.to_uppercase()),
+            _ => format!(
+                "SOLSOLVER_{}_{}",
+                subcommand.replace('-', "_").to_uppercase(),
+                id.to_uppercase()
+            ),
+        };
+        let takes_values = arg.get_action().takes_values();
+        let (values, source) = if let Ok(value) = env::var(&var) {
+            (from_env(&value, takes_values), Source::Env)
+        } else if let Some(value) = table.and_then(|table| table.get(id)) {
+            (from_file(value), Source::File)
+        } else {
+            continue;
+        };
+        let Some(values) = values else {
+            continue;
+        };
+        if takes_values {
+            args.extend(values.iter().map(|value| format!("--{}={}", long, value)));
+        } else {
+            args.push(format!("--{}", long));
+        }
+        sources.insert(key, source);
+    }
+    args
+}
+
+// the config file is --config, or SOLSOLVER_CONFIG, or DEFAULT_FILE if there is one
+pub fn layer(command: &Command, args: Vec<String>) -> Result<Layered, String> {
+    let mut sources = HashMap::new();
+    // a first look to see what's on the command line, and which subcommand. anything wrong with it
+    // gets reported properly when it's parsed for real
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&args)
+    else {
+        return Ok(Layered {
+            args,
+            sources,
+            file: PathBuf::from(DEFAULT_FILE),
+        });
+    };
+    let (path, required) = match (
+        matches.get_one::<PathBuf>("config"),
+        env::var("SOLSOLVER_CONFIG"),
+    ) {
+        (Some(path), _) => (path.clone(), true),
+        (None, Ok(path)) => (PathBuf::from(path), true),
+        (None, Err(_)) => (PathBuf::from(DEFAULT_FILE), false),
+    };
+    let file = load(&path, required)?;
+    let top = layer_command(command, &matches, "", Some(&file), &mut sources);
+    let sub = match matches.subcommand() {
+        Some((name, sub_matches)) => {
+            let subcommand = command.find_subcommand(name).unwrap();
+            let table = file.get(name).and_then(toml::Value::as_table);
+            layer_command(subcommand, sub_matches, name, table, &mut sources)
+        }
+        None => vec![],
+    };
+    // the top level's have to go before the subcommand, and the subcommand's after it
+    let mut layered = vec![args[0].clone()];
+    layered.extend(top);
+    layered.extend(args.into_iter().skip(1));
+    layered.extend(sub);
+    Ok(Layered {
+        args: layered,
+        sources,
+        file: path,
+    })
+}
+
+// notices the config file changing, so a long running session can pick up new settings without
+// being restarted (and losing everything it's worked out so far)
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Watcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified(path),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // true once for each time the file's been written (or created, or deleted) since we last looked
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+// values as they'd be written in the config file
+fn to_toml(values: &[String]) -> String {
+    let value = |s: &String| match s.as_str() {
+        "true" | "false" => s.clone(),
+        s if s.parse::<i64>().is_ok() => s.to_string(),
+        s => format!("{:?}", s),
+    };
+    match values {
+        [one] => value(one),
+        many => format!(
+            "[{}]",
+            many.iter().map(value).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn serialize_command(
+    command: &Command,
+    matches: &ArgMatches,
+    subcommand: &str,
+    sources: &HashMap<(String, String), Source>,
+) -> String {
+    let mut s = String::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if arg.get_long().is_none() || NOT_SETTINGS.contains(&id) {
+            continue;
+        }
+        let Some(raw) = matches.get_raw(id) else {
+            continue;
+        };
+        let values: Vec<String> = raw
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect();
+        let source = match sources.get(&(subcommand.to_string(), id.to_string())) {
+            Some(source) => *source,
+            None => Source::Default,
+        };
+        s.push_str(&format!(
+            "{} = {}  # {}\n",
+            id,
+            to_toml(&values),
+            source.serialize()
+        ));
+    }
+    s
+}
+
+// the settings in effect, in the config file's format, each with where it came from
+pub fn serialize(command: &Command, matches: &ArgMatches, layered: &Layered) -> String {
+    let mut s = serialize_command(command, matches, "", &layered.sources);
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        let subcommand = command.find_subcommand(name).unwrap();
+        s.push_str(&format!("\n[{}]\n", name));
+        s.push_str(&serialize_command(
+            subcommand,
+            sub_matches,
+            name,
+            &layered.sources,
+        ));
+    }
+    s
+}
@@ -0,0 +1,76 @@
+use crate::shuffle;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+// the regression corpus: deals we've solved before, and how long the solution was. every deal is
+// DIR/NAME.deal, in the same format as the solver's stdin (so SEED N will do, see shuffle.rs), and
+// DIR/index lists one NAME LENGTH pair per line, followed by the version of the build that found
+// the length (see provenance.rs) on entries from after we started keeping it
+const INDEX_FILE: &str = "index";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    pub name: String,
+    pub deal: String,
+    // number of moves in the known-good solution
+    pub length: usize,
+    // what found it, if we know
+    pub version: Option<String>,
+}
+
+pub fn load(dir: &Path) -> io::Result<Vec<CorpusEntry>> {
+    let index = fs::read_to_string(dir.join(INDEX_FILE))?;
+    let mut entries = vec![];
+    for line in index.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, length) = line.split_once(' ').ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad line {:?}", line))
+        })?;
+        let (length, version) = match length.trim().split_once(' ') {
+            Some((length, version)) => (length, Some(version.trim().to_string())),
+            None => (length.trim(), None),
+        };
+        let length = length.parse().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad length {:?}", line))
+        })?;
+        entries.push(CorpusEntry {
+            name: name.to_string(),
+            deal: shuffle::expand(&fs::read_to_string(dir.join(format!("{}.deal", name)))?),
+            length,
+            version,
+        });
+    }
+    Ok(entries)
+}
+
+// adds a deal we just solved to the corpus, so a later change that makes us worse at it gets caught
+pub fn append(dir: &Path, entry: &CorpusEntry) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{}.deal", entry.name)), &entry.deal)?;
+    let mut index = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(INDEX_FILE))?;
+    writeln!(index, "{}", index_line(entry))
+}
+
+fn index_line(entry: &CorpusEntry) -> String {
+    match &entry.version {
+        Some(version) => format!("{} {} {}", entry.name, entry.length, version),
+        None => format!("{} {}", entry.name, entry.length),
+    }
+}
+
+// writes the index again with the lengths in entries, e.g. after they've been tightened. the deals
+// are left as they are
+pub fn save_index(dir: &Path, entries: &[CorpusEntry]) -> io::Result<()> {
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|entry| index_line(entry) + "\n")
+        .collect();
+    fs::write(dir.join(INDEX_FILE), lines.concat())
+}
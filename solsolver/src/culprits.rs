@@ -0,0 +1,180 @@
+use crate::board::{Board, MoveLocation};
+use crate::card::{Card, CardId};
+use crate::rules::Rules;
+use crate::search::{Heuristic, Search, SearchOptions, SearchStatus, OLD};
+use std::collections::BTreeMap;
+
+// why the deals we can't win can't be won, over a batch of them. for each deal we look through
+// everything reachable without pruning, and if none of it wins we take the board that got closest
+// (fewest cards left) as where it went wrong. what's holding it up there are the culprits: the
+// cards a foundation is waiting for that can't get to it, and for each one, what's in its way.
+// added up over the batch, that says which cards and which layouts do the most damage
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pattern {
+    // a higher minor of its own suit is on top of it, so that has to find somewhere else to go
+    // before its own suit's foundation can get anywhere near it
+    UnderOwnSuit,
+    // only majors on top of it
+    UnderMajors,
+    // anything else on top of it
+    Buried,
+    // it's a minor on top of its pile, but the block's taken so the minor foundations are shut
+    Shut,
+}
+
+impl Pattern {
+    pub fn serialize(&self) -> &'static str {
+        match self {
+            Pattern::UnderOwnSuit => "under_own_suit",
+            Pattern::UnderMajors => "under_majors",
+            Pattern::Buried => "buried",
+            Pattern::Shut => "shut",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Culprit {
+    pub card: CardId,
+    pub pattern: Pattern,
+    // a minor while something's on the block, which would keep it out even if it were uncovered
+    pub block_taken: bool,
+}
+
+impl Culprit {
+    // e.g. under_own_suit+block_taken
+    pub fn pattern_name(&self) -> String {
+        if self.block_taken && self.pattern != Pattern::Shut {
+            format!("{}+block_taken", self.pattern.serialize())
+        } else {
+            self.pattern.serialize().to_string()
+        }
+    }
+}
+
+fn is_minor_of(card: CardId, suit: usize, above_value: u8) -> bool {
+    matches!(card.card(), Card::Minor { suit: s, value } if s as usize == suit && value.0 > above_value)
+}
+
+// the cards a foundation's waiting for that are still in the piles, and what's stopping them
+pub fn culprits(board: &Board) -> Vec<Culprit> {
+    let mut culprits = vec![];
+    for pile in &board.playing_area {
+        for (depth, &card) in pile.iter().enumerate() {
+            let Some(foundation) = board.foundation_accepting(card) else {
+                continue;
+            };
+            let block_taken = matches!(foundation, MoveLocation::MinorFoundation(_))
+                && board.minor_collection_blocked.is_some();
+            let above = &pile[depth + 1..];
+            let pattern = match card.card() {
+                _ if above.is_empty() => Pattern::Shut,
+                Card::Minor { suit, value }
+                    if above
+                        .iter()
+                        .any(|&above| is_minor_of(above, suit as usize, value.0)) =>
+                {
+                    Pattern::UnderOwnSuit
+                }
+                _ if above.iter().all(|above| above.is_major()) => Pattern::UnderMajors,
+                _ => Pattern::Buried,
+            };
+            culprits.push(Culprit {
+                card,
+                pattern,
+                block_taken,
+            });
+        }
+    }
+    culprits
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostMortem {
+    // it can be won after all, nothing to say
+    Winnable,
+    // ran out of expansions before we'd looked at everything, or something (like --upper-bound)
+    // left boards out
+    Unknown,
+    // the board that got closest, and what's holding it up
+    Lost {
+        closest: Box<Board>,
+        culprits: Vec<Culprit>,
+    },
+}
+
+pub fn post_mortem(board: &Board, rules: &Rules, max_expansions: usize) -> PostMortem {
+    let mut search = Search::new(
+        board,
+        SearchOptions {
+            num_prev_moves: OLD,
+            heuristic: Heuristic::CardsRemaining,
+            rules: *rules,
+        },
+    );
+    match search.step_expansions(max_expansions) {
+        SearchStatus::Solved(_) => PostMortem::Winnable,
+        SearchStatus::Exhausted if !search.proves_dead() => PostMortem::Unknown,
+        SearchStatus::InProgress => PostMortem::Unknown,
+        SearchStatus::Exhausted => {
+            let closest = Box::new(search.best_board().clone());
+            let culprits = culprits(&closest);
+            PostMortem::Lost { closest, culprits }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub num_lost: usize,
+    pub num_winnable: usize,
+    pub num_unknown: usize,
+    // how many of the lost deals each card was a culprit in
+    pub cards: BTreeMap<CardId, usize>,
+    // how many culprits there were with each Culprit::pattern_name
+    pub patterns: BTreeMap<String, usize>,
+}
+
+// the keys, most common first
+fn by_count<K: Ord + Clone>(counts: &BTreeMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = counts.iter().map(|(k, &n)| (k.clone(), n)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts
+}
+
+impl Report {
+    pub fn add(&mut self, post_mortem: &PostMortem) {
+        let culprits = match post_mortem {
+            PostMortem::Winnable => {
+                self.num_winnable += 1;
+                return;
+            }
+            PostMortem::Unknown => {
+                self.num_unknown += 1;
+                return;
+            }
+            PostMortem::Lost { culprits, .. } => culprits,
+        };
+        self.num_lost += 1;
+        for culprit in culprits {
+            *self.cards.entry(culprit.card).or_default() += 1;
+            *self.patterns.entry(culprit.pattern_name()).or_default() += 1;
+        }
+    }
+
+    // a summary line, then PATTERN lines, then CARD lines, most common first
+    pub fn serialize(&self) -> String {
+        let mut s = format!(
+            "lost={} winnable={} unknown={}\n",
+            self.num_lost, self.num_winnable, self.num_unknown
+        );
+        for (pattern, n) in by_count(&self.patterns) {
+            s.push_str(&format!("PATTERN {} {}\n", pattern, n));
+        }
+        for (card, n) in by_count(&self.cards) {
+            s.push_str(&format!("CARD {} {}\n", card.card().serialize(), n));
+        }
+        s
+    }
+}
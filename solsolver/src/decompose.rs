@@ -0,0 +1,120 @@
+use crate::board::{Board, MoveLocation, Solution, NUM_PLAYING_STACKS};
+use crate::card::{CardId, NUM_SUITS};
+use crate::rules::Rules;
+use crate::search::search;
+
+// cards only ever interact with cards of their own family: the same minor suit, or the majors.
+// (they stack on each other and they go to the same foundations.) the only other way they get in
+// each other's way is by sharing a pile, so families that never share a pile split the board into
+// groups that can be cleared without ever touching each other
+const NUM_FAMILIES: usize = NUM_SUITS + 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub families: [bool; NUM_FAMILIES],
+    pub piles: [bool; NUM_PLAYING_STACKS],
+}
+
+impl Group {
+    fn contains(&self, card: CardId) -> bool {
+        self.families[card.family()]
+    }
+
+    fn num_cards_remaining(&self, board: &Board) -> usize {
+        board
+            .playing_area
+            .iter()
+            .flatten()
+            .chain(board.minor_collection_blocked.iter())
+            .filter(|&&card| self.contains(card))
+            .count()
+    }
+}
+
+pub fn independent_groups(board: &Board) -> Vec<Group> {
+    // union-find over families, joining every family that shares a pile
+    let mut parent: [usize; NUM_FAMILIES] = std::array::from_fn(|i| i);
+    fn find(parent: &mut [usize; NUM_FAMILIES], i: usize) -> usize {
+        if parent[i] != i {
+            parent[i] = find(parent, parent[i]);
+        }
+        parent[i]
+    }
+
+    let mut present = [false; NUM_FAMILIES];
+    for card in board
+        .playing_area
+        .iter()
+        .flatten()
+        .chain(board.minor_collection_blocked.iter())
+    {
+        present[card.family()] = true;
+    }
+    for stack in &board.playing_area {
+        let mut families = stack.iter().map(|card| card.family());
+        if let Some(first) = families.next() {
+            for other in families {
+                let (a, b) = (find(&mut parent, first), find(&mut parent, other));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut groups: Vec<Group> = vec![];
+    for fam in (0..NUM_FAMILIES).filter(|&fam| present[fam]) {
+        let root = find(&mut parent, fam);
+        let mut families = [false; NUM_FAMILIES];
+        for other in 0..NUM_FAMILIES {
+            families[other] = present[other] && find(&mut parent, other) == root;
+        }
+        if groups.iter().any(|group| group.families == families) {
+            continue;
+        }
+        let mut piles = [false; NUM_PLAYING_STACKS];
+        for (pile, stack) in piles.iter_mut().zip(&board.playing_area) {
+            *pile = stack.iter().any(|card| families[card.family()]);
+        }
+        groups.push(Group { families, piles });
+    }
+    // knock out the small groups first, they free up piles for the bigger ones
+    groups.sort_by_key(|group| group.num_cards_remaining(board));
+    groups
+}
+
+// clears one group at a time, each with its own search that's only allowed to touch that group's
+// piles (plus whatever piles are empty when it starts). every search runs on the real board, so the
+// other groups' cards are really there and the stitched-together plan is valid as is
+pub fn solve_decomposed(board: &Board, num_prev_moves: usize, rules: &Rules) -> Option<Solution> {
+    let mut solution = Solution { moves: vec![] };
+    let mut board = board.clone();
+    for group in independent_groups(&board) {
+        let mut allowed = group.piles;
+        for (allowed, stack) in allowed.iter_mut().zip(&board.playing_area) {
+            *allowed |= stack.is_empty();
+        }
+        let touches_allowed = |location: MoveLocation| match location {
+            MoveLocation::BlockMinorPiles => true,
+            MoveLocation::PlayingArea { pile, .. } => allowed[pile],
+            // a card can only go home if it's in this group
+            MoveLocation::MajorLower
+            | MoveLocation::MajorHigher
+            | MoveLocation::MinorFoundation(_) => true,
+        };
+        let (part, end) = search(
+            &board,
+            num_prev_moves,
+            0,
+            &|b| {
+                b.next_boards(rules)
+                    .into_iter()
+                    .filter(|(_, moov)| touches_allowed(moov.from) && touches_allowed(moov.to))
+                    .collect()
+            },
+            &|b| group.num_cards_remaining(b),
+            |b| group.num_cards_remaining(b) == 0,
+        )?;
+        solution.moves.extend(part.moves);
+        board = end;
+    }
+    Some(solution)
+}
@@ -0,0 +1,129 @@
+use crate::board::{Board, Move, Protocol, Solution};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+// for --lock-dir: when the orchestrator starts two solvers on the same deal by mistake, only one of
+// them should be eating the machine's memory. the first to get there makes FINGERPRINT.lock in the
+// directory (with its pid in it) and solves the deal, and when it's done writes what it found to
+// FINGERPRINT.result for anyone else to pick up. a solver that finds the lock already there waits
+// for the result instead of solving it again
+//
+// the results are kept, so a deal that comes round again is answered straight away. everything
+// sharing a directory has to be run with the same rules and goal, since a result doesn't say which
+// it was for. a lock whose process has gone (it crashed, or was killed) is taken over
+//
+// a result is SOLVED and then the moves (a line each, in the newest protocol), or NO_SOLUTION.
+// that's only written when a search went through everything and there's no way through at all (see
+// search::proven_dead). a solver that gave up (it ran out of time, or --upper-bound or --safe-mode
+// forbid left some of the boards out) leaves no result, and the next one to come along tries again
+
+const SOLVED: &str = "SOLVED";
+const NO_SOLUTION: &str = "NO_SOLUTION";
+
+// how often a waiting solver looks for the result
+const POLL: Duration = Duration::from_millis(100);
+
+pub enum Claim {
+    // the deal's ours to solve
+    Mine(Lock),
+    // someone else solved it (None if there isn't a solution)
+    Answered(Option<Solution>),
+}
+
+// held while we solve a deal. dropping it (without finish, say if we panic) gives it up without a
+// result, and whoever's waiting takes over
+pub struct Lock {
+    path: PathBuf,
+    result: PathBuf,
+}
+
+impl Lock {
+    // None only if it's been proven there's no solution, see above
+    pub fn finish(self, solution: Option<&Solution>) -> io::Result<()> {
+        let text = match solution {
+            Some(solution) => {
+                let mut text = format!("{}\n", SOLVED);
+                for moov in &solution.moves {
+                    text.push_str(&moov.serialize(Protocol::NEWEST));
+                    text.push('\n');
+                }
+                text
+            }
+            None => format!("{}\n", NO_SOLUTION),
+        };
+        // so a waiter never sees half of it
+        let partial = self.result.with_extension("partial");
+        fs::write(&partial, text.as_bytes())?;
+        fs::rename(&partial, &self.result)
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn parse_result(s: &str) -> Option<Option<Solution>> {
+    let mut lines = s.lines();
+    match lines.next()? {
+        SOLVED => Some(Some(Solution {
+            moves: lines
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| Move::parse(line, Protocol::NEWEST))
+                .collect(),
+        })),
+        NO_SOLUTION => Some(None),
+        _ => None,
+    }
+}
+
+// whether the process that made a lock is still about
+fn is_alive(pid: u32) -> bool {
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+// the deal's lock, or (after waiting for it if need be) the answer whoever had it came up with
+pub fn claim(dir: &Path, board: &Board) -> io::Result<Claim> {
+    fs::create_dir_all(dir)?;
+    let fingerprint = board.fingerprint_hex();
+    let path = dir.join(format!("{}.lock", fingerprint));
+    let result = dir.join(format!("{}.result", fingerprint));
+    loop {
+        if let Ok(text) = fs::read_to_string(&result) {
+            if let Some(answer) = parse_result(&text) {
+                return Ok(Claim::Answered(answer));
+            }
+        }
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                writeln!(file, "{}", process::id())?;
+                let lock = Lock { path, result };
+                // whoever had it could have finished between our looking and our taking it
+                if let Some(answer) = fs::read_to_string(&lock.result)
+                    .ok()
+                    .and_then(|text| parse_result(&text))
+                {
+                    return Ok(Claim::Answered(answer));
+                }
+                return Ok(Claim::Mine(lock));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        // someone else's. if they've died, it's ours for the taking next time round
+        let owner = fs::read_to_string(&path)
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok());
+        if owner.is_some_and(|pid| !is_alive(pid)) {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+        thread::sleep(POLL);
+    }
+}
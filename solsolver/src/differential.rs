@@ -0,0 +1,185 @@
+use crate::board::{Board, Move, Solution, NUM_PLAYING_STACKS};
+use crate::card::NUM_SUITS;
+use crate::corpus::CorpusEntry;
+use crate::minimize;
+use crate::provenance;
+use crate::retrograde::{endgame_board, endgame_cards, family_size};
+use crate::rules::Rules;
+use crate::search::{self, Heuristic, Search, SearchOptions, SearchStatus, OLD};
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{RngExt, SeedableRng};
+use std::collections::HashSet;
+
+// fuzzing the search: random small boards, each solved two completely different ways, which had
+// better agree on whether there's a solution. the search has plenty of places for a bug to make it
+// miss a way through (the pruning, the dedup, the goal test) and this is how we'd find one. when
+// they don't agree, the board's shrunk down to as few cards as still shows it, which is usually few
+// enough to work out by hand
+
+// how many boards each backend gets to look at before we give up on a board. small boards are
+// either settled well within this, or they're not interesting enough to wait for
+pub const BUDGET: usize = 20_000;
+
+// the chance a random board starts with a card on the block, as 1 in this
+const ONE_IN_BLOCKED: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    // Search with no pruning, best first
+    AStar,
+    // depth first, with nothing but a set of boards we've already been to. too slow for real deals,
+    // but there's so little to it that it's hard to get wrong
+    Dfs,
+}
+
+// what a backend made of a board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Solved(Solution),
+    Unsolvable,
+    OutOfBudget,
+}
+
+impl Answer {
+    // None if the backend couldn't say either way
+    pub fn solvable(&self) -> Option<bool> {
+        match self {
+            Answer::Solved(_) => Some(true),
+            Answer::Unsolvable => Some(false),
+            Answer::OutOfBudget => None,
+        }
+    }
+}
+
+pub fn solve(backend: Backend, board: &Board, rules: &Rules) -> Answer {
+    match backend {
+        Backend::AStar => {
+            let options = SearchOptions {
+                num_prev_moves: OLD,
+                heuristic: Heuristic::CardsRemaining,
+                rules: *rules,
+            };
+            match Search::new(board, options).step_expansions(BUDGET) {
+                SearchStatus::Solved(solution) => Answer::Solved(solution),
+                SearchStatus::Exhausted => Answer::Unsolvable,
+                SearchStatus::InProgress => Answer::OutOfBudget,
+            }
+        }
+        Backend::Dfs => dfs(board, rules, BUDGET),
+    }
+}
+
+// also the second opinion on a session's unsolvable deals (see session::verify), with a bigger
+// max_boards
+pub fn dfs(board: &Board, rules: &Rules, max_boards: usize) -> Answer {
+    let mut board = board.clone();
+    board.suck_readies_into_receptacles();
+    if let Some(rest) = search::wrap_up(&board, Heuristic::CardsRemaining, rules) {
+        return Answer::Solved(Solution { moves: rest });
+    }
+    let mut seen = HashSet::from([board.clone()]);
+    // the moves down to where we are, and for each board on the way, the moves from it still to try
+    let mut line: Vec<Move> = vec![];
+    let mut untried: Vec<Vec<(Board, Move)>> = vec![board.next_boards(rules)];
+    while let Some(moves) = untried.last_mut() {
+        let Some((next, moov)) = moves.pop() else {
+            untried.pop();
+            line.pop();
+            continue;
+        };
+        if !seen.insert(next.clone()) {
+            continue;
+        }
+        if seen.len() > max_boards {
+            return Answer::OutOfBudget;
+        }
+        line.push(moov);
+        if let Some(rest) = search::wrap_up(&next, Heuristic::CardsRemaining, rules) {
+            line.extend(rest);
+            return Answer::Solved(Solution { moves: line });
+        }
+        untried.push(next.next_boards(rules));
+    }
+    Answer::Unsolvable
+}
+
+// a board with num_cards left on it, picked at random from the ones retrograde.rs would look at
+// (the top few of each suit, a run from the middle of the majors), dealt at random into the piles
+pub fn random_board(rng: &mut StdRng, num_cards: usize) -> Board {
+    let mut counts = [0; NUM_SUITS + 1];
+    for _ in 0..num_cards {
+        let open: Vec<usize> = (0..counts.len())
+            .filter(|&family| counts[family] < family_size(family))
+            .collect();
+        counts[*open.choose(rng).unwrap()] += 1;
+    }
+    let (mut cards, foundations) = endgame_cards(&counts);
+    cards.shuffle(rng);
+    let blocked = (rng.random_range(0..ONE_IN_BLOCKED) == 0)
+        .then(|| cards.pop())
+        .flatten();
+    let mut piles = vec![vec![]; NUM_PLAYING_STACKS];
+    for card in cards {
+        let pile = rng.random_range(0..piles.len());
+        piles[pile].push(card);
+    }
+    let mut board = endgame_board(&piles, blocked, &foundations);
+    board.suck_readies_into_receptacles();
+    board
+}
+
+// as few cards as the backends still disagree on, see minimize.rs
+pub fn shrink(board: &Board, rules: &Rules) -> Board {
+    minimize::minimize(board, |smaller| disagree(smaller, rules))
+}
+
+// both backends gave an answer, and they're not the same answer
+fn disagree(board: &Board, rules: &Rules) -> bool {
+    let a_star = solve(Backend::AStar, board, rules).solvable();
+    let dfs = solve(Backend::Dfs, board, rules).solvable();
+    matches!((a_star, dfs), (Some(a), Some(b)) if a != b)
+}
+
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    // shrunk, see shrink
+    pub board: Board,
+    pub a_star: Answer,
+    pub dfs: Answer,
+}
+
+impl Disagreement {
+    // for the regression corpus, with the length of whichever solution there was
+    pub fn corpus_entry(&self, name: String) -> CorpusEntry {
+        let length = match (&self.a_star, &self.dfs) {
+            (Answer::Solved(solution), _) | (_, Answer::Solved(solution)) => solution.moves.len(),
+            _ => 0,
+        };
+        CorpusEntry {
+            name,
+            deal: self.board.serialize(),
+            length,
+            version: Some(provenance::version()),
+        }
+    }
+}
+
+// num_boards random boards with num_cards each, and every one the backends didn't agree on
+pub fn run(seed: u64, num_boards: usize, num_cards: usize, rules: &Rules) -> Vec<Disagreement> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut disagreements = vec![];
+    for _ in 0..num_boards {
+        let board = random_board(&mut rng, num_cards);
+        if !disagree(&board, rules) {
+            continue;
+        }
+        let board = shrink(&board, rules);
+        disagreements.push(Disagreement {
+            a_star: solve(Backend::AStar, &board, rules),
+            dfs: solve(Backend::Dfs, &board, rules),
+            board,
+        });
+    }
+    disagreements
+}
@@ -0,0 +1,86 @@
+use crate::board::{Board, MoveLocation, Solution};
+use crate::card::Card;
+use crate::rules::Rules;
+use crate::search::{self, Heuristic};
+use crate::shorten;
+use crate::shuffle;
+
+// practice deals for one tactic at a time. we deal a batch (see shuffle.rs), solve each one and
+// shorten the solution as far as we can in the time (so it's close to the way a good player would
+// go, rather than whatever the search happened to find first), then keep the deal whose solution
+// leans on the tactic the most. nothing here proves the tactic's needed, only that the best line we
+// found uses it a lot
+
+// how long to spend finding a solution to each candidate, and how hard to look for a shorter one
+const SOLVE_MS: u64 = 2_000;
+const SHORTEN_EXPANSIONS: usize = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tactic {
+    // parking cards on the block and getting them off again in time
+    BlockManagement,
+    // moving majors around the piles to line them up for the two major foundations
+    MajorSequencing,
+}
+
+impl Tactic {
+    pub fn serialize(&self) -> &'static str {
+        match self {
+            Tactic::BlockManagement => "block-management",
+            Tactic::MajorSequencing => "major-sequencing",
+        }
+    }
+
+    // how many of the solution's moves are the tactic
+    pub fn score(&self, solution: &Solution) -> usize {
+        solution
+            .moves
+            .iter()
+            .filter(|moov| match self {
+                Tactic::BlockManagement => {
+                    moov.from == MoveLocation::BlockMinorPiles
+                        || moov.to == MoveLocation::BlockMinorPiles
+                }
+                Tactic::MajorSequencing => {
+                    matches!(moov.card, Card::Major(_))
+                        && matches!(moov.to, MoveLocation::PlayingArea { .. })
+                }
+            })
+            .count()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Drill {
+    // for --from-seed
+    pub seed: u64,
+    pub deal: Board,
+    pub solution: Solution,
+    pub score: usize,
+}
+
+// the best for practising the tactic of the deals with the num_candidates seeds from seed on, or
+// None if we couldn't solve any of them
+pub fn drill(tactic: Tactic, num_candidates: usize, seed: u64, rules: &Rules) -> Option<Drill> {
+    let mut best: Option<Drill> = None;
+    for seed in (0..num_candidates as u64).map(|i| seed.wrapping_add(i)) {
+        let deal = shuffle::deal(seed);
+        let mut board = deal.clone();
+        board.suck_readies_into_receptacles();
+        let Ok(solution) = search::anytime(&board, Heuristic::CardsRemaining, rules, SOLVE_MS)
+        else {
+            continue;
+        };
+        let solution = shorten::shorten(&board, &solution, rules, SHORTEN_EXPANSIONS);
+        let score = tactic.score(&solution);
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(Drill {
+                seed,
+                deal,
+                solution,
+                score,
+            });
+        }
+    }
+    best
+}
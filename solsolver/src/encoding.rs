@@ -0,0 +1,111 @@
+use crate::card::{Suit, NUM_SUITS};
+
+// every name and separator in what we read and write: the deal on stdin, the move lines on stdout
+// (see Protocol) and the Display at the end of each one. card.rs and board.rs print and parse from
+// these and nothing else, so the two directions can't drift apart the way V1 and V2 did once (one
+// side writing FROM-TO@N@TEXT while the other read FROM-TO@N@SUCKS@TEXT). tests/encoding.rs
+// checks that everything goes round and comes back the same
+//
+// a move line is FROM-TO, then the fields, all separated by FIELD_SEPARATOR. the Display is the
+// last field and has separators of its own in it ("Pile 0 -> Pile 5"), so a line is split into
+// num_fields at most and the Display is whatever's left
+
+pub struct SuitNames {
+    pub suit: Suit,
+    // in the deal and on the wire
+    pub wire: &'static str,
+    // with card::ASCII, see Suit::letter
+    pub letter: &'static str,
+    pub emoji: &'static str,
+}
+
+// in the same order as Suit::ALL
+pub const SUITS: [SuitNames; NUM_SUITS] = [
+    SuitNames {
+        suit: Suit::Sword,
+        wire: "SWO",
+        letter: "S",
+        emoji: "🗡",
+    },
+    SuitNames {
+        suit: Suit::Wand,
+        wire: "WAN",
+        letter: "W",
+        emoji: "🪄",
+    },
+    SuitNames {
+        suit: Suit::Cup,
+        wire: "CUP",
+        letter: "C",
+        emoji: "🍷",
+    },
+    SuitNames {
+        suit: Suit::Star,
+        wire: "STA",
+        letter: "T",
+        emoji: "⭐",
+    },
+];
+
+// the minor values from the ace (1) to the king (13), the same on the wire and in the Display
+pub const MINOR_VALUES: [&str; 13] = [
+    "A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K",
+];
+
+// a card is VALUE_SUIT, e.g. Q_CUP, or VALUE_MAJ for a major
+pub const CARD_SEPARATOR: char = '_';
+pub const MAJOR: &str = "MAJ";
+
+pub const BLOCK: &str = "BLOCK";
+pub const MAJOR_LOWER: &str = "MAJ_LO";
+pub const MAJOR_HIGHER: &str = "MAJ_HI";
+
+// where a game's got to, after the piles in a deal: BLOCK:CARD, and FOUNDATION:CARD for the top
+// card of each foundation that's had anything put on it, see Board::serialize
+pub const POSITION_SEPARATOR: char = ':';
+
+// a pile in a move is PILE:DEPTH, and a suck in V3 is CARD>FOUNDATION:DEPTH
+pub const DEPTH_SEPARATOR: char = ':';
+pub const FROM_TO_SEPARATOR: char = '-';
+pub const FIELD_SEPARATOR: char = '@';
+pub const SUCK_SEPARATOR: char = ',';
+pub const SUCK_ARROW: char = '>';
+
+// Move's Display is MOVE_CARD, the card, MOVE_FROM, where from, MOVE_TO, where to
+pub const MOVE_CARD: &str = "Card ";
+pub const MOVE_FROM: &str = " Pile ";
+pub const MOVE_TO: &str = " -> Pile ";
+
+pub fn suit_names(suit: Suit) -> &'static SuitNames {
+    &SUITS[suit as usize]
+}
+
+pub fn suit_from_wire(s: &str) -> Option<Suit> {
+    SUITS
+        .iter()
+        .find(|names| names.wire == s)
+        .map(|names| names.suit)
+}
+
+// the suit a Display of a card ends with, either way it might be drawn, and what's in front of it
+pub fn split_displayed_suit(s: &str) -> Option<(&str, Suit)> {
+    SUITS.iter().find_map(|names| {
+        let rest = s
+            .strip_suffix(names.emoji)
+            .or_else(|| s.strip_suffix(names.letter))?;
+        Some((rest, names.suit))
+    })
+}
+
+pub fn minor_value_name(value: u8) -> Option<&'static str> {
+    MINOR_VALUES
+        .get(usize::from(value).checked_sub(1)?)
+        .copied()
+}
+
+pub fn minor_value_from_name(s: &str) -> Option<u8> {
+    MINOR_VALUES
+        .iter()
+        .position(|&name| name == s)
+        .map(|i| i as u8 + 1)
+}
@@ -0,0 +1,90 @@
+use crate::board::{Move, Solution};
+use crate::search::Variant;
+
+// what the variants of the portfolio each made of a deal, side by side. when they can't agree on
+// where to start and their solutions are wildly different lengths, at least one of them is doing
+// something silly on this deal, which makes it a good one to keep in the regression corpus
+
+// how much longer (in percent) the longest solution can be than the shortest before it counts as
+// a strong disagreement
+const LENGTH_SPREAD_PERCENT: usize = 25;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opinion {
+    pub variant: Variant,
+    // None if the variant didn't find a solution
+    pub first_move: Option<Move>,
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub opinions: Vec<Opinion>,
+    // the variants that found something didn't agree on the first move, and their solutions'
+    // lengths were more than LENGTH_SPREAD_PERCENT apart
+    pub disagree: bool,
+}
+
+impl Report {
+    pub fn new(results: &[(Variant, Option<Solution>)]) -> Self {
+        let opinions: Vec<Opinion> = results
+            .iter()
+            .map(|(variant, solution)| Opinion {
+                variant: *variant,
+                first_move: solution
+                    .as_ref()
+                    .and_then(|solution| solution.moves.first().cloned()),
+                length: solution.as_ref().map(|solution| solution.moves.len()),
+            })
+            .collect();
+
+        let solved: Vec<&Opinion> = opinions.iter().filter(|o| o.length.is_some()).collect();
+        let first_moves_differ =
+            solved
+                .windows(2)
+                .any(|pair| match (&pair[0].first_move, &pair[1].first_move) {
+                    (Some(a), Some(b)) => !(a.from.same_place(&b.from) && a.to.same_place(&b.to)),
+                    (a, b) => a.is_some() != b.is_some(),
+                });
+        let lengths = solved.iter().filter_map(|o| o.length);
+        let lengths_differ = match (lengths.clone().min(), lengths.max()) {
+            (Some(shortest), Some(longest)) => {
+                (longest - shortest) * 100 > shortest * LENGTH_SPREAD_PERCENT
+            }
+            _ => false,
+        };
+        Report {
+            opinions,
+            disagree: first_moves_differ && lengths_differ,
+        }
+    }
+
+    // one line per variant, STRATEGY/NUM_PREV_MOVES/HEURISTIC FROM-TO LENGTH for its first move and
+    // its solution's length (- for both if it didn't find one), then AGREE or DISAGREE
+    pub fn serialize(&self) -> String {
+        let mut s = String::new();
+        for opinion in &self.opinions {
+            let first_move = opinion
+                .first_move
+                .as_ref()
+                .map(|moov| format!("{}-{}", moov.from.serialize(), moov.to.serialize()))
+                .unwrap_or_else(|| "-".to_string());
+            let length = opinion
+                .length
+                .map(|length| length.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            s.push_str(&format!(
+                "{} {} {}\n",
+                opinion.variant.serialize(),
+                first_move,
+                length
+            ));
+        }
+        s.push_str(if self.disagree {
+            "DISAGREE\n"
+        } else {
+            "AGREE\n"
+        });
+        s
+    }
+}
@@ -0,0 +1,73 @@
+use crate::card::Suit;
+use crate::warnings::{warn, Warning};
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter};
+
+// things that can't happen on a board that was put together properly, so if one does there's a bug
+// somewhere (or the board was handed to us in a state the game can't get into). each one carries
+// the board's fingerprint (see Board::fingerprint_hex) and where on it things went wrong, which
+// along with the deal from the bug report is enough to play it back and watch it happen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardError {
+    // took a card off a pile with nothing on it
+    EmptyPile {
+        fingerprint: String,
+        pile: usize,
+    },
+    // a minor foundation without even its ace, which it starts the game with
+    EmptyFoundation {
+        fingerprint: String,
+        suit: Suit,
+    },
+    // see Move::validate
+    InvalidMove {
+        fingerprint: String,
+        moov: String,
+        why: &'static str,
+    },
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BoardError::EmptyPile { fingerprint, pile } => {
+                write!(f, "pile {} is empty on board {}", pile, fingerprint)
+            }
+            BoardError::EmptyFoundation { fingerprint, suit } => write!(
+                f,
+                "the {} foundation is empty on board {}",
+                suit.serialize(),
+                fingerprint
+            ),
+            BoardError::InvalidMove {
+                fingerprint,
+                moov,
+                why,
+            } => write!(
+                f,
+                "invalid move {} ({}) on board {}",
+                moov, why, fingerprint
+            ),
+        }
+    }
+}
+
+impl BoardError {
+    pub fn fingerprint(&self) -> &str {
+        match self {
+            BoardError::EmptyPile { fingerprint, .. }
+            | BoardError::EmptyFoundation { fingerprint, .. }
+            | BoardError::InvalidMove { fingerprint, .. } => fingerprint,
+        }
+    }
+
+    // there's no carrying on from one of these: it goes out as a warning, and then we panic (which
+    // writes a bug report if they're on, see bundle.rs)
+    pub fn raise(self) -> ! {
+        warn(Warning::Internal {
+            what: self.to_string(),
+            fingerprint: self.fingerprint().to_string(),
+        });
+        panic!("{}", self)
+    }
+}
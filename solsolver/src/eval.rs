@@ -0,0 +1,56 @@
+use crate::board::{Board, Move};
+use crate::rules::Rules;
+use crate::search::{Heuristic, Search, SearchOptions, SearchStatus, OLD};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winnable {
+    // we found a line that clears the board (it's in best_line_prefix)
+    Likely,
+    // ran out of time before we could say either way
+    Unknown,
+    // we ran out of positions to look at without finding a win
+    Impossible,
+}
+
+#[derive(Debug, Clone)]
+pub struct Eval {
+    pub winnable: Winnable,
+    // the moves leading to the most promising position we saw
+    pub best_line_prefix: Vec<Move>,
+    // number of cards still left to clear at the end of best_line_prefix, lower is better
+    pub score: usize,
+}
+
+// a cheap, time-boxed look at a position, for when we want an answer right now (e.g. an overlay
+// telling a human the position looks lost) rather than a full solve
+//
+// this is a plain greedy best-first search without the last_n_moves pruning, so that running out of
+// positions really does mean there's no way to win from here
+pub fn evaluate(board: &Board, millis: u64) -> Eval {
+    let mut search = Search::new(
+        board,
+        SearchOptions {
+            num_prev_moves: OLD,
+            heuristic: Heuristic::CardsRemaining,
+            rules: Rules::default(),
+        },
+    );
+    let winnable = match search.step(millis) {
+        SearchStatus::Solved(solution) => {
+            return Eval {
+                winnable: Winnable::Likely,
+                best_line_prefix: solution.moves,
+                score: 0,
+            }
+        }
+        SearchStatus::Exhausted if search.proves_dead() => Winnable::Impossible,
+        // --safe-mode forbid kept some moves from it, and the way through could be one of them
+        SearchStatus::Exhausted | SearchStatus::InProgress => Winnable::Unknown,
+    };
+    let (best_line_prefix, score) = search.best_line();
+    Eval {
+        winnable,
+        best_line_prefix,
+        score,
+    }
+}
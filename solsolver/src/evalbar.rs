@@ -0,0 +1,284 @@
+use crate::board::{Board, Solution};
+use crate::eval::{self, Winnable};
+use crate::rules::Rules;
+use crate::session::Verdict;
+use crate::triage;
+use std::fs;
+use std::path::Path;
+
+// a 0 to 100 "how likely is this to be won" for a position, for the overlay's eval bar, the way a
+// chess stream shows the engine's opinion of each move. it starts with the same quick look as
+// triage (see eval.rs), just much shorter. if that settles it, it's 100 or 0. if not, the position
+// goes by a few things about it (the cheap ones of triage's features, how many cards are left, and
+// how close the quick look got to clearing the board) and a Calibration turns those into a chance
+// of winning. triage's relaxed bound would be worth having too, but it takes several times as long
+// as the quick look
+//
+// a calibration is a logistic regression fitted to how batches of deals really went (see fit and
+// calibrate-eval): every position along the solution of a deal that was solved counts as a win,
+// and every position along the best line eval finds on a deal that was proven unsolvable counts as
+// a loss, since nothing that's reachable from there can be won either. deals that ran out of time
+// are left out. it's kept in a toml file like
+//
+//   bias = 39.5421
+//   buried_minors = 0.2250
+//   empty_piles = 0.6936
+//   cards_left = 0.0011
+//   cards_left_after_search = -0.6868
+
+// how long the quick look gets at each position. the overlay looks at every board the session
+// reports while it's thinking, so this has to be short (and a calibration's only good for the
+// quick look it was fitted with)
+pub const EVAL_MS: u64 = 2;
+
+const NUM_INPUTS: usize = 4;
+
+const INPUT_NAMES: [&str; NUM_INPUTS] = [
+    "buried_minors",
+    "empty_piles",
+    "cards_left",
+    "cards_left_after_search",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub bias: f64,
+    // one for each of INPUT_NAMES
+    pub weights: [f64; NUM_INPUTS],
+}
+
+impl Default for Calibration {
+    // calibrate-eval over 30 seeded deals and the EXAMPLE_ ones, 5s each: 514 positions, brier
+    // score 0.056
+    fn default() -> Self {
+        Self {
+            bias: 39.5421,
+            weights: [0.2250, 0.6936, 0.0011, -0.6868],
+        }
+    }
+}
+
+impl Calibration {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let table: toml::Table = s.parse().map_err(|e| format!("{}", e))?;
+        let field = |key: &str| {
+            table
+                .get(key)
+                .and_then(|value| {
+                    value
+                        .as_float()
+                        .or_else(|| value.as_integer().map(|n| n as f64))
+                })
+                .ok_or_else(|| format!("the calibration needs {} (a number)", key))
+        };
+        let mut weights = [0.0; NUM_INPUTS];
+        for (weight, name) in weights.iter_mut().zip(INPUT_NAMES) {
+            *weight = field(name)?;
+        }
+        Ok(Self {
+            bias: field("bias")?,
+            weights,
+        })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut s = format!("bias = {:.4}\n", self.bias);
+        for (name, weight) in INPUT_NAMES.iter().zip(self.weights) {
+            s.push_str(&format!("{} = {:.4}\n", name, weight));
+        }
+        s
+    }
+
+    fn chance(&self, inputs: &[f64; NUM_INPUTS]) -> f64 {
+        let z = self.bias
+            + self
+                .weights
+                .iter()
+                .zip(inputs)
+                .map(|(weight, input)| weight * input)
+                .sum::<f64>();
+        1.0 / (1.0 + (-z).exp())
+    }
+
+    fn percent(&self, inputs: &[f64; NUM_INPUTS]) -> u8 {
+        (self.chance(inputs) * 100.0).round() as u8
+    }
+
+    // 0 to 100, see above
+    pub fn confidence(&self, board: &Board, millis: u64) -> u8 {
+        match inputs(board, millis) {
+            Ok(inputs) => self.percent(&inputs),
+            Err(settled) => settled,
+        }
+    }
+}
+
+// what the calibration goes by, or the confidence if the quick look settled it
+fn inputs(board: &Board, millis: u64) -> Result<[f64; NUM_INPUTS], u8> {
+    let eval = eval::evaluate(board, millis);
+    match eval.winnable {
+        Winnable::Likely => return Err(100),
+        Winnable::Impossible => return Err(0),
+        Winnable::Unknown => {}
+    }
+    let num_empty_piles = board
+        .playing_area
+        .iter()
+        .filter(|pile| pile.is_empty())
+        .count();
+    Ok([
+        triage::num_buried_minors(board) as f64,
+        num_empty_piles as f64,
+        board.num_cards_remaining() as f64,
+        eval.score as f64,
+    ])
+}
+
+fn positions(board: &Board, solution: &Solution, rules: &Rules) -> Vec<Board> {
+    let mut positions = vec![board.clone()];
+    let mut board = board.clone();
+    for moov in &solution.moves {
+        board = board.play(moov.from, moov.to, rules).unwrap().0;
+        positions.push(board.clone());
+    }
+    positions
+}
+
+// a position from a batch and how the deal it's from went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    inputs: [f64; NUM_INPUTS],
+    won: bool,
+}
+
+// how long eval gets to find a line through a deal that's been proven unsolvable, for its
+// positions
+const LOST_LINE_MS: u64 = 200;
+
+// the positions from a deal that was decided in a batch, see above. the ones the quick look settles
+// aren't any use to fit, since the calibration never sees them
+pub fn samples(board: &Board, verdict: &Verdict, rules: &Rules, millis: u64) -> Vec<Sample> {
+    let (positions, won) = match verdict {
+        Verdict::Solved(solution) => (positions(board, solution, rules), true),
+        Verdict::Unsolvable => {
+            let best_line = Solution {
+                moves: eval::evaluate(board, LOST_LINE_MS).best_line_prefix,
+            };
+            (positions(board, &best_line, rules), false)
+        }
+        Verdict::TimedOut => return vec![],
+    };
+    positions
+        .iter()
+        .filter_map(|position| inputs(position, millis).ok())
+        .map(|inputs| Sample { inputs, won })
+        .collect()
+}
+
+const FIT_ITERATIONS: usize = 5000;
+const LEARNING_RATE: f64 = 0.5;
+
+// the logistic regression that best predicts whether the samples were won, and its Brier score on
+// them (the mean squared difference between its chances and what happened, 0 is perfect and
+// always saying 50% gets 0.25). the inputs are standardized for the fit so that one learning rate
+// does for all of them
+pub fn fit(samples: &[Sample]) -> Result<(Calibration, f64), String> {
+    if !samples.iter().any(|sample| sample.won) || samples.iter().all(|sample| sample.won) {
+        return Err("a calibration needs positions from deals that were won and lost".to_string());
+    }
+    let n = samples.len() as f64;
+    let mut means = [0.0; NUM_INPUTS];
+    let mut spreads = [0.0; NUM_INPUTS];
+    for i in 0..NUM_INPUTS {
+        means[i] = samples.iter().map(|sample| sample.inputs[i]).sum::<f64>() / n;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample.inputs[i] - means[i]).powi(2))
+            .sum::<f64>()
+            / n;
+        // an input that's always the same can't tell us anything, and gets no weight
+        spreads[i] = if variance > 0.0 { variance.sqrt() } else { 0.0 };
+    }
+    let standardized: Vec<[f64; NUM_INPUTS]> = samples
+        .iter()
+        .map(|sample| {
+            let mut inputs = [0.0; NUM_INPUTS];
+            for i in 0..NUM_INPUTS {
+                if spreads[i] > 0.0 {
+                    inputs[i] = (sample.inputs[i] - means[i]) / spreads[i];
+                }
+            }
+            inputs
+        })
+        .collect();
+    let mut model = Calibration {
+        bias: 0.0,
+        weights: [0.0; NUM_INPUTS],
+    };
+    for _ in 0..FIT_ITERATIONS {
+        let mut bias_gradient = 0.0;
+        let mut gradients = [0.0; NUM_INPUTS];
+        for (inputs, sample) in standardized.iter().zip(samples) {
+            let error = model.chance(inputs) - if sample.won { 1.0 } else { 0.0 };
+            bias_gradient += error;
+            for (gradient, input) in gradients.iter_mut().zip(inputs) {
+                *gradient += error * input;
+            }
+        }
+        model.bias -= LEARNING_RATE * bias_gradient / n;
+        for (weight, gradient) in model.weights.iter_mut().zip(gradients) {
+            *weight -= LEARNING_RATE * gradient / n;
+        }
+    }
+    // back to the inputs as they are
+    let mut calibration = Calibration {
+        bias: model.bias,
+        weights: [0.0; NUM_INPUTS],
+    };
+    for i in 0..NUM_INPUTS {
+        if spreads[i] > 0.0 {
+            calibration.weights[i] = model.weights[i] / spreads[i];
+            calibration.bias -= model.weights[i] * means[i] / spreads[i];
+        }
+    }
+    let brier = samples
+        .iter()
+        .map(|sample| {
+            let won = if sample.won { 1.0 } else { 0.0 };
+            (calibration.chance(&sample.inputs) - won).powi(2)
+        })
+        .sum::<f64>()
+        / n;
+    Ok((calibration, brier))
+}
+
+// how well the calibration's chances match what happened: the samples split up by the confidence
+// they were given, a tenth of the way at a time, with how many of each were won. a line per tenth
+// that has any, e.g. "60-69: 41 positions, 66% won"
+pub fn reliability(calibration: &Calibration, samples: &[Sample]) -> String {
+    let mut buckets = [(0, 0); 10];
+    for sample in samples {
+        let confidence = calibration.percent(&sample.inputs) as usize;
+        let bucket = &mut buckets[(confidence / 10).min(9)];
+        bucket.0 += 1;
+        bucket.1 += sample.won as usize;
+    }
+    let mut s = String::new();
+    for (i, (num_positions, num_won)) in buckets.into_iter().enumerate() {
+        if num_positions > 0 {
+            s.push_str(&format!(
+                "{}-{}: {} positions, {}% won\n",
+                i * 10,
+                if i == 9 { 100 } else { i * 10 + 9 },
+                num_positions,
+                100 * num_won / num_positions
+            ));
+        }
+    }
+    s
+}
@@ -0,0 +1,18 @@
+use std::io::{stdout, Write};
+use std::thread;
+use std::time::Duration;
+
+// with --heartbeat, a THINKING line on stdout every so often for as long as we're running, so a
+// driver waiting on us through a pty or a pipe can tell a long search from a solver that's hung
+// (--unbuffered only makes sure what we've said gets there, it can't help with a long silence).
+// it's opt in, since a driver has to know to drop the line wherever it turns up (see
+// interactive.rs). it's only ever between the protocol's lines: the conversation writes to stdout
+// through Unbuffered without holding its lock, a whole line at a time, and so does this
+pub fn start(every: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(every);
+        if writeln!(stdout(), "THINKING").is_err() {
+            return;
+        }
+    });
+}
@@ -0,0 +1,263 @@
+use crate::board::Board;
+use crate::compare::Run;
+use crate::eval::Winnable;
+use crate::rules::Rules;
+use crate::triage::{self, Verdict};
+
+// a run over a batch of deals (the JSON compare.rs saves, e.g. from overnight) as one HTML file to
+// pass round, with nothing to install or load from anywhere else. it has:
+// - a summary: how many were solved, and how long the solved ones took
+// - the heatmap: how many deals there were of each difficulty (triage's score, see triage.rs) that
+//   took each long, with the ones that weren't solved in a column of their own
+// - why the ones that weren't solved weren't, as best we can tell after the fact (see Failure)
+// - every deal, hardest first
+//
+// the difficulty's worked out by this build when the report's made, not by whatever made the run
+
+// the rows of the heatmap: triage scores below each of these, and then the rest. the example deals
+// are all between 30 and 60
+const SCORE_BELOW: [i32; 6] = [30, 40, 50, 60, 70, 80];
+
+// the columns: solve times below each of these, and then the rest
+const MS_BELOW: [u64; 4] = [100, 1000, 10_000, 60_000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    // there wasn't a single move to make
+    NoMoves,
+    // eval went through everything from it and there's no way through
+    Lost,
+    // triage would have dealt again
+    Hopeless,
+    // none of the above, the search just didn't find one
+    NotFound,
+}
+
+impl Failure {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Failure::NoMoves => "no moves from the start",
+            Failure::Lost => "no way through",
+            Failure::Hopeless => "triage would have dealt again",
+            Failure::NotFound => "no solution found",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub run: Run,
+    // triage's score, None if the deal wasn't in the directory
+    pub score: Option<i32>,
+    // None if it was solved
+    pub failure: Option<Failure>,
+}
+
+// each deal in the run, with what triage makes of it (if it's in deals, by name)
+pub fn rows(runs: &[Run], deals: &[(String, String)], rules: &Rules) -> Vec<Row> {
+    runs.iter()
+        .map(|run| {
+            let Some((_, deal)) = deals.iter().find(|(name, _)| *name == run.name) else {
+                let failure = (!run.solved).then_some(Failure::NotFound);
+                return Row {
+                    run: run.clone(),
+                    score: None,
+                    failure,
+                };
+            };
+            let mut board = Board::parse(deal);
+            board.suck_readies_into_receptacles();
+            let triage = triage::triage(&board);
+            let failure = match () {
+                _ if run.solved => None,
+                _ if board.next_boards(rules).is_empty() => Some(Failure::NoMoves),
+                _ if triage.winnable == Winnable::Impossible => Some(Failure::Lost),
+                _ if triage.verdict == Verdict::Redeal => Some(Failure::Hopeless),
+                _ => Some(Failure::NotFound),
+            };
+            Row {
+                run: run.clone(),
+                score: Some(triage.score),
+                failure,
+            }
+        })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn bucket<T: PartialOrd>(value: T, below: &[T]) -> usize {
+    below
+        .iter()
+        .position(|limit| value < *limit)
+        .unwrap_or(below.len())
+}
+
+// "&lt; 30", "30 to 40" and so on up to "&ge; 80", a name for each of bucket's buckets
+fn bucket_names<T: Copy>(below: &[T], show: impl Fn(T) -> String) -> Vec<String> {
+    let mut names = vec![format!("&lt; {}", show(below[0]))];
+    for pair in below.windows(2) {
+        names.push(format!("{} to {}", show(pair[0]), show(pair[1])));
+    }
+    names.push(format!("&ge; {}", show(below[below.len() - 1])));
+    names
+}
+
+fn show_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{}s", ms / 1000)
+    }
+}
+
+// the value at p (0 to 1) of the way through, which have to be sorted
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+// white for nothing, up to a deep red for the most
+fn heat(count: usize, max: usize) -> String {
+    if count == 0 {
+        return "#fff".to_string();
+    }
+    let level = count as f64 / max as f64;
+    let fade = (235.0 * (1.0 - level)) as u8 + 20;
+    format!("rgb(220,{},{})", fade, fade)
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+    table{border-collapse:collapse;margin-bottom:2em}\
+    th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+    th{background:#f4f4f4}td.name{text-align:left}";
+
+// the whole report, see above. version is the build that made the run
+pub fn html(rows: &[Row], version: &str) -> String {
+    let mut s = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>solsolver report</title>\
+         <style>{}</style></head><body>\n<h1>{} deals</h1>\n<p>run by {}</p>\n",
+        STYLE,
+        rows.len(),
+        escape(version)
+    );
+
+    let mut solved_ms: Vec<u64> = rows
+        .iter()
+        .filter(|row| row.run.solved)
+        .map(|row| row.run.ms)
+        .collect();
+    solved_ms.sort();
+    s.push_str(&format!("<p>solved {} of {}", solved_ms.len(), rows.len()));
+    if !solved_ms.is_empty() {
+        s.push_str(&format!(
+            ", taking {} in the middle, {} at the 90th percentile and {} at the most",
+            show_ms(percentile(&solved_ms, 0.5)),
+            show_ms(percentile(&solved_ms, 0.9)),
+            show_ms(solved_ms[solved_ms.len() - 1])
+        ));
+    }
+    s.push_str("</p>\n");
+
+    // rows of the heatmap by score, the last one for deals we don't have a score for. columns by
+    // time, the last one for the failures
+    let score_names = bucket_names(&SCORE_BELOW, |score| score.to_string());
+    let ms_names = bucket_names(&MS_BELOW, show_ms);
+    let mut counts = vec![vec![0; ms_names.len() + 1]; score_names.len() + 1];
+    for row in rows {
+        let score_bucket = row
+            .score
+            .map_or(score_names.len(), |score| bucket(score, &SCORE_BELOW));
+        let ms_bucket = if row.run.solved {
+            bucket(row.run.ms, &MS_BELOW)
+        } else {
+            ms_names.len()
+        };
+        counts[score_bucket][ms_bucket] += 1;
+    }
+    let max = counts.iter().flatten().copied().max().unwrap_or(0);
+    s.push_str("<h2>difficulty and time</h2>\n<table><tr><th>triage score</th>");
+    for name in &ms_names {
+        s.push_str(&format!("<th>{}</th>", name));
+    }
+    s.push_str("<th>not solved</th><th>deals</th><th>solved</th></tr>\n");
+    for (name, row_counts) in score_names
+        .iter()
+        .map(String::as_str)
+        .chain(["unknown"])
+        .zip(&counts)
+    {
+        let num_deals: usize = row_counts.iter().sum();
+        if num_deals == 0 {
+            continue;
+        }
+        s.push_str(&format!("<tr><th>{}</th>", name));
+        for &count in row_counts {
+            s.push_str(&format!(
+                "<td style=\"background:{}\">{}</td>",
+                heat(count, max),
+                count
+            ));
+        }
+        let num_solved = num_deals - row_counts[ms_names.len()];
+        s.push_str(&format!(
+            "<td>{}</td><td>{}%</td></tr>\n",
+            num_deals,
+            100 * num_solved / num_deals
+        ));
+    }
+    s.push_str("</table>\n");
+
+    s.push_str(
+        "<h2>why deals weren't solved</h2>\n<table><tr><th>reason</th><th>deals</th></tr>\n",
+    );
+    for failure in [
+        Failure::NoMoves,
+        Failure::Lost,
+        Failure::Hopeless,
+        Failure::NotFound,
+    ] {
+        let names: Vec<String> = rows
+            .iter()
+            .filter(|row| row.failure == Some(failure))
+            .map(|row| escape(&row.run.name))
+            .collect();
+        if !names.is_empty() {
+            s.push_str(&format!(
+                "<tr><td class=\"name\">{}</td><td class=\"name\">{} ({})</td></tr>\n",
+                failure.describe(),
+                names.len(),
+                names.join(", ")
+            ));
+        }
+    }
+    s.push_str("</table>\n");
+
+    let mut hardest_first: Vec<&Row> = rows.iter().collect();
+    hardest_first.sort_by_key(|row| std::cmp::Reverse(row.score));
+    s.push_str(
+        "<h2>every deal</h2>\n<table><tr><th>deal</th><th>triage score</th><th>solved</th>\
+         <th>moves</th><th>time</th></tr>\n",
+    );
+    for row in hardest_first {
+        s.push_str(&format!(
+            "<tr><td class=\"name\">{}</td><td>{}</td><td class=\"name\">{}</td><td>{}</td>\
+             <td>{}</td></tr>\n",
+            escape(&row.run.name),
+            row.score.map_or("?".to_string(), |score| score.to_string()),
+            row.failure.map_or("yes", |failure| failure.describe()),
+            if row.run.solved {
+                row.run.moves.to_string()
+            } else {
+                String::new()
+            },
+            show_ms(row.run.ms)
+        ));
+    }
+    s.push_str("</table>\n</body></html>\n");
+    s
+}
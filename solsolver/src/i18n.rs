@@ -0,0 +1,139 @@
+use crate::card::Suit;
+use crate::reversibility::Reversibility;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// the words in what we print for people (see color.rs), so they can be in something other than
+// English. a translation is a TOML file of the keys below, each a string, e.g.
+//   move = "Karte {card} Stapel {from} -> Stapel {to}"
+//   irreversible = "endgültig"
+//   cup = "Kelche"
+// where anything in braces is filled in. keys that aren't there stay in English, and a key we
+// don't know is an error, so a typo doesn't quietly do nothing. the suits are drawn as glyphs (see
+// color::Glyphs) unless the file names them. none of this touches stdout, the driver reads that
+
+const KEYS: [&str; 17] = [
+    "move",
+    "notes",
+    "notes_timed",
+    "reversible",
+    "hard_to_reverse",
+    "irreversible",
+    "block",
+    "maj_lo",
+    "maj_hi",
+    "ace",
+    "jack",
+    "queen",
+    "king",
+    "sword",
+    "wand",
+    "cup",
+    "star",
+];
+
+fn english(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "move" => "Card {card} Pile {from} -> Pile {to}",
+        "notes" => "({sucks} sucks, {reversibility})",
+        "notes_timed" => "({sucks} sucks, {reversibility}, ~{ms}ms)",
+        "reversible" => "reversible",
+        "hard_to_reverse" => "hard_to_reverse",
+        "irreversible" => "irreversible",
+        "block" => "BLOCK",
+        "maj_lo" => "MAJ_LO",
+        "maj_hi" => "MAJ_HI",
+        "ace" => "A",
+        "jack" => "J",
+        "queen" => "Q",
+        "king" => "K",
+        // drawn as glyphs unless translated
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Messages {
+    translated: HashMap<String, String>,
+}
+
+static MESSAGES: OnceLock<Messages> = OnceLock::new();
+
+// picks the translation for the rest of the run. only the first call counts
+pub fn set(messages: Messages) {
+    let _ = MESSAGES.set(messages);
+}
+
+// English if set hasn't been called
+pub fn messages() -> &'static Messages {
+    MESSAGES.get_or_init(Messages::default)
+}
+
+// the template with each {name} replaced by its value
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |s, (name, value)| {
+            s.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+impl Messages {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let table = s.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        let mut translated = HashMap::new();
+        for (key, value) in table {
+            if !KEYS.contains(&key.as_str()) {
+                return Err(format!("unknown key {}", key));
+            }
+            let Some(value) = value.as_str() else {
+                return Err(format!("{} isn't a string", key));
+            };
+            translated.insert(key, value.to_string());
+        }
+        Ok(Self { translated })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| Self::parse(&s))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // the text for one of KEYS. panics on the suits if they aren't translated, see suit
+    pub fn get(&self, key: &str) -> &str {
+        match self.translated.get(key) {
+            Some(value) => value,
+            None => english(key).unwrap(),
+        }
+    }
+
+    // None if it's up to the glyphs
+    pub fn suit(&self, suit: Suit) -> Option<&str> {
+        let key = match suit {
+            Suit::Sword => "sword",
+            Suit::Wand => "wand",
+            Suit::Cup => "cup",
+            Suit::Star => "star",
+        };
+        self.translated.get(key).map(String::as_str)
+    }
+
+    // the minor values that aren't numbers
+    pub fn minor_value(&self, value: u8) -> String {
+        match value {
+            1 => self.get("ace").to_string(),
+            11 => self.get("jack").to_string(),
+            12 => self.get("queen").to_string(),
+            13 => self.get("king").to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn reversibility(&self, reversibility: Reversibility) -> &str {
+        self.get(reversibility.serialize())
+    }
+}
@@ -0,0 +1,298 @@
+use crate::board::{Board, Protocol, Solution};
+use crate::bundle;
+use crate::card::Card;
+use crate::rules::Rules;
+use crate::unknown::{self, Plan};
+use std::io::{self, BufRead, Write};
+
+// a conversation with the driver over stdin/stdout, one message per line, so we can ask it things
+// partway through instead of having to be given everything up front
+//
+// driver to us:
+//   DEAL               followed by the board, in the same format as stdin usually is, then END
+//                      (session.rs uses this part too)
+//   CARD <card>        the answer to a PROBE, e.g. CARD 10_CUP, or CARD ? if it still can't tell
+//   NEXT               carry on with the next chunk of moves (only with chunking, see send_moves)
+//   OK                 played the last MOVE, and the board's what we said it would be (only with
+//                      acks, see Pacing)
+//   FAIL               the last MOVE didn't go the way we said, followed by the board as it is
+//                      now, then END. we plan again from there, probing for any cards it has
+//                      as ? just like a DEAL (session.rs can't, and turns them away)
+//   STOP               don't bother with the rest of the moves, there'll be a new DEAL instead
+//   QUIT
+// us to driver:
+//   PROBE <pile:depth> what's the card here? we wait for a CARD before going on
+//   MOVE <move>        in whichever protocol we were asked for, one per move
+//   CHUNK <fingerprint> the end of a chunk of moves, and Board::fingerprint_hex of where they
+//                      should leave the board. we wait for NEXT or STOP before going on
+//   DONE               that's every move
+//   REREAD <pile:depth>,... instead of DONE: the moves are as far as we can go without knowing the
+//                      cards at these places, and every PROBE of them came back ?. send the DEAL
+//                      again once they can be read
+//   NO_SOLUTION
+//   THINKING           with --heartbeat, every so often whatever else is going on. it means nothing
+//                      and can come between any two lines
+
+// how the moves go out. all at once is fine for a driver that reads the lot and then plays them,
+// but a long solution can fill its buffer before it's read any, and it can't tell us it's gone off
+// the plan until the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pacing {
+    All,
+    // this many moves then a CHUNK, and wait for NEXT
+    Chunks(usize),
+    // one move at a time, and wait for OK or FAIL after each
+    Ack,
+}
+
+// how sending the moves went
+#[derive(Debug)]
+pub enum Sent {
+    Done,
+    // the driver QUIT (or went away)
+    Quit,
+    // a FAIL, and the board the driver's got instead, as it sent it. like a DEAL it can have cards
+    // it couldn't make out in it (see unknown.rs)
+    Diverged(String),
+}
+
+// reads the lines up to END. None if the driver's gone or said QUIT
+pub fn read_deal(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    loop {
+        match read_line(input)?.as_deref() {
+            None | Some("QUIT") => return Ok(None),
+            Some("DEAL") => break,
+            Some("") => continue,
+            Some(other) => return Err(unexpected(other, "DEAL")),
+        }
+    }
+    read_board(input)
+}
+
+// the lines after a DEAL or a FAIL, up to END
+fn read_board(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut deal = String::new();
+    loop {
+        match read_line(input)? {
+            Some(line) if line == "END" => return Ok(Some(deal)),
+            Some(line) => {
+                deal.push_str(&line);
+                deal.push('\n');
+            }
+            None => return Ok(None),
+        }
+    }
+}
+
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn unexpected(line: &str, expected: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("expected {}, got {:?}", expected, line),
+    )
+}
+
+// asks about the card at (pile, depth). None if the driver couldn't make it out either
+fn probe(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    (pile, depth): (usize, usize),
+) -> io::Result<Option<Card>> {
+    writeln!(output, "PROBE {}:{}", pile, depth)?;
+    output.flush()?;
+    let line = read_line(input)?.unwrap_or_default();
+    match line.strip_prefix("CARD ") {
+        Some(unknown::UNKNOWN) => Ok(None),
+        Some(card) => Ok(Some(Card::parse(card.trim()))),
+        None => Err(unexpected(&line, "CARD")),
+    }
+}
+
+// after a MOVE with acks
+fn read_ack(input: &mut impl BufRead) -> io::Result<Option<Sent>> {
+    match read_line(input)?.as_deref() {
+        Some("OK") => Ok(None),
+        Some("STOP") => Ok(Some(Sent::Done)),
+        None | Some("QUIT") => Ok(Some(Sent::Quit)),
+        Some("FAIL") => Ok(Some(match read_board(input)? {
+            Some(observed) => Sent::Diverged(observed),
+            None => Sent::Quit,
+        })),
+        Some(other) => Err(unexpected(other, "OK, FAIL, STOP or QUIT")),
+    }
+}
+
+// sends the moves, paced however the driver asked (see Pacing). with chunks there's a CHUNK after
+// every chunk_size of them so the driver can play that much, check it got where we thought it
+// would, and ask for more. with acks we wait to hear each one's been played before sending the
+// next, and stop at the first FAIL so the caller can plan again from where the driver really is
+pub fn send_moves(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    board: &Board,
+    solution: &Solution,
+    rules: &Rules,
+    protocol: Protocol,
+    pacing: Pacing,
+) -> io::Result<Sent> {
+    send_moves_then(
+        input, output, board, solution, rules, protocol, pacing, "DONE",
+    )
+}
+
+// send_moves, with last instead of DONE at the end
+#[allow(clippy::too_many_arguments)]
+fn send_moves_then(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    board: &Board,
+    solution: &Solution,
+    rules: &Rules,
+    protocol: Protocol,
+    pacing: Pacing,
+    last: &str,
+) -> io::Result<Sent> {
+    let chunk_size = match pacing {
+        Pacing::All => usize::MAX,
+        Pacing::Chunks(chunk_size) => chunk_size,
+        Pacing::Ack => 1,
+    };
+    let mut board = board.clone();
+    for (i, chunk) in solution.moves.chunks(chunk_size).enumerate() {
+        if i > 0 && matches!(pacing, Pacing::Chunks(_)) {
+            output.flush()?;
+            match read_line(input)?.as_deref() {
+                Some("NEXT") => {}
+                Some("STOP") => return Ok(Sent::Done),
+                None | Some("QUIT") => return Ok(Sent::Quit),
+                Some(other) => return Err(unexpected(other, "NEXT, STOP or QUIT")),
+            }
+        }
+        for moov in chunk {
+            writeln!(output, "MOVE {}", moov.serialize(protocol))?;
+            board = board.play(moov.from, moov.to, rules).unwrap().0;
+        }
+        match pacing {
+            Pacing::All => {}
+            Pacing::Chunks(_) => writeln!(output, "CHUNK {}", board.fingerprint_hex())?,
+            Pacing::Ack => {
+                output.flush()?;
+                if let Some(sent) = read_ack(input)? {
+                    return Ok(sent);
+                }
+            }
+        }
+    }
+    writeln!(output, "{}", last)?;
+    Ok(Sent::Done)
+}
+
+// a board the driver sent that has to be all there, with the readies sucked in. there's no asking
+// about the cards it couldn't make out outside of run, so a board with any is turned away
+pub fn parse_known(deal: &str) -> io::Result<Board> {
+    if !unknown::unknown_positions(deal).is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "there are cards we can't see in the board, and only interactive can ask about them",
+        ));
+    }
+    let mut board = Board::parse(deal);
+    board.suck_readies_into_receptacles();
+    Ok(board)
+}
+
+// plans a board the driver sent, probing for unknown cards until every way of filling in the rest
+// gives the same solution (see unknown::plan)
+fn plan_probing(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    mut deal: String,
+    solve: &dyn Fn(&Board) -> Option<Solution>,
+    rules: &Rules,
+) -> io::Result<Option<Plan>> {
+    let mut unsure = unknown::unknown_positions(&deal);
+    // the ones the driver couldn't make out either, which there's no point asking about again
+    let mut unreadable = vec![];
+    loop {
+        let plan = unknown::plan(&deal, solve, rules);
+        match plan {
+            Some(plan) if !plan.reread.is_empty() && !unsure.is_empty() => {
+                // ask about the cards the plan's held up on first, then the rest in the order they
+                // are in the deal
+                let i = plan
+                    .reread
+                    .iter()
+                    .find_map(|position| unsure.iter().position(|other| other == position))
+                    .unwrap_or(0);
+                let position = unsure.remove(i);
+                match probe(input, output, position)? {
+                    Some(card) => {
+                        deal = unknown::resolve(&deal, position, card);
+                        unsure = unknown::unknown_positions(&deal);
+                        unsure.retain(|position| !unreadable.contains(position));
+                    }
+                    None => unreadable.push(position),
+                }
+            }
+            plan => return Ok(plan),
+        }
+    }
+}
+
+// works out a deal (see plan_probing), then sends the moves. a FAIL gets the same treatment as a
+// new deal, probes and all, from the board the driver's got to. goes round again for the next
+// DEAL, until QUIT
+pub fn run(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    solve: &dyn Fn(&Board) -> Option<Solution>,
+    rules: &Rules,
+    protocol: Protocol,
+    pacing: Pacing,
+) -> io::Result<()> {
+    while let Some(mut deal) = read_deal(input)? {
+        bundle::note_deal(&deal);
+        // round again from wherever the driver says it got to, until it gets to the end
+        loop {
+            let Some(plan) = plan_probing(input, output, deal, solve, rules)? else {
+                writeln!(output, "NO_SOLUTION")?;
+                break;
+            };
+            // the driver couldn't tell us some of the cards the plan needs, so it only goes so far
+            let last = if plan.reread.is_empty() {
+                "DONE".to_string()
+            } else {
+                let positions: Vec<String> = plan
+                    .reread
+                    .iter()
+                    .map(|(pile, depth)| format!("{}:{}", pile, depth))
+                    .collect();
+                format!("REREAD {}", positions.join(","))
+            };
+            let sent = send_moves_then(
+                input,
+                output,
+                &plan.board,
+                &plan.solution,
+                rules,
+                protocol,
+                pacing,
+                &last,
+            )?;
+            match sent {
+                Sent::Done => break,
+                Sent::Quit => return Ok(()),
+                Sent::Diverged(observed) => deal = observed,
+            }
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
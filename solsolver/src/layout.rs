@@ -0,0 +1,161 @@
+use crate::board::{Move, MoveLocation};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// where the driver clicks on the game's screen, for --safe-mode. one stray click on the new game
+// button has thrown away a whole night's run before, so moves whose drop lands within a margin of
+// a button can be avoided, or left out altogether. the positions are the game screen's own pixels,
+// worked out the same way as zacdetect/main.py's convert_stack_pos_to_game_screen_px, and live in
+// a toml file (see layout.toml)
+
+// how many moves' worth a risky move costs the search with SafeMode::Avoid. with --heuristic
+// cards-remaining, that's as many cards left on the board
+pub const AVOID_PENALTY: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeMode {
+    // a risky move costs AVOID_PENALTY more, so the search goes another way when there's one that's
+    // not much longer
+    Avoid,
+    // a risky move is never made, even if that leaves the deal unsolvable
+    Forbid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub to_top_of_stacks_px: f64,
+    pub stack_width_px: f64,
+    // how much of each card shows below the one on top of it
+    pub card_top_height_px: f64,
+    pub card_total_height_px: f64,
+    // the gap to the left of each pile, the first being from the edge of the screen
+    pub gaps_until_next_stack: Vec<f64>,
+    pub block: (f64, f64),
+    // (what it is, where it is) of everything we mustn't click by mistake
+    pub dangers: Vec<(String, (f64, f64))>,
+    // how close to one of the dangers is too close
+    pub margin_px: f64,
+}
+
+fn number(value: &toml::Value) -> Option<f64> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|n| n as f64))
+}
+
+fn point(value: &toml::Value) -> Option<(f64, f64)> {
+    match value.as_array()?.as_slice() {
+        [x, y] => Some((number(x)?, number(y)?)),
+        _ => None,
+    }
+}
+
+impl Layout {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let table: toml::Table = s.parse().map_err(|e| format!("{}", e))?;
+        let field = |key: &str| {
+            table
+                .get(key)
+                .and_then(number)
+                .ok_or_else(|| format!("the layout needs {} (a number)", key))
+        };
+        let gaps_until_next_stack = table
+            .get("gaps_until_next_stack")
+            .and_then(toml::Value::as_array)
+            .and_then(|gaps| gaps.iter().map(number).collect::<Option<Vec<_>>>())
+            .ok_or("the layout needs gaps_until_next_stack (a list of numbers)")?;
+        let block = table
+            .get("block")
+            .and_then(point)
+            .ok_or("the layout needs block ([x, y])")?;
+        let dangers = table
+            .get("danger")
+            .and_then(toml::Value::as_array)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|danger| {
+                let name = danger.get("name").and_then(toml::Value::as_str);
+                let at = danger.get("at").and_then(point);
+                match (name, at) {
+                    (Some(name), Some(at)) => Ok((name.to_string(), at)),
+                    _ => Err("each [[danger]] needs a name and where it's at ([x, y])".to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            to_top_of_stacks_px: field("to_top_of_stacks_px")?,
+            stack_width_px: field("stack_width_px")?,
+            card_top_height_px: field("card_top_height_px")?,
+            card_total_height_px: field("card_total_height_px")?,
+            gaps_until_next_stack,
+            block,
+            dangers,
+            margin_px: field("margin_px")?,
+        })
+    }
+
+    // the middle of the card at a location, where the driver grabs it or lets go. None for the
+    // foundations, which the driver never clicks
+    pub fn click(&self, location: &MoveLocation) -> Option<(f64, f64)> {
+        match *location {
+            MoveLocation::BlockMinorPiles => Some(self.block),
+            MoveLocation::PlayingArea { pile, depth } => {
+                let gaps: f64 = self.gaps_until_next_stack.iter().take(pile + 1).sum();
+                let x = gaps + self.stack_width_px * pile as f64 + self.stack_width_px / 2.0;
+                let y = self.to_top_of_stacks_px
+                    + depth as f64 * self.card_top_height_px
+                    + self.card_total_height_px / 2.0;
+                Some((x, y))
+            }
+            MoveLocation::MajorLower
+            | MoveLocation::MajorHigher
+            | MoveLocation::MinorFoundation(_) => None,
+        }
+    }
+
+    // the danger the move's drop is too close to, if any
+    pub fn danger(&self, moov: &Move) -> Option<&str> {
+        let (x, y) = self.click(&moov.to)?;
+        self.dangers
+            .iter()
+            .find(|(_, (danger_x, danger_y))| (x - danger_x).hypot(y - danger_y) < self.margin_px)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+static INSTALLED: OnceLock<(Layout, SafeMode)> = OnceLock::new();
+
+// makes the searches steer clear of the dangers from now on, for --safe-mode
+pub fn install(layout: Layout, mode: SafeMode) {
+    if INSTALLED.set((layout, mode)).is_err() {
+        panic!("there's already a layout installed");
+    }
+}
+
+pub fn installed() -> bool {
+    INSTALLED.get().is_some()
+}
+
+// whether there are moves the searches aren't allowed to make, so running out of boards doesn't
+// mean there's no solution
+pub fn forbidding() -> bool {
+    matches!(INSTALLED.get(), Some((_, SafeMode::Forbid)))
+}
+
+// what a move costs the search on top of the usual: None if it mustn't be made at all
+pub fn penalty(moov: &Move) -> Option<usize> {
+    let Some((layout, mode)) = INSTALLED.get() else {
+        return Some(0);
+    };
+    match (layout.danger(moov), mode) {
+        (None, _) => Some(0),
+        (Some(_), SafeMode::Avoid) => Some(AVOID_PENALTY),
+        (Some(_), SafeMode::Forbid) => None,
+    }
+}
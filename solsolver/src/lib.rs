@@ -0,0 +1,105 @@
+#![feature(variant_count)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod alternatives;
+pub mod board;
+#[cfg(feature = "bot")]
+pub mod bot;
+#[cfg(feature = "std")]
+pub mod bundle;
+pub mod card;
+#[cfg(feature = "std")]
+pub mod certificate;
+#[cfg(feature = "std")]
+pub mod closed;
+#[cfg(feature = "std")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod compare;
+pub mod confidence;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod culprits;
+#[cfg(feature = "std")]
+pub mod decompose;
+#[cfg(feature = "std")]
+pub mod dedup;
+#[cfg(feature = "std")]
+pub mod differential;
+#[cfg(feature = "std")]
+pub mod drill;
+pub mod encoding;
+#[cfg(feature = "std")]
+pub mod ensemble;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod eval;
+#[cfg(feature = "std")]
+pub mod evalbar;
+#[cfg(feature = "std")]
+pub mod heartbeat;
+#[cfg(feature = "std")]
+pub mod heatmap;
+#[cfg(feature = "std")]
+pub mod i18n;
+#[cfg(feature = "std")]
+pub mod interactive;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod memo;
+#[cfg(feature = "std")]
+pub mod minimize;
+#[cfg(feature = "std")]
+pub mod nogoods;
+#[cfg(feature = "std")]
+pub mod ordering;
+#[cfg(feature = "overlay")]
+pub mod overlay;
+pub mod partial_order;
+#[cfg(feature = "std")]
+pub mod provenance;
+#[cfg(feature = "std")]
+pub mod race;
+pub mod reconcile;
+#[cfg(feature = "std")]
+pub mod relaxed;
+#[cfg(feature = "std")]
+pub mod retrograde;
+pub mod reversibility;
+pub mod rules;
+#[cfg(feature = "std")]
+pub mod rules_doc;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "std")]
+pub mod seen;
+#[cfg(feature = "std")]
+pub mod session;
+pub mod shape;
+pub mod share;
+#[cfg(feature = "std")]
+pub mod shorten;
+#[cfg(feature = "std")]
+pub mod shuffle;
+#[cfg(feature = "std")]
+pub mod stuck;
+#[cfg(feature = "std")]
+pub mod tablebase;
+#[cfg(feature = "std")]
+pub mod timing;
+#[cfg(feature = "std")]
+pub mod treesize;
+#[cfg(feature = "std")]
+pub mod triage;
+#[cfg(feature = "std")]
+pub mod unbuffered;
+#[cfg(feature = "std")]
+pub mod unknown;
+pub mod warnings;
@@ -1,597 +1,1850 @@
-#![feature(variant_count)]
-#![feature(const_option)]
-#![feature(const_for)]
-
-use cap::Cap;
-use derivative::Derivative;
-use pathfinding::prelude::astar;
-use rayon::prelude::*;
-use std::alloc;
-use std::collections::VecDeque;
-use std::fmt::{Debug, Display, Formatter};
-use std::hash::Hash;
-use std::io::{stdin, Read};
-
-// TODO: can we query how much memory's on the machine?
-const MEMORY_LIMIT_BYTES: usize = 8 * 1024 * 1024 * 1024;
-
-// HAX: sometimes we're not actually able to solve the position, i don't know why. but if we limit
-// the memory usage of the global allocator, we can more gracefully exit without taking down the
-// rest of the system
-//
-// there's something over on the python side that'll just restart the whole game and try to solve
-// a new position if we exit with a non-zero exit code, which is what happens when this Cap limited
-// global allocator runs out of memory
-#[global_allocator]
-static ALLOCATOR: Cap<alloc::System> = Cap::new(alloc::System, MEMORY_LIMIT_BYTES);
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum MoveLocation {
-    BlockMinorPiles,
-    PlayingArea { pile: usize, depth: usize },
-}
-
-impl Display for MoveLocation {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MoveLocation::BlockMinorPiles => f.write_str("BLOCK"),
-            MoveLocation::PlayingArea { pile, depth: _ } => Display::fmt(pile, f),
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use solsolver::alternatives;
+use solsolver::board::{Board, Move, Protocol, Solution, PARANOID, TIME_SUCKS};
+#[cfg(feature = "bot")]
+use solsolver::bot;
+use solsolver::bundle;
+use solsolver::card;
+use solsolver::closed;
+use solsolver::color;
+use solsolver::compare;
+use solsolver::confidence;
+use solsolver::config;
+use solsolver::corpus::{self, CorpusEntry};
+use solsolver::culprits::{self, PostMortem};
+use solsolver::dedup::{self, Claim};
+use solsolver::drill::{self, Tactic};
+use solsolver::ensemble::Report;
+use solsolver::error::BoardError;
+use solsolver::evalbar;
+use solsolver::heartbeat;
+use solsolver::heatmap;
+use solsolver::i18n::{self, fill, Messages};
+use solsolver::interactive::{self, Pacing};
+use solsolver::layout::{self, Layout, SafeMode};
+use solsolver::memo;
+use solsolver::minimize;
+use solsolver::ordering::{self, MoveOrder};
+#[cfg(feature = "overlay")]
+use solsolver::overlay::Overlay;
+use solsolver::partial_order;
+use solsolver::provenance;
+use solsolver::reconcile;
+use solsolver::relaxed::RelaxedBounds;
+use solsolver::retrograde;
+use solsolver::reversibility;
+use solsolver::rules::Rules;
+use solsolver::rules_doc;
+use solsolver::search::{self, Goal, Heuristic, SearchOptions};
+use solsolver::seen::SeenDeals;
+use solsolver::session::{self, SessionOptions};
+use solsolver::shape;
+use solsolver::share::ShareCode;
+use solsolver::shorten;
+use solsolver::shuffle;
+use solsolver::stuck;
+use solsolver::tablebase::{self, Tablebase};
+use solsolver::timing::{self, TimingProfile};
+use solsolver::triage::{self, Verdict};
+use solsolver::unbuffered::{self, Unbuffered};
+use solsolver::unknown;
+use solsolver::warnings::{self, Warning};
+use std::cell::RefCell;
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, stdin, stdout, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// for running in the background on the same machine as the game, which drops frames (and then the
+// screen reading goes wrong) if we hog the CPU. these get set on the main thread before we start
+// any others, and every thread we start afterwards picks them up
+mod priority {
+    // like nice(1), higher is lower priority
+    pub fn set_niceness(niceness: i32) {
+        let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, niceness) };
+        if result != 0 {
+            panic!(
+                "couldn't set niceness to {}: {}",
+                niceness,
+                std::io::Error::last_os_error()
+            );
         }
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Move {
-    from: MoveLocation,
-    to: MoveLocation,
-    card: Card,
-    // we count the number of sucks, so that in the GUI automation side, we know how long
-    // to wait before the next move
-    num_sucks: usize,
-}
+    #[cfg(target_os = "linux")]
+    pub fn pin_to_cpus(cpus: &[usize]) {
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            for &cpu in cpus {
+                libc::CPU_SET(cpu, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                panic!(
+                    "couldn't pin to cpus {:?}: {}",
+                    cpus,
+                    std::io::Error::last_os_error()
+                );
+            }
+        }
+    }
 
-impl Display for Move {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Card ")?;
-        Display::fmt(&self.card, f)?;
-        f.write_str(" Pile ")?;
-        Display::fmt(&self.from, f)?;
-        f.write_str(" -> Pile ")?;
-        Display::fmt(&self.to, f)
+    #[cfg(not(target_os = "linux"))]
+    pub fn pin_to_cpus(_cpus: &[usize]) {
+        panic!("pinning to cpus is only supported on linux");
     }
 }
 
-impl Move {
-    fn serialize(&self) -> String {
-        format!(
-            "{}-{}@{}@{}",
-            self.from.serialize(),
-            self.to.serialize(),
-            self.num_sucks,
-            self,
-        )
+#[cfg(feature = "memcap")]
+mod memcap {
+    use cap::Cap;
+    use solsolver::bundle;
+    use solsolver::warnings::{self, Warning};
+    use std::alloc;
+    use std::thread;
+    use std::time::Duration;
+
+    // TODO: can we query how much memory's on the machine?
+    const MEMORY_LIMIT_BYTES: usize = 8 * 1024 * 1024 * 1024;
+    const BYTES_PER_GB: usize = 1024 * 1024 * 1024;
+
+    // HAX: sometimes we're not actually able to solve the position, i don't know why. but if we
+    // limit the memory usage of the global allocator, we can more gracefully exit without taking
+    // down the rest of the system
+    //
+    // there's something over on the python side that'll just restart the whole game and try to
+    // solve a new position if we exit with a non-zero exit code, which is what happens when this
+    // Cap limited global allocator runs out of memory
+    #[global_allocator]
+    static ALLOCATOR: Cap<alloc::System> = Cap::new(alloc::System, MEMORY_LIMIT_BYTES);
+
+    // for --memory-limit-gb, before we've allocated much
+    pub fn set_limit_gb(gb: usize) {
+        if ALLOCATOR.set_limit(gb * BYTES_PER_GB).is_err() {
+            panic!("already using more than {}GB", gb);
+        }
+    }
+
+    // warns (once) when we get close to the memory cap, so that if we do hit it there's some
+    // record of why we went down
+    pub fn watch_memory() {
+        let limit_bytes = ALLOCATOR.limit();
+        let warn_at_bytes = limit_bytes / 10 * 8;
+        thread::spawn(move || loop {
+            let allocated_bytes = ALLOCATOR.allocated();
+            if allocated_bytes > warn_at_bytes {
+                warnings::warn(Warning::MemoryHigh {
+                    allocated_bytes,
+                    limit_bytes,
+                });
+                bundle::report("memory high");
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        });
     }
 }
 
-impl MoveLocation {
-    fn serialize(&self) -> String {
-        match self {
-            MoveLocation::BlockMinorPiles => "BLOCK".to_string(),
-            MoveLocation::PlayingArea { pile, depth } => format!("{}:{}", pile, depth),
+// any of these can also be set in the environment or solsolver.toml, see config.rs
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Read settings from this file rather than solsolver.toml
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Print the settings in effect, and where each one came from, and exit
+    #[arg(long)]
+    print_config: bool,
+
+    /// Give up (exit non-zero) rather than use more memory than this, when built with memcap
+    #[arg(long, default_value_t = 8)]
+    memory_limit_gb: usize,
+
+    /// Also race the majors-then-minors decomposition alongside the full search
+    #[arg(long)]
+    two_phase: bool,
+
+    /// Clear groups of piles that never interact with each other separately
+    #[arg(long)]
+    decompose: bool,
+
+    /// Also race a search that breaks ties at random and keeps starting again, for deals where
+    /// the usual search gets unlucky (--seed makes it repeatable)
+    #[arg(long)]
+    restarts: bool,
+
+    /// What the search steers by
+    #[arg(long, value_enum, default_value_t = HeuristicArg::CardsRemaining)]
+    heuristic: HeuristicArg,
+
+    /// Only look for solutions this many moves long or shorter (say, the length of one found
+    /// before), leaving out every board that couldn't be on one. only with --heuristic relaxed,
+    /// the other doesn't count moves
+    #[arg(long)]
+    upper_bound: Option<usize>,
+
+    /// Settle for a solution up to 1 + this times as long as the shortest, for a lot less
+    /// searching, and say how close it's guaranteed to be on stderr. only with --heuristic relaxed
+    #[arg(long)]
+    epsilon: Option<f64>,
+
+    /// Write a certificate that nothing shorter than the solution exists (or how short something
+    /// could be, at least) to this file: how many boards the search went through and the bound it
+    /// stopped at, for anyone to check. only with --heuristic relaxed, see certificate.rs
+    #[arg(
+        long,
+        conflicts_with_all = ["epsilon", "hash_only_closed", "canonical_suits", "game_auto_completes"]
+    )]
+    certificate: Option<PathBuf>,
+
+    /// Remember the boards the search has been to by fingerprint only, for about half the memory
+    /// and a small chance of skipping a board that only looks like one we've seen, see closed.rs
+    #[arg(long)]
+    hash_only_closed: bool,
+
+    /// With --hash-only-closed, keep a second checksum of each board to catch the fingerprints
+    /// that collide, and say how many there were on stderr
+    #[arg(long, requires = "hash_only_closed")]
+    check_collisions: bool,
+
+    /// Count a board as seen if it's one the search has been to with the minor suits renamed, so
+    /// boards that only differ by which suit's which are only searched once, see closed.rs
+    #[arg(long)]
+    canonical_suits: bool,
+
+    /// Remember what we found out about up to this many boards (the rest of a solution, or that
+    /// there isn't one) for the rest of the run, and say how often it helped on stderr, see memo.rs
+    #[arg(long, default_value_t = 0)]
+    memo_size: usize,
+
+    /// Colour the cards in what we print for people by suit: auto does when stderr's a terminal and
+    /// NO_COLOR isn't set
+    #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+
+    /// Draw the suits in what we print for people as emoji, or as the letters S, W, C and T for
+    /// terminals that get the emoji's widths wrong
+    #[arg(long, value_enum, default_value_t = GlyphsArg::Emoji)]
+    glyphs: GlyphsArg,
+
+    /// Print the moves for people in another language: a TOML file of translations, see i18n.rs
+    #[arg(long)]
+    messages: Option<PathBuf>,
+
+    /// Write the suits as S, W, C and T instead of emoji everywhere, including the human readable
+    /// end of each move on stdout
+    #[arg(long)]
+    ascii: bool,
+
+    /// Flush stdout after every line, for a driver on the other end of a pty or a pipe that
+    /// shouldn't have to wait for a buffer to fill
+    #[arg(long)]
+    unbuffered: bool,
+
+    /// Version of the line-per-move output format
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=3))]
+    protocol: u8,
+
+    /// Allow moving cards onto the foundations by hand, for game variants that let you
+    #[arg(long)]
+    explicit_foundation_moves: bool,
+
+    /// The game clears the board by itself at the end, so stop the solution where it takes over
+    #[arg(long)]
+    game_auto_completes: bool,
+
+    /// Never put a card on the block
+    #[arg(long)]
+    no_block: bool,
+
+    /// Cards aren't collected by themselves, sending each one home is a move that can be put off
+    #[arg(long)]
+    optional_sucks: bool,
+
+    /// Like --optional-sucks, but only the majors wait to be sent home, the minors still go by
+    /// themselves
+    #[arg(long)]
+    optional_major_sucks: bool,
+
+    /// What to solve for: clear, cards:N (get N more cards home) or majors-only
+    #[arg(long, default_value = "clear")]
+    goal: Goal,
+
+    /// Reorder the solution to finish one pile before starting another, where that costs nothing
+    #[arg(long)]
+    human_style: bool,
+
+    /// Write the heuristic's value before and after each move to this file, one move per line
+    #[arg(long)]
+    trajectory: Option<PathBuf>,
+
+    /// Write which earlier moves each move has to wait for to this file, one move per line (0
+    /// based, comma separated), so a driver can overlap the ones that don't
+    #[arg(long)]
+    dependencies: Option<PathBuf>,
+
+    /// Write how easily each move could be taken back to this file, one move per line: reversible,
+    /// hard_to_reverse, or irreversible (it sent cards home)
+    #[arg(long)]
+    reversibility: Option<PathBuf>,
+
+    /// Write the two best other moves from the board before each move to this file, one move per
+    /// line (comma separated FROM-TO@ESTIMATE@LOOKAHEAD@HUMAN_READABLE, best first), to play
+    /// instead when the screen doesn't quite match
+    #[arg(long)]
+    alternatives: Option<PathBuf>,
+
+    /// How many threads to race the search variants on, 0 for one per core
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+
+    /// Lower our scheduling priority by this much, like nice(1)
+    #[arg(long)]
+    nice: Option<i32>,
+
+    /// Only run on these cores (comma separated), so there's some left over for the game
+    #[arg(long, value_delimiter = ',')]
+    cpus: Vec<usize>,
+
+    /// Append each move to this file as it's printed, synced to disk, along with the fingerprint of
+    /// the board it should leave behind, so we can tell how far we got after a crash
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
+    /// If the deal can't be solved, try swapping the cards the screen reader was least sure of
+    /// (see confidence.rs) for the cards that are missing from the deal
+    #[arg(long)]
+    try_substitutions: bool,
+
+    /// With unknown cards in the deal, sample this many ways they could be and give just the one
+    /// move that does best on average, instead of solving every way they could be
+    #[arg(long)]
+    sample: Option<usize>,
+
+    /// Print where each search spent its time to stderr: boards expanded per depth and per
+    /// estimate, the branching factor, and how long each phase of expanding a board took
+    #[arg(long)]
+    profile_search: bool,
+
+    /// Print the first this many boards each search expands to stderr, with their g, h and f and
+    /// what became of each move from them, to follow the search step by step
+    #[arg(long)]
+    trace_expansions: Option<usize>,
+
+    /// If there's no solution within this many milliseconds, send the first move of the most
+    /// promising line so far and work out the rest while it's being played
+    #[arg(long)]
+    first_move_deadline: Option<u64>,
+
+    /// Finish boards with this many cards left (or fewer) in the fewest moves there are, and don't
+    /// search past ones that can't be finished. worked out the first time they come up, which gets
+    /// slow fast: 20 is fine, 30 isn't
+    #[arg(long)]
+    tablebase: Option<usize>,
+
+    /// Keep what --tablebase works out in this file, to start from next time
+    #[arg(long, requires = "tablebase")]
+    tablebase_file: Option<PathBuf>,
+
+    /// Sanity check every generated move, even in release builds
+    #[arg(long)]
+    paranoid: bool,
+
+    /// Write warnings (as JSON lines) to this file instead of stderr
+    #[arg(long)]
+    warnings: Option<PathBuf>,
+
+    /// If we panic, run low on memory or (in a session) run out of time, write everything needed to
+    /// look into it to a file in this directory
+    #[arg(long)]
+    bug_report_dir: Option<PathBuf>,
+
+    /// Deal the game with this seed instead of reading the deal from stdin. our own shuffle, not
+    /// the game's, so only for deals that came from here (e.g. drill), see shuffle.rs
+    #[arg(long)]
+    from_seed: Option<u64>,
+
+    /// Seed for anything random (sampling unknown cards, drill's deals), for running something
+    /// again the same way
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Add the deal and the length of its solution to the regression corpus in this directory
+    #[arg(long)]
+    record_corpus: Option<PathBuf>,
+
+    /// Write what each variant of the portfolio made of the deal to this file. deals they disagree
+    /// on go into --record-corpus with a name starting disagreement-
+    #[arg(long)]
+    ensemble_report: Option<PathBuf>,
+
+    /// How fast the game plays moves out (a table in --timing-file). with this we go for the
+    /// solution that's quickest to play rather than the one with the fewest moves
+    #[arg(long)]
+    timing_profile: Option<String>,
+
+    /// Write the solution to this file as a recording: when to start each move, going by
+    /// --timing-profile, see TimingProfile::recording
+    #[arg(long, requires = "timing_profile")]
+    recording: Option<PathBuf>,
+
+    /// Where the timing profiles are
+    #[arg(long, default_value = "timing.toml")]
+    timing_file: PathBuf,
+
+    /// Take a lock on the deal in this directory while solving it, and if another solver already
+    /// has, wait for its solution rather than working it out again, see dedup.rs
+    #[arg(long)]
+    lock_dir: Option<PathBuf>,
+
+    /// Steer clear of moves that drop a card near one of the game's buttons: avoid them where
+    /// there's a way round that isn't much longer, or forbid them outright, see layout.rs
+    #[arg(long, value_enum)]
+    safe_mode: Option<SafeModeArg>,
+
+    /// Which of the moves the search likes equally well to try first, a comma separated list of
+    /// policies with the most important first, e.g. empties-pile,sends-home, see ordering.rs
+    #[arg(long, value_enum, value_delimiter = ',')]
+    move_order: Vec<MoveOrderArg>,
+
+    /// Where the piles and the buttons are on the game's screen, for --safe-mode
+    #[arg(long, default_value = "layout.toml")]
+    layout_file: PathBuf,
+
+    /// Send the moves this many at a time, each chunk followed by the fingerprint of the board it
+    /// leaves, and wait for the driver to ask for the next (interactive and session only)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    chunk: Option<u64>,
+
+    /// Send the moves one at a time, waiting for the driver to say OK (or FAIL and the board it
+    /// got instead, which we plan again from) after each (interactive and session only)
+    #[arg(long, conflicts_with = "chunk")]
+    ack: bool,
+
+    /// Send a THINKING line every this many seconds, so that a driver waiting on a long search can
+    /// tell we haven't hung. The driver has to drop them, they can come between any two lines
+    /// (interactive and session only)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    heartbeat: Option<u64>,
+}
+
+// with no subcommand, we read a deal from stdin and solve it
+#[derive(Subcommand)]
+enum Command {
+    /// Play the moves that have already been made on a deal, and print where that leaves the board
+    Advance {
+        /// The deal, in the same format as stdin
+        #[arg(long)]
+        board: PathBuf,
+
+        /// The moves, one per line, as printed (any protocol) or as written to --journal
+        #[arg(long)]
+        moves: PathBuf,
+    },
+
+    /// Check two reads of the same screen against each other, and print the board if they agree
+    Reconcile { a: PathBuf, b: PathBuf },
+
+    /// Take deals (and answers to questions about them) as messages on stdin, see interactive.rs
+    Interactive,
+
+    /// Play game after game, deciding for each deal whether to solve it or deal again, see
+    /// session.rs
+    Session {
+        /// How long to spend deciding on each deal
+        #[arg(long, default_value_t = 5000)]
+        budget_ms: u64,
+
+        /// While the driver plays a plan, work out what to do if it ends up a move off it
+        #[arg(long)]
+        ponder: bool,
+
+        /// Remember every deal and how it went in this file, and don't try again at ones we've
+        /// found unsolvable before
+        #[arg(long)]
+        seen_deals: Option<PathBuf>,
+
+        /// Give deals triage says are easy a quarter of --budget-ms, and hard ones four times it
+        #[arg(long)]
+        adaptive: bool,
+
+        /// Spend no more than this many minutes deciding on deals in any hour
+        #[arg(long)]
+        minutes_per_hour: Option<u64>,
+
+        /// Before calling a deal unsolvable, search it again a different way, looking at up to
+        /// this many boards, and report a bug if that finds a way through
+        #[arg(long)]
+        verify_unsolvable: Option<usize>,
+
+        /// Broadcast the board, what we made of it and the next move over a WebSocket on this
+        /// address (e.g. 127.0.0.1:9001), for a stream overlay, see overlay.rs
+        #[cfg(feature = "overlay")]
+        #[arg(long)]
+        overlay: Option<String>,
+
+        /// The calibration for the overlay's eval bar, as printed by calibrate-eval, rather than
+        /// the one that's built in, see evalbar.rs
+        #[cfg(feature = "overlay")]
+        #[arg(long, requires = "overlay")]
+        eval_calibration: Option<PathBuf>,
+    },
+
+    /// Guess quickly whether the deal on stdin is worth solving, exits with 1 if it isn't
+    Triage,
+
+    /// Check whether the board on stdin has any legal move, and whether any line from it wins, for
+    /// a second opinion on the game saying there are no more moves, see stuck.rs
+    StuckCheck {
+        /// Give up (verdict UNKNOWN) after looking at this many boards
+        #[arg(long, default_value_t = 1_000_000)]
+        max_expansions: usize,
+    },
+
+    /// Solve the deal on stdin and print the solution as a short code to send someone, see share.rs
+    Share,
+
+    /// Print the moves in a code from share, for the deal on stdin
+    LoadShare { code: String },
+
+    /// List the endgames with this many cards left that can't be won, see retrograde.rs
+    Retrograde {
+        /// Goes up steeply: 4 is quick, 5 takes a while, 6 needs a lot of memory
+        #[arg(long, default_value_t = 4)]
+        cards: usize,
+    },
+
+    /// Send cards home from a deal one at a time for as long as it still goes wrong, and print the
+    /// smallest board that does, see minimize.rs
+    Minimize {
+        /// The deal, in the same format as stdin
+        deal: PathBuf,
+
+        /// What going wrong means
+        #[arg(long, value_enum)]
+        predicate: PredicateArg,
+
+        /// For --predicate oom, how many boards the search gets to expand before we say it's run
+        /// away. a lot fewer fit in memory with --heuristic relaxed, and each one's slower
+        #[arg(long, default_value_t = 100_000)]
+        max_expansions: usize,
+    },
+
+    /// Solve every deal in a regression corpus again, look for shorter solutions than that, and
+    /// lower the recorded lengths to match, see shorten.rs
+    TightenCorpus {
+        dir: PathBuf,
+
+        /// How many boards to look through for a shorter solution, per deal
+        #[arg(long, default_value_t = 100_000)]
+        max_expansions: usize,
+    },
+
+    /// Compare two runs over the same deals, e.g. before and after a heuristic change: solve rate,
+    /// length, time and first moves. a run that isn't saved yet is made now by this build with
+    /// these settings, so give the old build the same file twice. exits with 1 if the new run's
+    /// worse on any deal, see compare.rs
+    Compare {
+        /// JSON file for the old run
+        #[arg(long)]
+        old: PathBuf,
+
+        /// JSON file for the new run
+        #[arg(long)]
+        new: PathBuf,
+
+        /// Directory of NAME.deal files, e.g. a corpus
+        #[arg(long)]
+        deals: PathBuf,
+    },
+
+    /// Write a run saved by compare up as one HTML file to share: how hard the deals were, how long
+    /// they took and why the ones that weren't solved weren't, see heatmap.rs
+    Report {
+        /// JSON file of the run, as compare saves it
+        run: PathBuf,
+
+        /// Directory of the run's NAME.deal files, to say how hard each one is
+        #[arg(long)]
+        deals: PathBuf,
+
+        /// Where to write the report
+        #[arg(long)]
+        heatmap: PathBuf,
+    },
+
+    /// Look through every line from each deal, and for the ones that can't be won, count up which
+    /// cards were holding things up where it got closest and what was on top of them, see
+    /// culprits.rs
+    Culprits {
+        deals: Vec<PathBuf>,
+
+        /// Give up on a deal (counted as unknown) after looking at this many boards
+        #[arg(long, default_value_t = 1_000_000)]
+        max_expansions: usize,
+    },
+
+    /// Deal a practice game for one tactic: the best of a batch of random deals at it, by how much
+    /// the solution we find uses it. prints the deal, and its --from-seed on stderr, see drill.rs
+    Drill {
+        #[arg(long, value_enum)]
+        pattern: TacticArg,
+
+        /// How many deals to pick from. each one takes a couple of seconds
+        #[arg(long, default_value_t = 20)]
+        candidates: usize,
+    },
+
+    /// Fit a timing profile to the driver's logs of how long each move took (MOVE<tab>MS lines),
+    /// and print it as a table for --timing-file, see timing::fit
+    FitCosts {
+        logs: Vec<PathBuf>,
+
+        /// What to call the profile
+        #[arg(long, default_value = "fitted")]
+        name: String,
+    },
+
+    /// Decide every deal in a directory of NAME.deal files like session would, and fit the eval
+    /// bar's calibration to how they went, printed for --eval-calibration, see evalbar.rs
+    CalibrateEval {
+        deals: PathBuf,
+
+        /// How long to spend deciding on each deal
+        #[arg(long, default_value_t = 5000)]
+        budget_ms: u64,
+    },
+
+    /// Print the rules the solver plays by, with the rule switches as given, see rules_doc.rs
+    Rules {
+        #[arg(long, value_enum, default_value = "md")]
+        format: FormatArg,
+    },
+
+    /// Answer deals posted in Discord, passed along over stdin by a relay, see bot.rs
+    #[cfg(feature = "bot")]
+    Bot {
+        /// How long to spend on each deal
+        #[arg(long, default_value_t = 10_000)]
+        budget_ms: u64,
+
+        /// Draw each deal answered as an SVG in this directory, for the relay to attach
+        #[arg(long)]
+        image_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Md,
+    Text,
+}
+
+impl From<FormatArg> for rules_doc::Format {
+    fn from(arg: FormatArg) -> Self {
+        match arg {
+            FormatArg::Md => rules_doc::Format::Markdown,
+            FormatArg::Text => rules_doc::Format::Text,
         }
     }
 }
 
-// Ace = 1
-// 2 = 2
-// 3 = 3
-// ...
-// 10 = 10
-// J = 11
-// Q = 12
-// K = 13
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
-struct MinorValue(u8);
+#[derive(Clone, Copy, ValueEnum)]
+enum PredicateArg {
+    // the search goes on and on, as it does before we run out of memory
+    Oom,
+    // the solver gives up without a solution
+    Unsolved,
+}
 
-impl Debug for MinorValue {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self.0 {
-            1 => f.write_str("A"),
-            11 => f.write_str("J"),
-            12 => f.write_str("Q"),
-            13 => f.write_str("K"),
-            otherwise => Debug::fmt(&otherwise, f),
+#[derive(Clone, Copy, ValueEnum)]
+enum TacticArg {
+    BlockManagement,
+    MajorSequencing,
+}
+
+impl From<TacticArg> for Tactic {
+    fn from(tactic: TacticArg) -> Self {
+        match tactic {
+            TacticArg::BlockManagement => Tactic::BlockManagement,
+            TacticArg::MajorSequencing => Tactic::MajorSequencing,
         }
     }
 }
 
-impl Display for MinorValue {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Debug::fmt(self, f)
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorArg> for color::Choice {
+    fn from(arg: ColorArg) -> Self {
+        match arg {
+            ColorArg::Auto => color::Choice::Auto,
+            ColorArg::Always => color::Choice::Always,
+            ColorArg::Never => color::Choice::Never,
+        }
     }
 }
 
-impl MinorValue {
-    fn parse(s: &str) -> Self {
-        match s {
-            "A" => MinorValue(1),
-            "2" => MinorValue(2),
-            "3" => MinorValue(3),
-            "4" => MinorValue(4),
-            "5" => MinorValue(5),
-            "6" => MinorValue(6),
-            "7" => MinorValue(7),
-            "8" => MinorValue(8),
-            "9" => MinorValue(9),
-            "10" => MinorValue(10),
-            "J" => MinorValue(11),
-            "Q" => MinorValue(12),
-            "K" => MinorValue(13),
-            otherwise => panic!("Invalid minor value: {}", otherwise),
+#[derive(Clone, Copy, ValueEnum)]
+enum GlyphsArg {
+    Emoji,
+    Letters,
+}
+
+impl From<GlyphsArg> for color::Glyphs {
+    fn from(arg: GlyphsArg) -> Self {
+        match arg {
+            GlyphsArg::Emoji => color::Glyphs::Emoji,
+            GlyphsArg::Letters => color::Glyphs::Letters,
         }
     }
 }
 
-// from 0 to 21
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct MajorValue(u8);
+#[derive(Clone, Copy, ValueEnum)]
+enum SafeModeArg {
+    Avoid,
+    Forbid,
+}
 
-impl MajorValue {
-    fn parse(s: &str) -> Self {
-        MajorValue(s.parse().unwrap())
+impl From<SafeModeArg> for SafeMode {
+    fn from(arg: SafeModeArg) -> Self {
+        match arg {
+            SafeModeArg::Avoid => SafeMode::Avoid,
+            SafeModeArg::Forbid => SafeMode::Forbid,
+        }
     }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MoveOrderArg {
+    EmptiesPile,
+    SendsHome,
+    FreesBlock,
+}
 
-    const fn first() -> Self {
-        MajorValue(0)
+impl From<MoveOrderArg> for MoveOrder {
+    fn from(arg: MoveOrderArg) -> Self {
+        match arg {
+            MoveOrderArg::EmptiesPile => MoveOrder::EmptiesPile,
+            MoveOrderArg::SendsHome => MoveOrder::SendsHome,
+            MoveOrderArg::FreesBlock => MoveOrder::FreesBlock,
+        }
     }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HeuristicArg {
+    CardsRemaining,
+    Relaxed,
+}
 
-    const fn last() -> Self {
-        MajorValue(21)
+impl From<HeuristicArg> for Heuristic {
+    fn from(arg: HeuristicArg) -> Self {
+        match arg {
+            HeuristicArg::CardsRemaining => Heuristic::CardsRemaining,
+            HeuristicArg::Relaxed => Heuristic::RelaxedLowerBound,
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[repr(usize)]
-enum Suit {
-    Sword,
-    Wand,
-    Cup,
-    Star,
+// how many of the least confident cards we point the finger at (or try swapping) when a deal goes
+// wrong
+const NUM_SUSPECTS: usize = 3;
+
+fn report_suspects(deal: &str) {
+    for suspect in confidence::read_confidences(deal)
+        .into_iter()
+        .take(NUM_SUSPECTS)
+    {
+        warnings::warn(Warning::LikelyMisread {
+            pile: suspect.pile,
+            depth: suspect.depth,
+            card: suspect.card.serialize(),
+            confidence: suspect.confidence,
+        });
+    }
 }
 
-const NUM_SUITS: usize = std::mem::variant_count::<Suit>();
+// the ways a run can fail that aren't bugs (for those, see BoardError): a file we couldn't read or
+// write, or a deal we couldn't solve. each is a line on stderr (and a bug report, if they're on)
+// and exit code 2, rather than a panic and a backtrace
+enum Failure {
+    Io { what: String, error: io::Error },
+    // None if the deal had unknowns in it, so there's no one board to point at
+    NoSolution { fingerprint: Option<String> },
+}
 
-impl Display for Suit {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Suit::Sword => f.write_str("🗡"),
-            Suit::Wand => f.write_str("🪄"),
-            Suit::Cup => f.write_str("🍷"),
-            Suit::Star => f.write_str("⭐"),
+            Failure::Io { what, error } => write!(f, "couldn't {}: {}", what, error),
+            Failure::NoSolution {
+                fingerprint: Some(fingerprint),
+            } => write!(f, "no solution for board {}", fingerprint),
+            Failure::NoSolution { fingerprint: None } => {
+                write!(f, "no solution for any way the unknown cards could be")
+            }
         }
     }
 }
 
-impl Suit {
-    fn parse(s: &str) -> Self {
-        match s {
-            "SWO" => Suit::Sword,
-            "WAN" => Suit::Wand,
-            "CUP" => Suit::Cup,
-            "STA" => Suit::Star,
-            otherwise => panic!("Invalid suit: {}", otherwise),
-        }
+impl Failure {
+    fn exit(self) -> ! {
+        eprintln!("{}", self);
+        bundle::report(&self.to_string());
+        process::exit(2)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Card {
-    Major(MajorValue),
-    Minor { suit: Suit, value: MinorValue },
+trait OrFail<T> {
+    // what is what we were trying to do, e.g. "write trajectory.txt"
+    fn or_fail(self, what: impl FnOnce() -> String) -> T;
 }
 
-impl Display for Card {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Card::Major(value) => Display::fmt(&value.0, f),
-            Card::Minor { suit, value } => {
-                Display::fmt(value, f)?;
-                Display::fmt(suit, f)
+impl<T> OrFail<T> for io::Result<T> {
+    fn or_fail(self, what: impl FnOnce() -> String) -> T {
+        self.unwrap_or_else(|error| {
+            Failure::Io {
+                what: what(),
+                error,
             }
+            .exit()
+        })
+    }
+}
+
+// the deal on stdin, or the one --from-seed makes. either way it can be a SEED N, see shuffle.rs. a
+// bug report gets it as it was given, so a seed stays a seed
+fn read_deal(from_seed: Option<u64>) -> String {
+    let deal = match from_seed {
+        Some(seed) => format!("{}{}\n", shuffle::SEED_PREFIX, seed),
+        None => {
+            let mut deal = String::new();
+            stdin()
+                .read_to_string(&mut deal)
+                .or_fail(|| "read stdin".to_string());
+            deal
         }
+    };
+    bundle::note_deal(&deal);
+    shuffle::expand(&deal)
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).or_fail(|| format!("read {}", path.display()))
+}
+
+fn create(path: &Path) -> File {
+    File::create(path).or_fail(|| format!("create {}", path.display()))
+}
+
+// for the files with a line per move
+fn write_lines(path: &Path, lines: impl Iterator<Item = String>) {
+    let mut file = create(path);
+    for line in lines {
+        writeln!(file, "{}", line).or_fail(|| format!("write {}", path.display()));
     }
 }
 
-impl Card {
-    fn parse(s: &str) -> Self {
-        let mut split = s.split('_');
-        let value = split.next().unwrap();
-        let suit = split.next().unwrap();
-        if suit == "MAJ" {
-            Card::Major(MajorValue::parse(value))
-        } else {
-            Card::Minor {
-                suit: Suit::parse(suit),
-                value: MinorValue::parse(value),
+// so that after a crash we can pick up where we got to without reading the screen again. moves
+// from a journal are checked against their fingerprints as we go
+fn advance(board: &Path, moves: &Path, rules: &Rules) {
+    let deal = read(board);
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    for (i, line) in read(moves).lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (fingerprint, moov) = match line.split_once('\t') {
+            Some((fingerprint, moov)) => (Some(fingerprint.to_ascii_lowercase()), moov),
+            None => (None, line),
+        };
+        let (from, to) = Move::parse_locations(moov.trim());
+        board = match board.play(from, to, rules) {
+            Some((board, _)) => board,
+            None => {
+                report_suspects(&deal);
+                panic!("move {} ({}) can't be played on {:?}", i + 1, moov, board);
+            }
+        };
+        if let Some(fingerprint) = fingerprint {
+            if board.fingerprint_hex() != fingerprint {
+                report_suspects(&deal);
             }
+            assert_eq!(
+                board.fingerprint_hex(),
+                fingerprint,
+                "board after move {} ({}) isn't the one the journal expected",
+                i + 1,
+                moov
+            );
         }
     }
+    print!("{}", board.serialize());
+}
 
-    fn is_next_card(self, next_card: Self) -> bool {
-        match (self, next_card) {
-            (Card::Major(this_value), Card::Major(next_value)) => this_value.0 + 1 == next_value.0,
-            (
-                Card::Minor {
-                    suit,
-                    value: this_value,
-                },
-                Card::Minor {
-                    suit: next_suit,
-                    value: next_value,
-                },
-            ) => suit == next_suit && this_value.0 + 1 == next_value.0,
-            _ => false,
+// exits with 1 (after listing where the reads differ) if they don't agree
+fn reconcile(a: &Path, b: &Path) {
+    let parse = |path: &Path| Board::parse(&read(path));
+    match reconcile::reconcile(&parse(a), &parse(b)) {
+        Ok(board) => print!("{}", board.serialize()),
+        Err(disagreements) => {
+            for disagreement in disagreements {
+                eprintln!("{}", disagreement);
+            }
+            process::exit(1);
         }
     }
+}
 
-    fn is_prev_card(self, prev_card: Self) -> bool {
-        match (self, prev_card) {
-            (Card::Major(MajorValue(this_val)), Card::Major(MajorValue(prev_val))) => {
-                this_val == prev_val + 1
-            }
-            (
-                Card::Minor {
-                    suit,
-                    value: this_val,
-                },
-                Card::Minor {
-                    suit: prev_suit,
-                    value: prev_val,
-                },
-            ) => suit == prev_suit && this_val.0 == prev_val.0 + 1,
-            _ => false,
-        }
-    }
-
-    fn is_next_or_prev(self, other: Self) -> bool {
-        self.is_next_card(other) || self.is_prev_card(other)
-    }
-}
-
-const NUM_PLAYING_STACKS: usize = 11;
-
-// #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-#[derive(Debug, Clone, Derivative)]
-#[derivative(PartialEq, Eq, Hash)]
-struct Board {
-    major_lower_stack: Vec<Card>,
-    major_higher_stack: Vec<Card>,
-    minor_collection_piles: [Vec<Card>; NUM_SUITS],
-    minor_collection_blocked: Option<Card>,
-    playing_area: [Vec<Card>; NUM_PLAYING_STACKS],
-    #[derivative(PartialEq = "ignore", Hash = "ignore")]
-    last_n_moves: VecDeque<Move>,
-}
-
-// HAX: OLD is used to indicate that we don't limit the num_prev_moves, and instead use what we were using before: no limit to prune the search tree. SOMETIMES that was producing better results
-const OLD: usize = 0;
-const NUM_PREV_MOVES_TO_CONSIDERS: [usize; 4] = [5, 10, 15, OLD];
-
-const fn const_max(ns: &[usize]) -> usize {
-    let mut max = 0;
-    let mut i = 0;
-    while i < ns.len() {
-        if ns[i] > max {
-            max = ns[i];
-        }
-        i += 1;
-    }
-    max
-}
-
-const MAX_NUM_PREV_MOVES_TO_CONSIDER: usize = const_max(&NUM_PREV_MOVES_TO_CONSIDERS);
-
-impl Board {
-    fn with_prev_move(self, prev_move: Move) -> Self {
-        let mut new_board = self;
-        new_board.last_n_moves.push_front(prev_move);
-        if new_board.last_n_moves.len() > MAX_NUM_PREV_MOVES_TO_CONSIDER {
-            new_board.last_n_moves.pop_back();
-        }
-        new_board
-    }
-
-    fn is_done(&self) -> bool {
-        self.playing_area.iter().all(|pile| pile.is_empty())
-    }
-
-    fn parse(s: &str) -> Self {
-        let mut playing_area = [
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-        ];
-        for (line, stack_to_fill) in s.lines().zip(playing_area.iter_mut()) {
-            for card in line.trim().split_terminator(',') {
-                let card = Card::parse(card);
-                stack_to_fill.push(card);
-            }
+// a line of the protocol on stdout, see --unbuffered
+fn say(line: &str) {
+    writeln!(Unbuffered(stdout()), "{}", line).or_fail(|| "write to stdout".to_string());
+}
+
+// prints a move (to stdout, and to stderr for people) and journals it, as it's played on board
+fn emit_move(
+    board: &mut Board,
+    moov: &Move,
+    rules: &Rules,
+    protocol: Protocol,
+    timing: Option<&TimingProfile>,
+    journal: &mut Option<File>,
+) {
+    let Some((after, _)) = board.play(moov.from, moov.to, rules) else {
+        BoardError::InvalidMove {
+            fingerprint: board.fingerprint_hex(),
+            moov: moov.serialize(Protocol::V3),
+            why: "it isn't one of the moves on the board",
         }
-        Self {
-            last_n_moves: VecDeque::new(),
-            major_higher_stack: vec![],
-            major_lower_stack: vec![],
-            minor_collection_piles: [
-                vec![Card::Minor {
-                    suit: Suit::Sword,
-                    value: MinorValue(1),
-                }],
-                vec![Card::Minor {
-                    suit: Suit::Wand,
-                    value: MinorValue(1),
-                }],
-                vec![Card::Minor {
-                    suit: Suit::Cup,
-                    value: MinorValue(1),
-                }],
-                vec![Card::Minor {
-                    suit: Suit::Star,
-                    value: MinorValue(1),
-                }],
+        .raise()
+    };
+    let reversibility = reversibility::reversibility(board, &after, moov, rules);
+    *board = after;
+    let palette = color::palette();
+    let messages = i18n::messages();
+    let sucks = moov.num_sucks().to_string();
+    let reversibility = messages.reversibility(reversibility);
+    let notes = match timing {
+        Some(timing) => fill(
+            messages.get("notes_timed"),
+            &[
+                ("sucks", &sucks),
+                ("reversibility", reversibility),
+                ("ms", &timing.move_ms(moov).to_string()),
             ],
-            minor_collection_blocked: None,
-            playing_area,
+        ),
+        None => fill(
+            messages.get("notes"),
+            &[("sucks", &sucks), ("reversibility", reversibility)],
+        ),
+    };
+    eprintln!("{} {}", palette.moov(moov), palette.dim(&notes));
+    say(&moov.serialize(protocol));
+    if let Some(journal) = journal {
+        writeln!(
+            journal,
+            "{}\t{}",
+            board.fingerprint_hex(),
+            moov.serialize(Protocol::V3)
+        )
+        .and_then(|_| journal.sync_data())
+        .or_fail(|| "write to the journal".to_string());
+    }
+}
+
+// the settings again, if the config file's changed since we last looked. one we can't use is a
+// warning, and we carry on as we were
+fn reload(watcher: &mut config::Watcher) -> Option<Args> {
+    if !watcher.changed() {
+        return None;
+    }
+    let args = config::layer(&Args::command(), env::args().collect())
+        .and_then(|layered| Args::try_parse_from(&layered.args).map_err(|e| e.to_string()));
+    match args {
+        Ok(args) => {
+            eprintln!("reloaded settings from {}", watcher.path().display());
+            Some(args)
+        }
+        Err(why) => {
+            warnings::warn(Warning::ConfigNotReloaded {
+                path: watcher.path().display().to_string(),
+                why,
+            });
+            None
         }
     }
+}
 
-    fn num_cards_remaining(&self) -> usize {
-        self.playing_area
-            .iter()
-            .map(|stack| stack.len())
-            .sum::<usize>()
-        // TODO: sometimes commenting this bottom part out makes it so we complete instead of not completing ???
-        + self.minor_collection_blocked.is_some() as usize
+// writes out everything --tablebase has worked out, for --tablebase-file
+fn save_tablebase(path: Option<&Path>) {
+    if let (Some(path), Some(tablebase)) = (path, tablebase::installed()) {
+        tablebase
+            .save(path)
+            .or_fail(|| format!("write {}", path.display()));
     }
+}
 
-    fn suck_readies_into_receptacles(&mut self) -> Vec<Card> {
-        let mut sucked_cards = vec![];
+fn main() {
+    let layered =
+        config::layer(&Args::command(), env::args().collect()).unwrap_or_else(|e| panic!("{}", e));
+    let matches = Args::command().get_matches_from(&layered.args);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.print_config {
+        print!(
+            "{}",
+            config::serialize(&Args::command(), &matches, &layered)
+        );
+        return;
+    }
+    // before anything else starts a thread
+    if let Some(niceness) = args.nice {
+        priority::set_niceness(niceness);
+    }
+    if !args.cpus.is_empty() {
+        priority::pin_to_cpus(&args.cpus);
+    }
+    if args.ascii {
+        card::ASCII.store(true, Ordering::Relaxed);
+    }
+    if args.unbuffered {
+        unbuffered::UNBUFFERED.store(true, Ordering::Relaxed);
+    }
+    color::set(args.color.into(), args.glyphs.into());
+    if let Some(path) = &args.messages {
+        i18n::set(Messages::load(path).unwrap_or_else(|e| panic!("{}", e)));
+    }
+    if args.paranoid {
+        PARANOID.store(true, Ordering::Relaxed);
+    }
+    if let Some(epsilon) = args.epsilon {
+        if epsilon.is_nan() || epsilon < 0.0 {
+            panic!("--epsilon can't be negative");
+        }
+        if !matches!(args.heuristic, HeuristicArg::Relaxed) {
+            panic!("--epsilon only works with --heuristic relaxed");
+        }
+        search::set_epsilon(epsilon);
+    }
+    if let Some(upper_bound) = args.upper_bound {
+        if !matches!(args.heuristic, HeuristicArg::Relaxed) {
+            panic!("--upper-bound only works with --heuristic relaxed");
+        }
+        search::UPPER_BOUND.store(upper_bound, Ordering::Relaxed);
+    }
+    memo::set_capacity(args.memo_size);
+    if args.hash_only_closed {
+        closed::HASH_ONLY.store(true, Ordering::Relaxed);
+    }
+    if args.check_collisions {
+        closed::CHECK_COLLISIONS.store(true, Ordering::Relaxed);
+    }
+    if args.canonical_suits {
+        closed::CANONICAL_SUITS.store(true, Ordering::Relaxed);
+    }
+    if args.profile_search {
+        search::PROFILING.store(true, Ordering::Relaxed);
+        TIME_SUCKS.store(true, Ordering::Relaxed);
+    }
+    if let Some(max_traced) = args.trace_expansions {
+        search::TRACE_EXPANSIONS.store(max_traced, Ordering::Relaxed);
+    }
+    if args.certificate.is_some() {
+        if !matches!(args.heuristic, HeuristicArg::Relaxed) {
+            panic!("--certificate only works with --heuristic relaxed");
+        }
+        search::CERTIFYING.store(true, Ordering::Relaxed);
+    }
+    if let Some(path) = &args.warnings {
+        warnings::set_sink(Box::new(create(path)));
+    }
+    if let Some(dir) = &args.bug_report_dir {
+        bundle::enable(dir.clone());
+    }
+    if let Some(seed) = args.seed {
+        unknown::set_seed(seed);
+    }
+    // on stderr, so whatever's keeping the output knows what it came from without the protocol
+    // changing under the driver
+    eprintln!("version {}", provenance::version());
+    #[cfg(feature = "memcap")]
+    {
+        memcap::set_limit_gb(args.memory_limit_gb);
+        memcap::watch_memory();
+    }
 
-        let mut changed = true;
-        while changed {
-            changed = false;
+    let rules = Rules {
+        explicit_foundation_moves: args.explicit_foundation_moves,
+        game_auto_completes: args.game_auto_completes,
+        no_block: args.no_block,
+        optional_sucks: args.optional_sucks,
+        optional_major_sucks: args.optional_major_sucks,
+    };
+    if let Some(max_cards) = args.tablebase {
+        let tablebase = match &args.tablebase_file {
+            Some(path) => Tablebase::load(path, max_cards, &rules)
+                .or_fail(|| format!("read {}", path.display())),
+            None => Tablebase::new(max_cards, &rules),
+        };
+        tablebase::install(tablebase);
+    }
+    if let Some(mode) = args.safe_mode {
+        let layout = Layout::load(&args.layout_file).unwrap_or_else(|e| panic!("{}", e));
+        layout::install(layout, mode.into());
+    }
+    if !args.move_order.is_empty() {
+        ordering::install(
+            args.move_order
+                .iter()
+                .map(|&policy| policy.into())
+                .collect(),
+        );
+    }
+    let protocol = match args.protocol {
+        1 => Protocol::V1,
+        2 => Protocol::V2,
+        _ => Protocol::V3,
+    };
+    let portfolio = search::default_portfolio(
+        args.two_phase,
+        args.decompose,
+        args.restarts,
+        args.heuristic.into(),
+    );
+    let timing = args.timing_profile.as_ref().map(|name| {
+        TimingProfile::load(&args.timing_file, name).unwrap_or_else(|e| panic!("{}", e))
+    });
+    let solve = |b: &Board| search::solve(b, &portfolio, &rules, args.goal, args.threads);
 
-            for (playing_area_index, last_card) in
-                self.last_card_of_every_stack_mut().into_iter().enumerate()
-            {
-                if last_card.is_none() {
-                    continue;
+    // plain stdin is read to the end before we start, so there's no way to hear back from the
+    // driver
+    let pacing = match (args.chunk, args.ack) {
+        (_, true) => Pacing::Ack,
+        (Some(chunk), false) => Pacing::Chunks(chunk as usize),
+        (None, false) => Pacing::All,
+    };
+    if pacing != Pacing::All
+        && !matches!(
+            args.command,
+            Some(Command::Interactive) | Some(Command::Session { .. })
+        )
+    {
+        panic!("--chunk and --ack only work with the interactive and session subcommands");
+    }
+    if let Some(secs) = args.heartbeat {
+        if !matches!(
+            args.command,
+            Some(Command::Interactive) | Some(Command::Session { .. })
+        ) {
+            panic!("--heartbeat only works with the interactive and session subcommands");
+        }
+        heartbeat::start(Duration::from_secs(secs));
+    }
+
+    match &args.command {
+        Some(Command::Advance { board, moves }) => return advance(board, moves, &rules),
+        Some(Command::Reconcile { a, b }) => return reconcile(a, b),
+        Some(Command::Interactive) => {
+            let watcher = RefCell::new(config::Watcher::new(&layered.file));
+            let current = RefCell::new((portfolio.clone(), args.goal, args.threads));
+            let solve = |b: &Board| {
+                if let Some(args) = reload(&mut watcher.borrow_mut()) {
+                    let portfolio = search::default_portfolio(
+                        args.two_phase,
+                        args.decompose,
+                        args.restarts,
+                        args.heuristic.into(),
+                    );
+                    *current.borrow_mut() = (portfolio, args.goal, args.threads);
                 }
-                let last_card = last_card.unwrap();
-
-                // see if we can suck into minor collection pile
-                if self.minor_collection_blocked.is_none() {
-                    for minor_collection_pile in self.minor_collection_piles.iter_mut() {
-                        if minor_collection_pile
-                            .last()
-                            .unwrap()
-                            .is_next_card(last_card)
-                        {
-                            let card = self.playing_area[playing_area_index].pop().unwrap();
-                            minor_collection_pile.push(card);
-                            sucked_cards.push(card);
-                            changed = true;
+                let (portfolio, goal, threads) = &*current.borrow();
+                search::solve(b, portfolio, &rules, *goal, *threads)
+            };
+            interactive::run(
+                &mut stdin().lock(),
+                &mut Unbuffered(stdout()),
+                &solve,
+                &rules,
+                protocol,
+                pacing,
+            )
+            .or_fail(|| "talk to the driver".to_string());
+            return save_tablebase(args.tablebase_file.as_deref());
+        }
+        Some(Command::Session {
+            budget_ms,
+            ponder,
+            seen_deals,
+            adaptive,
+            minutes_per_hour,
+            verify_unsolvable,
+            ..
+        }) => {
+            let options = SessionOptions {
+                heuristic: args.heuristic.into(),
+                rules,
+                protocol,
+                budget_ms: *budget_ms,
+                pacing,
+                ponder: *ponder,
+                adaptive: *adaptive,
+                hourly_budget_ms: minutes_per_hour.map(|minutes| minutes * 60 * 1000),
+                verify_unsolvable: *verify_unsolvable,
+            };
+            let mut seen = seen_deals
+                .as_ref()
+                .map(|path| SeenDeals::open(path).or_fail(|| format!("open {}", path.display())));
+            let mut watcher = config::Watcher::new(&layered.file);
+            let mut reload = || {
+                let args = reload(&mut watcher)?;
+                let Some(Command::Session {
+                    budget_ms,
+                    ponder,
+                    adaptive,
+                    minutes_per_hour,
+                    verify_unsolvable,
+                    ..
+                }) = args.command
+                else {
+                    return None;
+                };
+                Some(SessionOptions {
+                    heuristic: args.heuristic.into(),
+                    budget_ms,
+                    ponder,
+                    adaptive,
+                    hourly_budget_ms: minutes_per_hour.map(|minutes| minutes * 60 * 1000),
+                    verify_unsolvable,
+                    ..options
+                })
+            };
+            #[cfg(feature = "overlay")]
+            let overlay = match &args.command {
+                Some(Command::Session {
+                    overlay: Some(addr),
+                    eval_calibration,
+                    ..
+                }) => {
+                    let calibration = match eval_calibration {
+                        Some(path) => {
+                            evalbar::Calibration::load(path).unwrap_or_else(|e| panic!("{}", e))
                         }
-                    }
+                        None => evalbar::Calibration::default(),
+                    };
+                    Some(Overlay::bind(addr, calibration).or_fail(|| format!("listen on {}", addr)))
                 }
-
-                // see if we can suck into one of the major collection piles
-                if self
-                    .major_lower_stack
-                    .last()
-                    .map(|card| card.is_next_card(last_card))
-                    .unwrap_or(false)
-                    || (self.major_lower_stack.is_empty()
-                        && last_card == Card::Major(MajorValue::first()))
-                {
-                    let card = self.playing_area[playing_area_index].pop().unwrap();
-                    self.major_lower_stack.push(card);
-                    sucked_cards.push(card);
-                    changed = true;
-                } else if self
-                    .major_higher_stack
-                    .last()
-                    .map(|card| card.is_prev_card(last_card))
-                    .unwrap_or(false)
-                    || (self.major_higher_stack.is_empty()
-                        && last_card == Card::Major(MajorValue::last()))
-                {
-                    let card = self.playing_area[playing_area_index].pop().unwrap();
-                    self.major_higher_stack.push(card);
-                    sucked_cards.push(card);
-                    changed = true;
+                _ => None,
+            };
+            #[allow(unused_variables)]
+            let mut observe = |progress: session::Progress, stats: &session::Stats| {
+                #[cfg(feature = "overlay")]
+                if let Some(overlay) = &overlay {
+                    overlay.broadcast(progress, stats);
                 }
-            }
-
-            if let Some(blocking_card) = self.minor_collection_blocked {
-                // TODO: slight duplication with above logic
-                if self
-                    .major_lower_stack
-                    .last()
-                    .map(|card| card.is_next_card(blocking_card))
-                    .unwrap_or(false)
-                {
-                    self.major_lower_stack.push(blocking_card);
-                    self.minor_collection_blocked = None;
-                    sucked_cards.push(blocking_card);
-                    changed = true;
-                } else if self
-                    .major_higher_stack
-                    .last()
-                    .map(|card| card.is_prev_card(blocking_card))
-                    .unwrap_or(false)
-                {
-                    self.major_higher_stack.push(blocking_card);
-                    self.minor_collection_blocked = None;
-                    sucked_cards.push(blocking_card);
-                    changed = true;
+            };
+            let stats = session::run(
+                &mut stdin().lock(),
+                &mut Unbuffered(stdout()),
+                &options,
+                seen.as_mut(),
+                &mut reload,
+                &mut observe,
+            )
+            .or_fail(|| "talk to the driver".to_string());
+            eprintln!("{}", stats.serialize());
+            return save_tablebase(args.tablebase_file.as_deref());
+        }
+        Some(Command::StuckCheck { max_expansions }) => {
+            let deal = read_deal(args.from_seed);
+            let board = Board::parse(&deal);
+            let check = stuck::check(&board, &rules, *max_expansions);
+            println!("{}", check.serialize());
+            // the winning line, to try on the game
+            if let stuck::Verdict::Winnable(solution) = &check.verdict {
+                for moov in &solution.moves {
+                    say(&moov.serialize(protocol));
                 }
             }
+            return;
         }
-
-        sucked_cards
-    }
-
-    fn last_card_of_every_stack_mut(&mut self) -> [Option<Card>; 11] {
-        let mut last_cards = [None; NUM_PLAYING_STACKS];
-        for (stack, last_card) in self.playing_area.iter().zip(last_cards.iter_mut()) {
-            if let Some(card) = stack.last().copied() {
-                *last_card = Some(card);
+        Some(Command::Triage) => {
+            let deal = read_deal(args.from_seed);
+            let mut board = Board::parse(&deal);
+            board.suck_readies_into_receptacles();
+            let triage = triage::triage(&board);
+            let tree_size = triage::tree_size(&board, &rules);
+            println!("{} tree={:.1e}", triage.serialize(), tree_size as f64);
+            if triage.verdict == Verdict::Redeal {
+                process::exit(1);
             }
+            return;
         }
-        last_cards
-    }
-
-    fn next_boards(&self, num_prev_moves_to_consider: usize) -> Vec<(Self, Move)> {
-        let mut boards = vec![];
-
-        const MINIMUM_AMT_OF_PROGRESS: usize = 1;
-
-        if num_prev_moves_to_consider != OLD
-            && self.last_n_moves.len() >= num_prev_moves_to_consider
-            && self
-                .last_n_moves
-                .iter()
-                .take(num_prev_moves_to_consider)
-                .map(|m| m.num_sucks)
-                .sum::<usize>()
-                <= MINIMUM_AMT_OF_PROGRESS
-        {
-            return boards;
-        }
-
-        for (src_index, src_stack) in self.playing_area.iter().enumerate() {
-            let src_card = src_stack.last().copied();
-            if src_card.is_none() {
-                continue;
-            }
-            let src_card = src_card.unwrap();
-
-            if self.minor_collection_blocked.is_none() {
-                // // filters out a useless move: there is never any reason to block the minor pile
-                // // from a stack that only has one card
-                if src_stack.len() == 1 {
-                    continue;
+        Some(Command::Share) => {
+            let deal = read_deal(args.from_seed);
+            let mut board = Board::parse(&deal);
+            board.suck_readies_into_receptacles();
+            let solution = solve(&board).unwrap_or_else(|| {
+                Failure::NoSolution {
+                    fingerprint: Some(board.fingerprint_hex()),
                 }
-
-                let mut new_board = self.clone();
-                let card = new_board.playing_area[src_index].pop().unwrap();
-                new_board.minor_collection_blocked = Some(card);
-                let sucked_cards = new_board.suck_readies_into_receptacles();
-                let moov = Move {
-                    from: MoveLocation::PlayingArea {
-                        pile: src_index,
-                        depth: self.playing_area[src_index].len() - 1,
-                    },
-                    to: MoveLocation::BlockMinorPiles,
-                    card,
-                    num_sucks: sucked_cards.len(),
+                .exit()
+            });
+            println!("{}", ShareCode::new(&board, &solution, &rules).serialize());
+            return;
+        }
+        Some(Command::LoadShare { code }) => {
+            let deal = read_deal(args.from_seed);
+            let mut board = Board::parse(&deal);
+            board.suck_readies_into_receptacles();
+            let code = ShareCode::parse(code);
+            let solution = code
+                .replay(&board)
+                .unwrap_or_else(|| panic!("that code isn't for this deal"));
+            for moov in &solution.moves {
+                emit_move(
+                    &mut board,
+                    moov,
+                    &code.rules,
+                    protocol,
+                    timing.as_ref(),
+                    &mut None,
+                );
+            }
+            return;
+        }
+        Some(Command::Retrograde { cards }) => {
+            print!("{}", retrograde::analyze(*cards, &rules).serialize());
+            return;
+        }
+        Some(Command::Minimize {
+            deal,
+            predicate,
+            max_expansions,
+        }) => {
+            let deal = read(deal);
+            bundle::note_deal(&deal);
+            let mut board = Board::parse(&deal);
+            board.suck_readies_into_receptacles();
+            let options = SearchOptions {
+                num_prev_moves: search::OLD,
+                heuristic: args.heuristic.into(),
+                rules,
+            };
+            let reproduces = |b: &Board| {
+                let reproduces = match predicate {
+                    PredicateArg::Oom => minimize::runs_away(b, options, *max_expansions),
+                    PredicateArg::Unsolved => solve(b).is_none(),
                 };
-                boards.push((new_board.with_prev_move(moov), moov));
+                eprintln!(
+                    "{} cards: {}",
+                    b.num_cards_remaining(),
+                    if reproduces {
+                        "still goes wrong"
+                    } else {
+                        "fine"
+                    }
+                );
+                reproduces
+            };
+            if !reproduces(&board) {
+                eprintln!("the deal doesn't go wrong to begin with");
+                process::exit(1);
             }
-
-            for (dst_index, dst_stack) in self.playing_area.iter().enumerate() {
-                // moving a card to its own stack isn't a move
-                if src_index == dst_index {
+            print!("{}", minimize::minimize(&board, reproduces).serialize());
+            return;
+        }
+        Some(Command::TightenCorpus {
+            dir,
+            max_expansions,
+        }) => {
+            let mut entries = corpus::load(dir).or_fail(|| format!("read {}", dir.display()));
+            for entry in &mut entries {
+                let mut board = Board::parse(&entry.deal);
+                board.suck_readies_into_receptacles();
+                let Some(solution) = solve(&board) else {
+                    eprintln!(
+                        "{}: no solution, leaving it at {}",
+                        entry.name, entry.length
+                    );
                     continue;
+                };
+                let shortest = shorten::shorten(&board, &solution, &rules, *max_expansions);
+                let length = shortest.moves.len().min(entry.length);
+                if length < entry.length {
+                    entry.version = Some(provenance::version());
                 }
-                // filters out a non-progress move: moving a card from a 1-stack to another 1-stack
-                if src_stack.len() == 1 && dst_stack.is_empty() {
-                    continue;
+                eprintln!(
+                    "{}: recorded {}, solved in {}, shortened to {}",
+                    entry.name,
+                    entry.length,
+                    solution.moves.len(),
+                    shortest.moves.len()
+                );
+                entry.length = length;
+            }
+            corpus::save_index(dir, &entries).or_fail(|| format!("write {}", dir.display()));
+            return;
+        }
+        Some(Command::Compare { old, new, deals }) => {
+            let deals = compare::load_deals(deals).or_fail(|| format!("read {}", deals.display()));
+            let load_or_run = |path: &Path| {
+                if path.exists() {
+                    return compare::parse(&read(path))
+                        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
                 }
-                if dst_stack.is_empty() || dst_stack.last().unwrap().is_next_or_prev(src_card) {
-                    let mut new_board = self.clone();
-                    let src_card = new_board.playing_area[src_index].pop().unwrap();
-                    new_board.playing_area[dst_index].push(src_card);
-                    let sucked_cards = new_board.suck_readies_into_receptacles();
-                    let moov = Move {
-                        from: MoveLocation::PlayingArea {
-                            pile: src_index,
-                            depth: self.playing_area[src_index].len() - 1,
-                        },
-                        to: MoveLocation::PlayingArea {
-                            pile: dst_index,
-                            depth: self.playing_area[dst_index].len(),
-                        },
-                        card: src_card,
-                        num_sucks: sucked_cards.len(),
-                    };
-                    boards.push((new_board.with_prev_move(moov), moov));
+                let runs = compare::run(&deals, &solve);
+                fs::write(path, compare::serialize(&runs))
+                    .or_fail(|| format!("write {}", path.display()));
+                runs
+            };
+            let old_runs = load_or_run(old);
+            let new_runs = if old == new {
+                old_runs.clone()
+            } else {
+                load_or_run(new)
+            };
+            let comparison = compare::compare(&old_runs, &new_runs);
+            print!("{}", comparison.serialize());
+            if comparison.num_regressions() > 0 {
+                process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Report {
+            run,
+            deals,
+            heatmap,
+        }) => {
+            let text = read(run);
+            let runs = compare::parse(&text).unwrap_or_else(|e| panic!("{}: {}", run.display(), e));
+            let deals = compare::load_deals(deals).or_fail(|| format!("read {}", deals.display()));
+            let rows = heatmap::rows(&runs, &deals, &rules);
+            let version = compare::parse_version(&text).unwrap_or_else(|| "unknown".to_string());
+            fs::write(heatmap, heatmap::html(&rows, &version))
+                .or_fail(|| format!("write {}", heatmap.display()));
+            return;
+        }
+        Some(Command::Culprits {
+            deals,
+            max_expansions,
+        }) => {
+            let mut report = culprits::Report::default();
+            for path in deals {
+                let board = Board::parse(&read(path));
+                let post_mortem = culprits::post_mortem(&board, &rules, *max_expansions);
+                match &post_mortem {
+                    PostMortem::Winnable => eprintln!("{}: winnable", path.display()),
+                    PostMortem::Unknown => eprintln!("{}: didn't finish", path.display()),
+                    PostMortem::Lost { closest, culprits } => eprintln!(
+                        "{}: lost, {} cards left at best, {} culprits",
+                        path.display(),
+                        closest.num_cards_remaining(),
+                        culprits.len()
+                    ),
                 }
+                report.add(&post_mortem);
+            }
+            print!("{}", report.serialize());
+            return;
+        }
+        Some(Command::Drill {
+            pattern,
+            candidates,
+        }) => {
+            let tactic: Tactic = (*pattern).into();
+            let seed = unknown::seed();
+            let Some(drill) = drill::drill(tactic, *candidates, seed, &rules) else {
+                eprintln!("couldn't solve any of the deals, try another --seed");
+                process::exit(1);
+            };
+            eprintln!(
+                "--from-seed {}: {} of the {} moves are {}",
+                drill.seed,
+                drill.score,
+                drill.solution.moves.len(),
+                tactic.serialize()
+            );
+            print!("{}", drill.deal.serialize());
+            return;
+        }
+        Some(Command::FitCosts { logs, name }) => {
+            let mut played = vec![];
+            for path in logs {
+                played.extend(
+                    timing::parse_log(&read(path))
+                        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e)),
+                );
+            }
+            let (profile, rms_ms) = timing::fit(&played).unwrap_or_else(|e| panic!("{}", e));
+            println!(
+                "# fitted to {} moves, off by {:.0}ms on a typical one",
+                played.len(),
+                rms_ms
+            );
+            print!("{}", profile.serialize(name));
+            return;
+        }
+        Some(Command::CalibrateEval { deals, budget_ms }) => {
+            let deals = compare::load_deals(deals).or_fail(|| format!("read {}", deals.display()));
+            let mut samples = vec![];
+            let mut num_timed_out = 0;
+            for (_, deal) in &deals {
+                let mut board = Board::parse(deal);
+                board.suck_readies_into_receptacles();
+                let verdict = session::decide(
+                    &board,
+                    args.heuristic.into(),
+                    &rules,
+                    *budget_ms,
+                    &search::NUM_PREV_MOVES_TO_CONSIDERS,
+                );
+                num_timed_out += (verdict == session::Verdict::TimedOut) as usize;
+                samples.extend(evalbar::samples(&board, &verdict, &rules, evalbar::EVAL_MS));
             }
+            let (calibration, brier) = evalbar::fit(&samples).unwrap_or_else(|e| panic!("{}", e));
+            println!(
+                "# fitted to {} positions from {} deals ({} ran out of time and were left out), \
+                 brier score {:.3}",
+                samples.len(),
+                deals.len(),
+                num_timed_out,
+                brier
+            );
+            for line in evalbar::reliability(&calibration, &samples).lines() {
+                println!("# {}", line);
+            }
+            print!("{}", calibration.serialize());
+            return;
+        }
+        Some(Command::Rules { format }) => {
+            print!("{}", rules_doc::document(&rules, (*format).into()));
+            return;
+        }
+        #[cfg(feature = "bot")]
+        Some(Command::Bot {
+            budget_ms,
+            image_dir,
+        }) => {
+            bot::run(
+                &mut stdin().lock(),
+                &mut Unbuffered(stdout()),
+                &rules,
+                *budget_ms,
+                image_dir.as_deref(),
+            )
+            .or_fail(|| "talk to the relay".to_string());
+            return;
         }
+        None => {}
+    }
 
-        // unblock the minor collection piles
-        if let Some(card) = self.minor_collection_blocked {
-            // TODO: this is duplicated from above, we could consolidate if need be
-            for (dst_index, dst_stack) in self.playing_area.iter().enumerate() {
-                if dst_stack.is_empty() || dst_stack.last().unwrap().is_next_or_prev(card) {
-                    let mut new_board = self.clone();
-                    let card = new_board.minor_collection_blocked.take().unwrap();
-                    new_board.playing_area[dst_index].push(card);
-                    let sucked_cards = new_board.suck_readies_into_receptacles();
-                    let moov = Move {
-                        from: MoveLocation::BlockMinorPiles,
-                        to: MoveLocation::PlayingArea {
-                            pile: dst_index,
-                            depth: self.playing_area[dst_index].len(),
-                        },
-                        card,
-                        num_sucks: sucked_cards.len(),
-                    };
-                    boards.push((new_board.with_prev_move(moov), moov));
-                }
+    // one line per move, FINGERPRINT<tab>MOVE, where the move's always in the newest protocol and
+    // the fingerprint (in hex) is of the board after it
+    let mut journal = args.journal.as_ref().map(|path| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .or_fail(|| format!("open {}", path.display()))
+    });
+    // the moves we've already sent, and where they left the board, see --first-move-deadline
+    let mut num_sent = 0;
+    let mut sent_board = None;
+
+    let init = read_deal(args.from_seed);
+    // where the cards are that we still need to see, if the deal had unknowns in it
+    let mut reread = vec![];
+    // set if --ensemble-report found the variants at odds
+    let mut disagreement = false;
+    // the deal we actually solved, if it isn't the one we were given: a misread card swapped for
+    // what must have been there, or the unknowns filled in, see --record-corpus
+    let mut solved_deal = None;
+    let (b, solution) = if unknown::unknown_positions(&init).is_empty() {
+        let mut b = Board::parse(&init);
+        b.suck_readies_into_receptacles();
+        dbg!(&b);
+        // a card turning up twice means something was definitely misread, so don't bother solving
+        // as is
+        let num_cards = b.playing_area.iter().map(Vec::len).sum::<usize>();
+        let has_duplicates = b.tableau.len() < num_cards;
+        // the portfolio's solutions are only worth looking at one by one for the report, or to pick
+        // the quickest to play rather than the shortest
+        let mut first_try = |b: &Board| {
+            if args.ensemble_report.is_none() && timing.is_none() {
+                return solve(b);
+            }
+            let results = search::solve_all(b, &portfolio, &rules, args.goal, args.threads);
+            if let Some(path) = &args.ensemble_report {
+                let report = Report::new(&results);
+                fs::write(path, report.serialize()).or_fail(|| format!("write {}", path.display()));
+                disagreement = report.disagree;
+            }
+            match &timing {
+                Some(timing) => results
+                    .into_iter()
+                    .filter_map(|(_, solution)| solution)
+                    .min_by_key(|solution| timing.solution_ms(solution)),
+                None => search::shortest(results),
+            }
+        };
+        // see dedup.rs
+        let claim = args.lock_dir.as_ref().map(|dir| {
+            dedup::claim(dir, &b).or_fail(|| format!("lock the deal in {}", dir.display()))
+        });
+        let (lock, answered) = match claim {
+            Some(Claim::Mine(lock)) => (Some(lock), None),
+            Some(Claim::Answered(solution)) => (None, Some(solution)),
+            None => (None, None),
+        };
+        let fingerprint = b.fingerprint_hex();
+        let solution = match answered {
+            Some(solution) => solution,
+            None => (!has_duplicates)
+                .then(|| match args.first_move_deadline {
+                    Some(deadline_ms) => {
+                        match search::anytime(&b, args.heuristic.into(), &rules, deadline_ms) {
+                            Ok(solution) => Some(solution),
+                            Err(best_line) if best_line.is_empty() => first_try(&b),
+                            // send the first move now, and work out the rest while it's being
+                            // played
+                            Err(best_line) => {
+                                let mut board = b.clone();
+                                let first = &best_line[0];
+                                emit_move(
+                                    &mut board,
+                                    first,
+                                    &rules,
+                                    protocol,
+                                    timing.as_ref(),
+                                    &mut journal,
+                                );
+                                num_sent = 1;
+                                sent_board = Some(board.clone());
+                                let mut solution = first_try(&board)?;
+                                solution.moves.insert(0, first.clone());
+                                Some(solution)
+                            }
+                        }
+                    }
+                    None => first_try(&b),
+                })
+                .flatten()
+                .or_else(|| {
+                    report_suspects(&init);
+                    // we can't take back a move we've sent
+                    if !args.try_substitutions || num_sent > 0 {
+                        return None;
+                    }
+                    for suspect in confidence::read_confidences(&init)
+                        .iter()
+                        .take(NUM_SUSPECTS)
+                    {
+                        // as read, since a suspect that's been sucked in isn't where it was
+                        let read = Board::parse(&init);
+                        for (card, mut substituted) in confidence::substitutions(&read, suspect) {
+                            let deal = substituted.serialize();
+                            substituted.suck_readies_into_receptacles();
+                            if let Some(solution) = solve(&substituted) {
+                                warnings::warn(Warning::InputCorrected {
+                                    what: format!(
+                                        "read pile {} depth {} as {} but it must have been {}",
+                                        suspect.pile,
+                                        suspect.depth,
+                                        suspect.card.serialize(),
+                                        card.serialize()
+                                    ),
+                                });
+                                b = substituted;
+                                solved_deal = Some(deal);
+                                return Some(solution);
+                            }
+                        }
+                    }
+                    None
+                }),
+        };
+        if let Some(lock) = lock {
+            // a solution to a board with a card swapped is no good to anyone who read it as it
+            // was, and a search that gave up isn't an answer to keep, see dedup.rs
+            let answer = solution.is_some() || search::proven_dead(&b);
+            if b.fingerprint_hex() == fingerprint && answer {
+                lock.finish(solution.as_ref())
+                    .or_fail(|| format!("write the result for {}", fingerprint));
+            }
+        }
+        (b, solution)
+    } else {
+        match args.sample {
+            Some(num_samples) => {
+                // the unknowns stay where they are, since the move's of a card we can see
+                reread = unknown::unknown_positions(&init);
+                let (board, moov) = unknown::best_first_move(&init, num_samples, &solve, &rules)
+                    .unwrap_or_else(|| Failure::NoSolution { fingerprint: None }.exit());
+                (board, Some(Solution { moves: vec![moov] }))
+            }
+            None => {
+                let plan = unknown::plan(&init, &solve, &rules)
+                    .unwrap_or_else(|| Failure::NoSolution { fingerprint: None }.exit());
+                reread = plan.reread;
+                solved_deal = Some(plan.board.serialize());
+                (plan.board, Some(plan.solution))
             }
         }
+    };
+    save_tablebase(args.tablebase_file.as_deref());
+    for profile in search::take_profiles() {
+        eprint!("{}", profile.serialize());
+    }
+    for trace in search::take_traces() {
+        eprint!("{}", trace);
+    }
+    let mut solution = solution.unwrap_or_else(|| {
+        Failure::NoSolution {
+            fingerprint: Some(b.fingerprint_hex()),
+        }
+        .exit()
+    });
+    // how much --epsilon might have cost us: the shortest solution's at least as long as the
+    // relaxed solution, and if the unpruned weighted A* got one, at least that over 1 + epsilon.
+    // only for a board we've been given all of, not a guess at the unknowns
+    if let (Some(epsilon), Heuristic::RelaxedLowerBound) = (args.epsilon, args.heuristic.into()) {
+        let length = solution.moves.len();
+        let mut shortest_at_least = RelaxedBounds::new().lower_bound(&b);
+        if let Some(bounded) = search::bounded_shortest(&b).filter(|_| reread.is_empty()) {
+            shortest_at_least = shortest_at_least.max(bounded);
+        }
+        eprintln!(
+            "epsilon={} moves={} shortest>={}",
+            epsilon, length, shortest_at_least
+        );
+    }
+    if args.check_collisions {
+        eprintln!("collisions={}", closed::num_collisions());
+    }
+    if memo::enabled() {
+        eprintln!("memo {}", memo::serialize_hits());
+    }
+    if let Some(path) = &args.certificate {
+        // the variants that can't prove anything don't leave one, and nor do searches of other
+        // boards (one phase of a decomposition, say)
+        let fingerprint = b.fingerprint_hex();
+        let certificate = search::take_certificates()
+            .into_iter()
+            .filter(|certificate| certificate.fingerprint == fingerprint)
+            .max_by_key(|certificate| certificate.lower_bound);
+        match certificate {
+            Some(certificate) => fs::write(path, certificate.serialize(solution.moves.len()))
+                .or_fail(|| format!("write {}", path.display())),
+            None => eprintln!("no certificate, no exact search finished from this board"),
+        }
+    }
+    if args.human_style {
+        // leaving the moves we've sent where they are
+        let mut rest = Solution {
+            moves: solution.moves.split_off(num_sent),
+        };
+        rest = shape::humanize(sent_board.as_ref().unwrap_or(&b), &rest, &rules);
+        solution.moves.extend(rest.moves);
+    }
 
-        boards
+    let mut board = sent_board.unwrap_or_else(|| b.clone());
+    for moov in &solution.moves[num_sent..] {
+        emit_move(
+            &mut board,
+            moov,
+            &rules,
+            protocol,
+            timing.as_ref(),
+            &mut journal,
+        );
     }
-}
 
-fn main() {
-    let mut init = String::new();
-    stdin().read_to_string(&mut init).unwrap();
-    let mut b = Board::parse(&init);
-    b.suck_readies_into_receptacles();
-    dbg!(&b);
-
-    let path = NUM_PREV_MOVES_TO_CONSIDERS
-        .into_par_iter()
-        .filter_map(|num_prev_moves| {
-            let (path, _score): (Vec<(Board, Option<Move>)>, usize) = astar(
-                &(b.clone(), None),
-                |(b, _path)| {
-                    b.next_boards(num_prev_moves)
-                        .into_iter()
-                        .map(|(board, moov)| ((board.clone(), Some(moov)), 0))
-                },
-                |(b, _move)| b.num_cards_remaining(),
-                |(b, _move)| b.is_done(),
-            )?;
-            Some(path)
-        })
-        .min_by_key(|path| path.len())
-        .unwrap();
+    // we can't go any further until the driver's had another look at these cards, and given us the
+    // board again
+    if !reread.is_empty() {
+        let positions: Vec<String> = reread
+            .iter()
+            .map(|(pile, depth)| format!("{}:{}", pile, depth))
+            .collect();
+        say(&format!("REREAD {}", positions.join(",")));
+    }
+
+    if let Some(path) = &args.trajectory {
+        let steps = search::trajectory(&b, &solution, args.heuristic.into(), &rules);
+        write_lines(
+            path,
+            steps
+                .iter()
+                .map(|(before, after)| format!("{} {}", before, after)),
+        );
+    }
+
+    if let Some(path) = &args.reversibility {
+        let annotations = reversibility::annotate(&b, &solution, &rules);
+        write_lines(
+            path,
+            annotations
+                .iter()
+                .map(|reversibility| reversibility.serialize().to_string()),
+        );
+    }
+
+    if let (Some(path), Some(timing)) = (&args.recording, &timing) {
+        fs::write(path, timing.recording(&solution))
+            .or_fail(|| format!("write {}", path.display()));
+    }
+
+    if let Some(path) = &args.alternatives {
+        let annotations = alternatives::annotate(&b, &solution, args.heuristic.into(), &rules);
+        write_lines(
+            path,
+            annotations.iter().map(|alternatives| {
+                let alternatives: Vec<String> =
+                    alternatives.iter().map(|a| a.serialize()).collect();
+                alternatives.join(",")
+            }),
+        );
+    }
+
+    if let Some(path) = &args.dependencies {
+        let dependencies = partial_order::dependencies(&b, &solution, &rules);
+        write_lines(
+            path,
+            dependencies.iter().map(|waits_for| {
+                let waits_for: Vec<String> = waits_for.iter().map(usize::to_string).collect();
+                waits_for.join(",")
+            }),
+        );
+    }
 
-    for moov in path.iter().filter_map(|(_, moov)| moov.as_ref()) {
-        eprintln!("{} ({} sucks)", moov, moov.num_sucks);
-        println!("{}", moov.serialize());
+    // the corpus is for deals we clear, a partial solution's length doesn't say anything
+    if let (Some(dir), Goal::ClearBoard, true) = (&args.record_corpus, args.goal, reread.is_empty())
+    {
+        let name = format!(
+            "{}-{}",
+            if disagreement { "disagreement" } else { "deal" },
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis()
+        );
+        let entry = CorpusEntry {
+            name,
+            deal: solved_deal.unwrap_or(init),
+            length: solution.moves.len(),
+            version: Some(provenance::version()),
+        };
+        corpus::append(dir, &entry).or_fail(|| format!("add to {}", dir.display()));
     }
 }
@@ -0,0 +1,130 @@
+use crate::board::{Board, Move, Solution};
+use crate::rules::Rules;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+// what we've already found out about boards this process has been through, so the next variant in
+// the portfolio, the next restart or the next deal doesn't have to find it out again: the rest of
+// a solution from every board along one we found, or that a board can't be cleared at all (the
+// unpruned search went through everything under it). kept for the most recently used boards only,
+// up to set_capacity of them, for sessions that go on for days
+//
+// a board with the rest of a solution is a goal, the same as one wrap_up can finish, but only for
+// the searches that don't care how long the solution is (see search::finish_from): the rest of
+// someone else's solution isn't the shortest way from there. and since which variant gets there
+// first decides what's kept, it's off unless asked for, so runs without it come out the same every
+// time
+//
+// it's not the same thing as nogoods.rs, which only knows about dead ends it's worked out a level
+// at a time, and never forgets one
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Known {
+    Solved(Vec<Move>),
+    Dead,
+}
+
+type Key = (Rules, [u8; 16]);
+
+#[derive(Default)]
+struct Lru {
+    // and when each was last used, by the tick
+    entries: HashMap<Key, (Known, u64)>,
+    by_tick: BTreeMap<u64, Key>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &Key) -> Option<Known> {
+        let (known, last_used) = self.entries.get_mut(key)?;
+        self.by_tick.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.by_tick.insert(self.tick, *key);
+        Some(known.clone())
+    }
+
+    fn insert(&mut self, key: Key, known: Known, capacity: usize) {
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.by_tick.remove(&last_used);
+        }
+        while self.entries.len() >= capacity {
+            let Some((_, oldest)) = self.by_tick.pop_first() else {
+                return;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.entries.insert(key, (known, self.tick));
+        self.by_tick.insert(self.tick, key);
+    }
+}
+
+static MEMO: LazyLock<Mutex<Lru>> = LazyLock::new(Mutex::default);
+// how many boards MEMO keeps. outside the lock, so the searches can tell it's off without taking it
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+
+static NUM_LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+static NUM_HITS: AtomicUsize = AtomicUsize::new(0);
+
+// for --memo-size. 0, the default, is off
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    CAPACITY.load(Ordering::Relaxed) > 0
+}
+
+pub fn lookup(board: &Board, rules: &Rules) -> Option<Known> {
+    if !enabled() {
+        return None;
+    }
+    let key = (*rules, board.fingerprint());
+    NUM_LOOKUPS.fetch_add(1, Ordering::Relaxed);
+    let known = MEMO.lock().unwrap().touch(&key);
+    if known.is_some() {
+        NUM_HITS.fetch_add(1, Ordering::Relaxed);
+    }
+    known
+}
+
+pub fn is_dead(board: &Board, rules: &Rules) -> bool {
+    lookup(board, rules) == Some(Known::Dead)
+}
+
+// the rest of solution from each board along it
+pub fn remember_solution(board: &Board, solution: &Solution, rules: &Rules) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    if capacity == 0 {
+        return;
+    }
+    let mut board = board.clone();
+    let mut memo = MEMO.lock().unwrap();
+    for (i, moov) in solution.moves.iter().enumerate() {
+        let rest = Known::Solved(solution.moves[i..].to_vec());
+        memo.insert((*rules, board.fingerprint()), rest, capacity);
+        board = board.play(moov.from, moov.to, rules).unwrap().0;
+    }
+}
+
+pub fn remember_dead(board: &Board, rules: &Rules) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    if capacity > 0 {
+        let key = (*rules, board.fingerprint());
+        MEMO.lock().unwrap().insert(key, Known::Dead, capacity);
+    }
+}
+
+// hits=H lookups=L hit_rate=R since we started, for the end of a run or a session's STATS
+pub fn serialize_hits() -> String {
+    let num_hits = NUM_HITS.load(Ordering::Relaxed);
+    let num_lookups = NUM_LOOKUPS.load(Ordering::Relaxed);
+    format!(
+        "hits={} lookups={} hit_rate={:.3}",
+        num_hits,
+        num_lookups,
+        num_hits as f64 / num_lookups.max(1) as f64
+    )
+}
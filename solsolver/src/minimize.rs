@@ -0,0 +1,102 @@
+use crate::board::Board;
+use crate::card::{Card, MajorValue, MinorValue, Suit};
+use crate::search::{Search, SearchOptions, SearchStatus};
+
+// making a board that trips something up smaller, for a bug report anyone can look at by hand. we
+// only ever take cards away by sending them home, the next card its foundation wants from wherever
+// it is, so every board on the way down is one a real game could get to (the whole deck's still
+// accounted for, just with more of it home already)
+
+// what gets sent home next: the next major for either major foundation, or a suit's next card (the
+// suit by where it comes in Suit::ALL)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Removal {
+    MajorLower,
+    MajorHigher,
+    Minor(usize),
+}
+
+const REMOVALS: [Removal; 6] = [
+    Removal::MajorLower,
+    Removal::MajorHigher,
+    Removal::Minor(0),
+    Removal::Minor(1),
+    Removal::Minor(2),
+    Removal::Minor(3),
+];
+
+// the board with one more card sent home, or None if it's already home (or up on the other major
+// foundation)
+fn send_home(board: &Board, removal: Removal) -> Option<Board> {
+    let (card, foundation) = match removal {
+        Removal::MajorLower => {
+            let card = Card::Major(MajorValue(board.major_lower_stack.len() as u8));
+            (card, format!("MAJ_LO:{}", card.serialize()))
+        }
+        Removal::MajorHigher => {
+            let home = board.major_higher_stack.len() as u8;
+            let card = Card::Major(MajorValue(MajorValue::last().0.checked_sub(home)?));
+            (card, format!("MAJ_HI:{}", card.serialize()))
+        }
+        Removal::Minor(index) => {
+            let suit = Suit::ALL[index];
+            let value = MinorValue(board.minor_collection_piles[index].len() as u8 + 1);
+            let card = Card::Minor { suit, value };
+            (card, format!("{}:{}", suit.serialize(), card.serialize()))
+        }
+    };
+    let name = card.serialize();
+    let key = foundation.split(':').next().unwrap();
+    let mut found = false;
+    let lines: Vec<String> = board
+        .serialize()
+        .lines()
+        .filter_map(|line| {
+            if line == format!("BLOCK:{}", name) {
+                found = true;
+                return None;
+            }
+            if line.starts_with(&format!("{}:", key)) {
+                return None;
+            }
+            let cards: Vec<&str> = line.split(',').collect();
+            if !line.contains(':') && cards.contains(&name.as_str()) {
+                found = true;
+                let rest: Vec<&str> = cards.into_iter().filter(|&c| c != name).collect();
+                return Some(rest.join(","));
+            }
+            Some(line.to_string())
+        })
+        .collect();
+    if !found {
+        return None;
+    }
+    let mut smaller = Board::parse(&(lines.join("\n") + "\n" + &foundation + "\n"));
+    smaller.suck_readies_into_receptacles();
+    Some(smaller)
+}
+
+// the smallest board we can get to by sending cards home one at a time, with reproduces true the
+// whole way. greedy, so it's a board where sending any one more card home makes the problem go
+// away, not necessarily the smallest there is. reproduces should be true of the board we start with
+pub fn minimize(board: &Board, reproduces: impl Fn(&Board) -> bool) -> Board {
+    let mut board = board.clone();
+    'shrinking: loop {
+        for removal in REMOVALS {
+            let Some(smaller) = send_home(&board, removal) else {
+                continue;
+            };
+            if reproduces(&smaller) {
+                board = smaller;
+                continue 'shrinking;
+            }
+        }
+        return board;
+    }
+}
+
+// a search that's still going after max_expansions boards. the one we'd run out of memory on, if
+// we'd let it
+pub fn runs_away(board: &Board, options: SearchOptions, max_expansions: usize) -> bool {
+    Search::new(board, options).step_expansions(max_expansions) == SearchStatus::InProgress
+}
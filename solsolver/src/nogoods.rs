@@ -0,0 +1,41 @@
+use crate::board::{Board, Move};
+use crate::rules::Rules;
+use std::collections::HashSet;
+use std::sync::{LazyLock, RwLock};
+
+// boards we've found can't be cleared, so nothing that comes after (another variant in the
+// portfolio, the next restart, the next deal) goes looking through them again. a board's dead if
+// it isn't cleared and it has no moves, or all its moves lead to dead boards. that's only ever
+// true of the board itself, not how we got there or how much searching we had left, so it holds
+// for every search under the same rules
+//
+// we only find out one level at a time: a board gets marked when it's expanded and every move from
+// it goes somewhere already marked. so the dead ends near the bottom get found first, and each
+// search after that can mark the ones a level further up
+
+// past this many we stop adding, for sessions that go on for days
+const MAX_NOGOODS: usize = 1_000_000;
+
+// by the rules and Board::fingerprint
+type Nogoods = HashSet<(Rules, [u8; 16])>;
+
+static NOGOODS: LazyLock<RwLock<Nogoods>> = LazyLock::new(|| RwLock::new(HashSet::new()));
+
+// the moves from a board that isn't cleared, minus the ones to boards we know are dead. if that's
+// all of them, this one's dead too
+pub fn prune(board: &Board, next_boards: Vec<(Board, Move)>, rules: &Rules) -> Vec<(Board, Move)> {
+    let live: Vec<(Board, Move)> = {
+        let nogoods = NOGOODS.read().unwrap();
+        next_boards
+            .into_iter()
+            .filter(|(next, _)| !nogoods.contains(&(*rules, next.fingerprint())))
+            .collect()
+    };
+    if live.is_empty() {
+        let mut nogoods = NOGOODS.write().unwrap();
+        if nogoods.len() < MAX_NOGOODS {
+            nogoods.insert((*rules, board.fingerprint()));
+        }
+    }
+    live
+}
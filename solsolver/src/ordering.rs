@@ -0,0 +1,67 @@
+use crate::board::{Board, Move};
+use std::sync::OnceLock;
+
+// which of the boards the search likes equally well (the same estimate, as far along and setting up
+// as many sucks) it tries first, for --move-order. without any policies that's left to however the
+// open list shakes out (see search::Queued), which is what we've always done. with them, a move one
+// of them prefers goes ahead of one it doesn't. they're given in order of importance: the second
+// only decides between moves the first one likes as much, and so on. none of this changes which
+// boards get searched, or the cost of what's found, only which of a tie goes first, so a greedy
+// search (which ties all the time) feels it a lot more than an exact one
+//
+// an empty pile's the one place any card can go, so empties-pile seemed like the obvious one, but
+// over 30 seeded deals and the EXAMPLE_ ones (see compare.rs) it solved the same 9 and its
+// solutions came out 15% longer in all. frees-block did best there (the same 9, a third fewer
+// moves in all and less than half the time), and sends-home was in between. they're all off unless
+// asked for until they've been tried on more deals than that
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveOrder {
+    // moves that leave more of the piles empty than there were
+    EmptiesPile,
+    // moves that put cards on the foundations, by hand or by them getting sucked in after
+    SendsHome,
+    // moves that leave the block with nothing on it
+    FreesBlock,
+}
+
+impl MoveOrder {
+    pub fn prefers(&self, board: &Board, next_board: &Board, moov: &Move) -> bool {
+        match self {
+            MoveOrder::EmptiesPile => num_empty_piles(next_board) > num_empty_piles(board),
+            MoveOrder::SendsHome => moov.num_cards_home() > 0,
+            MoveOrder::FreesBlock => next_board.minor_collection_blocked.is_none(),
+        }
+    }
+}
+
+fn num_empty_piles(board: &Board) -> usize {
+    board
+        .playing_area
+        .iter()
+        .filter(|pile| pile.is_empty())
+        .count()
+}
+
+static INSTALLED: OnceLock<Vec<MoveOrder>> = OnceLock::new();
+
+// orders the searches' ties with these policies from now on, most important first
+pub fn install(policies: Vec<MoveOrder>) {
+    if policies.len() > u32::BITS as usize {
+        panic!("at most {} move orders at once", u32::BITS);
+    }
+    if INSTALLED.set(policies).is_err() {
+        panic!("there's already a move order installed");
+    }
+}
+
+// where a move goes among the ones it's tied with, lowest first. each policy's a bit, the first
+// policy the most significant, set when it doesn't prefer the move. 0 for everything without any
+pub fn rank(board: &Board, next_board: &Board, moov: &Move) -> u32 {
+    let Some(policies) = INSTALLED.get() else {
+        return 0;
+    };
+    policies.iter().fold(0, |rank, policy| {
+        (rank << 1) | !policy.prefers(board, next_board, moov) as u32
+    })
+}
@@ -0,0 +1,235 @@
+use crate::board::{Board, Protocol};
+use crate::color;
+use crate::evalbar::{self, Calibration};
+use crate::session::{Progress, Stats, Verdict};
+use crate::warnings::escape;
+use std::io;
+use std::iter;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tungstenite::{Message, WebSocket};
+
+// what the session's thinking, for a browser source overlay on a stream. each browser that connects
+// to the WebSocket gets the latest snapshot straight away, then every one after it as session::run
+// reports progress. a snapshot is a JSON object:
+//   status       thinking, solved, unsolvable, timed_out or seen_before
+//   fingerprint  Board::fingerprint_hex
+//   piles        the cards in each pile, bottom first, as in the deal format (e.g. "10_CUP")
+//   block        the card on the block, or null
+//   cards_left   Board::num_cards_remaining
+//   moves        how long the solution is, or null if there isn't one (yet)
+//   next_move    the first move of the solution on the wire (Protocol::V3), or null
+//   next_move_text  the same move for people, in the language from i18n.rs, or null
+//   eval         how likely the board is to be won, 0 to 100, for an eval bar (see evalbar.rs)
+//   eval_along   the same for every position along the solution, from this board to the finished
+//                one (all 100, it's a win), or null if there isn't one (yet)
+//   stats        Stats::serialize as an object, e.g. {"games":3,"solved":2,...}
+// we never read anything the browsers send
+//
+// the snapshots are worked out on a thread of their own, and only while there's a browser to send
+// them to, since the eval bar's quick look at a board takes a couple of milliseconds that the
+// session shouldn't be waiting on. if the session reports faster than that, the snapshots in
+// between are skipped. and each browser has a thread of its own to write to it, so one that's slow
+// to read (or has stopped reading without hanging up) never holds up the rest. one that falls more
+// than LAG_LIMIT behind misses the snapshots in between
+
+// a client is the way to its writer thread
+type Clients = Arc<Mutex<Vec<SyncSender<String>>>>;
+
+const LAG_LIMIT: usize = 4;
+
+pub struct Overlay {
+    updates: Sender<Option<(Update, Stats)>>,
+}
+
+fn cards(cards: impl Iterator<Item = String>) -> String {
+    let quoted: Vec<String> = cards.map(|card| format!("\"{}\"", escape(&card))).collect();
+    format!("[{}]", quoted.join(","))
+}
+
+fn or_null(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_string())
+}
+
+fn quoted(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+// "games=3 solved=2" as {"games":3,"solved":2}. every value's a number
+fn stats_object(stats: &Stats) -> String {
+    let fields: Vec<String> = stats
+        .serialize()
+        .split_whitespace()
+        .filter_map(|field| field.split_once('='))
+        .map(|(key, value)| format!("\"{}\":{}", key, value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+// writes each snapshot that comes in to the browser, until it goes away
+fn write_to(mut socket: WebSocket<TcpStream>, snapshots: mpsc::Receiver<String>) {
+    for snapshot in snapshots {
+        if socket.send(Message::text(snapshot)).is_err() {
+            return;
+        }
+    }
+}
+
+// evals is what the eval bar shows, see eval and eval_along above: the board's first, then (with
+// a solution) the rest of the positions along it
+pub fn snapshot(progress: Progress, stats: &Stats, evals: &[u8]) -> String {
+    let (status, board, verdict): (&str, &Board, Option<&Verdict>) = match progress {
+        Progress::Thinking(board) => ("thinking", board, None),
+        Progress::SeenBefore(board) => ("seen_before", board, None),
+        Progress::Decided(board, verdict) => {
+            let status = match verdict {
+                Verdict::Solved(_) => "solved",
+                Verdict::Unsolvable => "unsolvable",
+                Verdict::TimedOut => "timed_out",
+            };
+            (status, board, Some(verdict))
+        }
+    };
+    let solution = match verdict {
+        Some(Verdict::Solved(solution)) => Some(solution),
+        _ => None,
+    };
+    let next_move = solution.and_then(|solution| solution.moves.first());
+    let palette = color::palette().without_color();
+    let piles: Vec<String> = board
+        .playing_area
+        .iter()
+        .map(|pile| cards(pile.iter().map(|card| card.card().serialize())))
+        .collect();
+    let eval_along = solution.map(|_| {
+        let evals: Vec<String> = evals.iter().map(u8::to_string).collect();
+        format!("[{}]", evals.join(","))
+    });
+    format!(
+        "{{\"status\":\"{}\",\"fingerprint\":\"{}\",\"piles\":[{}],\"block\":{},\"cards_left\":{},\
+         \"moves\":{},\"next_move\":{},\"next_move_text\":{},\"eval\":{},\"eval_along\":{},\
+         \"stats\":{}}}",
+        status,
+        board.fingerprint_hex(),
+        piles.join(","),
+        or_null(
+            board
+                .minor_collection_blocked
+                .map(|card| quoted(&card.card().serialize()))
+        ),
+        board.num_cards_remaining(),
+        or_null(solution.map(|solution| solution.moves.len().to_string())),
+        or_null(next_move.map(|moov| quoted(&moov.serialize(Protocol::V3)))),
+        or_null(next_move.map(|moov| quoted(&palette.moov(moov)))),
+        evals[0],
+        or_null(eval_along),
+        stats_object(stats)
+    )
+}
+
+impl Overlay {
+    // starts listening, taking browsers on a thread of its own
+    pub fn bind(addr: impl ToSocketAddrs, calibration: Calibration) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Clients::default();
+        let (updates, received) = mpsc::channel();
+        let overlay = Self {
+            updates: updates.clone(),
+        };
+        let writers = clients.clone();
+        thread::spawn(move || send_snapshots(received, writers, calibration));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // something that isn't a browser after a WebSocket doesn't get anything
+                let Ok(socket) = tungstenite::accept(stream) else {
+                    continue;
+                };
+                let (sender, snapshots) = mpsc::sync_channel(LAG_LIMIT);
+                thread::spawn(move || write_to(socket, snapshots));
+                clients.lock().unwrap().push(sender);
+                // so it gets where things are now straight away
+                if updates.send(None).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(overlay)
+    }
+
+    // passes what the session's up to on to be sent, without waiting for it to be
+    pub fn broadcast(&self, progress: Progress, stats: &Stats) {
+        let _ = self.updates.send(Some((Update::new(progress), *stats)));
+    }
+}
+
+// what broadcast's been told, owned so it can go to the thread that works out the snapshots
+enum Update {
+    Thinking(Board),
+    SeenBefore(Board),
+    Decided(Board, Verdict),
+}
+
+impl Update {
+    fn new(progress: Progress) -> Self {
+        match progress {
+            Progress::Thinking(board) => Update::Thinking(board.clone()),
+            Progress::SeenBefore(board) => Update::SeenBefore(board.clone()),
+            Progress::Decided(board, verdict) => Update::Decided(board.clone(), verdict.clone()),
+        }
+    }
+
+    fn progress(&self) -> Progress<'_> {
+        match self {
+            Update::Thinking(board) => Progress::Thinking(board),
+            Update::SeenBefore(board) => Progress::SeenBefore(board),
+            Update::Decided(board, verdict) => Progress::Decided(board, verdict),
+        }
+    }
+}
+
+// the snapshot thread, see above. each update's a new snapshot to send everyone, or None when a
+// browser's connected and wants the latest one. the browsers' writers get them, forgetting the
+// ones that have gone away
+fn send_snapshots(
+    updates: Receiver<Option<(Update, Stats)>>,
+    clients: Clients,
+    calibration: Calibration,
+) {
+    let mut latest = None;
+    while let Ok(update) = updates.recv() {
+        // only the newest matters, if more came in while we were busy
+        for update in iter::once(update).chain(updates.try_iter()).flatten() {
+            latest = Some(update);
+        }
+        let Some((update, stats)) = &latest else {
+            continue;
+        };
+        if clients.lock().unwrap().is_empty() {
+            continue;
+        }
+        let evals = evals(&calibration, &update.progress());
+        let snapshot = snapshot(update.progress(), stats, &evals);
+        clients
+            .lock()
+            .unwrap()
+            .retain(|sender| match sender.try_send(snapshot.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+    }
+}
+
+// see snapshot. every position along a solution is on the way to a win we know about, and a deal
+// that's been proven unsolvable (now, or before for one we've seen) is as lost as it gets. the
+// calibration's only for the boards that haven't been decided either way
+fn evals(calibration: &Calibration, progress: &Progress) -> Vec<u8> {
+    match progress {
+        Progress::Decided(_, Verdict::Solved(solution)) => vec![100; solution.moves.len() + 1],
+        Progress::Decided(_, Verdict::Unsolvable) | Progress::SeenBefore(_) => vec![0],
+        Progress::Thinking(board) | Progress::Decided(board, Verdict::TimedOut) => {
+            vec![calibration.confidence(board, evalbar::EVAL_MS)]
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::board::{Board, MoveLocation, Solution, NUM_PLAYING_STACKS};
+use crate::card::NUM_SUITS;
+use crate::rules::Rules;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// which moves of a solution have to wait for which, so a driver that can drag more than one card
+// at once knows what it can overlap. two moves are independent if they don't touch any of the same
+// places (piles, the block, foundations), counting the places their sucks take cards from and put
+// them, and they can then be played either way round
+//
+// anything that sucks a minor also counts as touching the block, since a card on the block stops
+// minors being sucked, and so does moving a pile's last card
+
+// the places a move can touch, one bit each
+const BLOCK: usize = NUM_PLAYING_STACKS;
+const MAJOR_LOWER: usize = BLOCK + 1;
+const MAJOR_HIGHER: usize = MAJOR_LOWER + 1;
+const MINOR_FOUNDATIONS: usize = MAJOR_HIGHER + 1;
+const NUM_PLACES: usize = MINOR_FOUNDATIONS + NUM_SUITS;
+
+// what a move touched, going by what changed when it was played. locations is where it went from
+// and to
+fn touched(before: &Board, after: &Board, locations: &[MoveLocation]) -> u32 {
+    let mut places = 0;
+    for (pile, (a, b)) in before
+        .playing_area
+        .iter()
+        .zip(&after.playing_area)
+        .enumerate()
+    {
+        if a != b {
+            places |= 1 << pile;
+        }
+    }
+    for location in locations {
+        match location {
+            MoveLocation::PlayingArea { pile, .. } => places |= 1 << pile,
+            // the card might have been sucked straight off again
+            MoveLocation::BlockMinorPiles => places |= 1 << BLOCK,
+            _ => {}
+        }
+    }
+    // a pile's last card can only be moved while something's on the block, see Board::next_boards
+    if let Some(MoveLocation::PlayingArea { depth: 0, .. }) = locations.first() {
+        places |= 1 << BLOCK;
+    }
+    if before.minor_collection_blocked != after.minor_collection_blocked {
+        places |= 1 << BLOCK;
+    }
+    if before.major_lower_stack.len() != after.major_lower_stack.len() {
+        places |= 1 << MAJOR_LOWER;
+    }
+    if before.major_higher_stack.len() != after.major_higher_stack.len() {
+        places |= 1 << MAJOR_HIGHER;
+    }
+    for (suit, (a, b)) in before
+        .minor_collection_piles
+        .iter()
+        .zip(&after.minor_collection_piles)
+        .enumerate()
+    {
+        if a.len() != b.len() {
+            places |= 1 << (MINOR_FOUNDATIONS + suit);
+        }
+    }
+    if places >> MINOR_FOUNDATIONS != 0 {
+        places |= 1 << BLOCK;
+    }
+    places
+}
+
+// for each move, the earlier moves (by index) it has to wait for. only the last move to touch each
+// place is listed, the ones before that are waited for through it
+pub fn dependencies(board: &Board, solution: &Solution, rules: &Rules) -> Vec<Vec<usize>> {
+    let mut last_toucher: [Option<usize>; NUM_PLACES] = [None; NUM_PLACES];
+    let mut board = board.clone();
+    let mut dependencies = vec![];
+    for (i, moov) in solution.moves.iter().enumerate() {
+        let (after, _) = board.play(moov.from, moov.to, rules).unwrap();
+        let places = touched(&board, &after, &[moov.from, moov.to]);
+        let mut waits_for: Vec<usize> = (0..NUM_PLACES)
+            .filter(|place| places & (1 << place) != 0)
+            .filter_map(|place| last_toucher[place].replace(i))
+            .collect();
+        waits_for.sort_unstable();
+        waits_for.dedup();
+        dependencies.push(waits_for);
+        board = after;
+    }
+    dependencies
+}
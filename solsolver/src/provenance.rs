@@ -0,0 +1,34 @@
+use crate::board::Protocol;
+use crate::rules::RULES_VERSION;
+
+// which code something came from, for everything that outlives the run that made it: the
+// solutions in a tablebase, the lengths in a corpus, a SeenDeals file, a compare run, a bug report.
+// months later that's the difference between "the solver got this wrong" and "a solver from before
+// the fix got this wrong". one line, no tabs, e.g.
+//
+//   v3.2-14-gab12cd3 rules=1 protocol=3 features=std,parallel,memcap
+//
+// the git describe is the build's (see build.rs), the rules and protocol are the newest this build
+// knows, whatever it was run with
+
+pub fn version() -> String {
+    let features: Vec<&str> = [
+        ("std", cfg!(feature = "std")),
+        ("parallel", cfg!(feature = "parallel")),
+        ("memcap", cfg!(feature = "memcap")),
+        ("bot", cfg!(feature = "bot")),
+        ("overlay", cfg!(feature = "overlay")),
+        ("serde", cfg!(feature = "serde")),
+    ]
+    .into_iter()
+    .filter(|&(_, on)| on)
+    .map(|(name, _)| name)
+    .collect();
+    format!(
+        "{} rules={} protocol={} features={}",
+        env!("SOLSOLVER_GIT_DESCRIBE"),
+        RULES_VERSION,
+        Protocol::NEWEST.number(),
+        features.join(",")
+    )
+}
@@ -0,0 +1,178 @@
+use crate::board::{Board, Solution};
+use crate::bundle;
+use crate::rules::Rules;
+use crate::search::{solve_variant, Goal, Variant};
+use crate::warnings::{warn, Warning};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use std::{
+    num::NonZero,
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::AtomicUsize,
+    sync::mpsc,
+    thread,
+};
+
+// the portfolio's variants racing each other on the same board. each variant's a task of its own,
+// run on one of num_threads threads (with the parallel feature, otherwise they go one after
+// another) which take the next one as they finish the last, and they tell whoever's watching how
+// they got on over a channel as they finish, fastest first. the watcher can call off any one of
+// them, or the lot, with their Cancels, and a task that panics only takes itself down: the rest
+// carry on, and it's reported as a Failed (and a VariantFailed warning, and a bug report if they're
+// on). a variant only says anything once it's done, there's nothing from partway through a search
+
+// a flag to stop searching. a search looks at the ones for the thread it's on (see cancelled)
+// every board it expands, and gives up with nothing once one's set
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+std::thread_local! {
+    // the race's and the variant's, for whichever variant's running on this thread
+    static CANCELS: RefCell<Vec<Cancel>> = const { RefCell::new(Vec::new()) };
+}
+
+// whether the search running on this thread has been called off
+pub fn cancelled() -> bool {
+    CANCELS.with(|cancels| cancels.borrow().iter().any(Cancel::is_cancelled))
+}
+
+fn with_cancels<T>(cancels: Vec<Cancel>, f: impl FnOnce() -> T) -> T {
+    CANCELS.with(|current| *current.borrow_mut() = cancels);
+    let result = f();
+    CANCELS.with(|current| current.borrow_mut().clear());
+    result
+}
+
+// what the tasks say as they go. index is the variant's place in the portfolio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    // None if it didn't find one, or was cancelled first
+    Finished {
+        index: usize,
+        solution: Option<Solution>,
+    },
+    // it panicked, with this message
+    Failed {
+        index: usize,
+        why: String,
+    },
+}
+
+// what the watcher can call off: everything at once, or one variant at a time
+#[derive(Debug, Clone)]
+pub struct Cancels {
+    pub all: Cancel,
+    pub variants: Vec<Cancel>,
+}
+
+// how each variant got on, in portfolio order: its solution (if any), or why it failed
+pub type Outcome = Result<Option<Solution>, String>;
+
+// runs the race, calling on_event with each event as it comes in. 0 threads is one per core
+pub fn race(
+    board: &Board,
+    portfolio: &[Variant],
+    rules: &Rules,
+    goal: Goal,
+    num_threads: usize,
+    on_event: &mut dyn FnMut(&Event, &Cancels),
+) -> Vec<(Variant, Outcome)> {
+    let cancels = Cancels {
+        all: Cancel::new(),
+        variants: portfolio.iter().map(|_| Cancel::new()).collect(),
+    };
+    let run = |index: usize| {
+        let variant = portfolio[index];
+        let variant_cancels = vec![cancels.all.clone(), cancels.variants[index].clone()];
+        let what = format!("variant {}", variant.serialize());
+        let outcome = with_cancels(variant_cancels, || {
+            bundle::contain(&what, || solve_variant(board, variant, rules, goal))
+        });
+        match outcome {
+            Ok(solution) => Event::Finished { index, solution },
+            Err(why) => {
+                warn(Warning::VariantFailed {
+                    variant: variant.serialize(),
+                    fingerprint: board.fingerprint_hex(),
+                    why: why.clone(),
+                });
+                Event::Failed { index, why }
+            }
+        }
+    };
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; portfolio.len()];
+    let mut record = |event: Event| {
+        on_event(&event, &cancels);
+        match event {
+            Event::Finished { index, solution } => outcomes[index] = Some(Ok(solution)),
+            Event::Failed { index, why } => outcomes[index] = Some(Err(why)),
+        }
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        let num_threads = match num_threads {
+            0 => thread::available_parallelism().map_or(1, NonZero::get),
+            n => n,
+        };
+        // num_threads workers, each taking the next variant that hasn't been started until there
+        // are none left
+        let next = AtomicUsize::new(0);
+        let (events, finished) = mpsc::channel();
+        thread::scope(|scope| {
+            for worker in 0..num_threads.min(portfolio.len()) {
+                let (next, events, run) = (&next, events.clone(), &run);
+                thread::Builder::new()
+                    .name(format!("solsolver-{}", worker))
+                    .spawn_scoped(scope, move || loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= portfolio.len() {
+                            return;
+                        }
+                        // a task that panics outside its search only takes itself down, not the
+                        // worker and the variants it'd have gone on to
+                        let event = panic::catch_unwind(AssertUnwindSafe(|| run(index)))
+                            .unwrap_or_else(|payload| Event::Failed {
+                                index,
+                                why: bundle::panic_message(payload.as_ref()),
+                            });
+                        let _ = events.send(event);
+                    })
+                    .unwrap();
+            }
+            drop(events);
+            for event in finished {
+                record(event);
+            }
+        });
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let _ = num_threads;
+        for index in 0..portfolio.len() {
+            record(run(index));
+        }
+    }
+
+    portfolio
+        .iter()
+        .zip(outcomes)
+        .map(|(variant, outcome)| (*variant, outcome.unwrap()))
+        .collect()
+}
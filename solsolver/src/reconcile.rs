@@ -0,0 +1,95 @@
+use crate::board::{Board, Foundation};
+use crate::card::{Card, CardId, Suit};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+// two reads of the same screen should give the same board. when they don't, this is where
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disagreement {
+    // None where a pile is shorter in one read than the other
+    Card {
+        pile: usize,
+        depth: usize,
+        a: Option<Card>,
+        b: Option<Card>,
+    },
+    Block {
+        a: Option<Card>,
+        b: Option<Card>,
+    },
+    // the number of cards on the foundation
+    Foundation {
+        foundation: Foundation,
+        a: usize,
+        b: usize,
+    },
+}
+
+fn show(card: &Option<Card>) -> String {
+    card.map_or("nothing".into(), |card| card.serialize())
+}
+
+impl Display for Disagreement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Disagreement::Card { pile, depth, a, b } => write!(
+                f,
+                "pile {} depth {}: {} vs {}",
+                pile,
+                depth,
+                show(a),
+                show(b)
+            ),
+            Disagreement::Block { a, b } => write!(f, "block: {} vs {}", show(a), show(b)),
+            Disagreement::Foundation { foundation, a, b } => {
+                write!(f, "{}: {} cards vs {}", foundation.serialize(), a, b)
+            }
+        }
+    }
+}
+
+// the board both reads agree on, or everywhere they don't
+pub fn reconcile(a: &Board, b: &Board) -> Result<Board, Vec<Disagreement>> {
+    let mut disagreements = vec![];
+    for (pile, (a_pile, b_pile)) in a.playing_area.iter().zip(&b.playing_area).enumerate() {
+        for depth in 0..a_pile.len().max(b_pile.len()) {
+            let a = a_pile.get(depth).copied().map(CardId::card);
+            let b = b_pile.get(depth).copied().map(CardId::card);
+            if a != b {
+                disagreements.push(Disagreement::Card { pile, depth, a, b });
+            }
+        }
+    }
+    if a.minor_collection_blocked != b.minor_collection_blocked {
+        disagreements.push(Disagreement::Block {
+            a: a.minor_collection_blocked.map(CardId::card),
+            b: b.minor_collection_blocked.map(CardId::card),
+        });
+    }
+    let foundations = |board: &Board| {
+        [
+            (Foundation::MajorLower, board.major_lower_stack.len()),
+            (Foundation::MajorHigher, board.major_higher_stack.len()),
+        ]
+        .into_iter()
+        .chain(Suit::ALL.iter().map(|&suit| {
+            (
+                Foundation::Minor(suit),
+                board.minor_collection_piles[suit as usize].len(),
+            )
+        }))
+        .collect::<Vec<_>>()
+    };
+    for ((foundation, a), (_, b)) in foundations(a).into_iter().zip(foundations(b)) {
+        if a != b {
+            disagreements.push(Disagreement::Foundation { foundation, a, b });
+        }
+    }
+    if disagreements.is_empty() {
+        Ok(a.clone())
+    } else {
+        Err(disagreements)
+    }
+}
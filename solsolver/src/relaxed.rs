@@ -0,0 +1,158 @@
+use crate::board::{Board, NUM_PLAYING_STACKS};
+use crate::card::{Card, CardId, NUM_SUITS};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+// a lower bound on the number of moves left, from solving a relaxed version of the game:
+// - the majors are gone
+// - instead of the one block slot, there's an unlimited free area that any top card can be moved
+//   to, and that doesn't stop anything being sucked into the minor piles
+//
+// every real move is either free in the relaxed game (moving a major, taking a card off the block)
+// or has a relaxed counterpart that's at least as good (moving a minor anywhere -> moving it to the
+// free area), so the relaxed solution can never be longer than the real one. with Rules::no_block
+// there are fewer real moves, so the bound still holds, it's just looser
+
+const KING: u8 = 13;
+
+// how many relaxed positions we're willing to look at per bound. we search breadth first, so if we
+// run out we still know the answer is at least as deep as we got
+const NODE_BUDGET: usize = 2_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RelaxedBoard {
+    playing_area: [Vec<Card>; NUM_PLAYING_STACKS],
+    // the value on top of each minor collection pile
+    foundations: [u8; NUM_SUITS],
+    // one bit per minor card that's sitting in the free area
+    free: u64,
+}
+
+impl RelaxedBoard {
+    fn from_board(board: &Board) -> Self {
+        let mut foundations = [0; NUM_SUITS];
+        for (foundation, pile) in foundations.iter_mut().zip(&board.minor_collection_piles) {
+            if let Some(Card::Minor { value, .. }) = pile.last().map(|card| card.card()) {
+                *foundation = value.0;
+            }
+        }
+        let mut relaxed = Self {
+            playing_area: board.playing_area.clone().map(|stack| {
+                stack
+                    .into_iter()
+                    .map(CardId::card)
+                    .filter(|card| matches!(card, Card::Minor { .. }))
+                    .collect()
+            }),
+            foundations,
+            free: 0,
+        };
+        if let Some(card @ Card::Minor { .. }) = board.minor_collection_blocked.map(CardId::card) {
+            relaxed.free |= free_bit(card);
+        }
+        relaxed.suck();
+        relaxed
+    }
+
+    fn is_done(&self) -> bool {
+        self.foundations.iter().all(|&value| value == KING)
+    }
+
+    fn suck(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for suit in 0..NUM_SUITS {
+                let next_value = self.foundations[suit] + 1;
+                if next_value > KING {
+                    continue;
+                }
+                let bit = 1 << (suit * KING as usize + next_value as usize - 1);
+                if self.free & bit != 0 {
+                    self.free &= !bit;
+                    self.foundations[suit] = next_value;
+                    changed = true;
+                    continue;
+                }
+                for stack in self.playing_area.iter_mut() {
+                    if stack.last().map(|&card| free_bit(card)) == Some(bit) {
+                        stack.pop();
+                        self.foundations[suit] = next_value;
+                        changed = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_boards(&self) -> impl Iterator<Item = Self> + '_ {
+        self.playing_area
+            .iter()
+            .enumerate()
+            .filter(|(_, stack)| !stack.is_empty())
+            .map(|(index, _)| {
+                let mut next = self.clone();
+                let card = next.playing_area[index].pop().unwrap();
+                next.free |= free_bit(card);
+                next.suck();
+                next
+            })
+    }
+}
+
+fn free_bit(card: Card) -> u64 {
+    match card {
+        Card::Minor { suit, value } => 1 << (suit as usize * KING as usize + value.0 as usize - 1),
+        Card::Major(_) => 0,
+    }
+}
+
+// cache of relaxed solution lengths, keyed by the relaxed position, so all the real positions that
+// only differ in where the majors are (or in last_n_moves) share one relaxed solve
+#[derive(Default)]
+pub struct RelaxedBounds {
+    cache: RefCell<HashMap<RelaxedBoard, usize>>,
+}
+
+impl RelaxedBounds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lower_bound(&self, board: &Board) -> usize {
+        let relaxed = RelaxedBoard::from_board(board);
+        if let Some(&bound) = self.cache.borrow().get(&relaxed) {
+            return bound;
+        }
+        let bound = solve_relaxed(&relaxed);
+        self.cache.borrow_mut().insert(relaxed, bound);
+        bound
+    }
+}
+
+fn solve_relaxed(start: &RelaxedBoard) -> usize {
+    let mut seen = HashSet::from([start.clone()]);
+    let mut layer = vec![start.clone()];
+    let mut depth = 0;
+    while !layer.is_empty() {
+        if layer.iter().any(RelaxedBoard::is_done) {
+            return depth;
+        }
+        if seen.len() > NODE_BUDGET {
+            return depth;
+        }
+        let mut next_layer = vec![];
+        for board in &layer {
+            for next in board.next_boards() {
+                if seen.insert(next.clone()) {
+                    next_layer.push(next);
+                }
+            }
+        }
+        layer = next_layer;
+        depth += 1;
+    }
+    // can't happen, every card can always be freed
+    depth
+}
@@ -0,0 +1,351 @@
+use crate::board::{Board, NUM_PLAYING_STACKS};
+use crate::card::{Card, MajorValue, MinorValue, Suit, NUM_SUITS};
+use crate::rules::Rules;
+use crate::search::{self, Heuristic};
+use std::collections::{HashMap, VecDeque};
+
+// every endgame with a few cards left, and which of them can't be won. for finding the traps
+// (mostly ones involving the block) worth teaching the search to spot early, and for writing up
+// how to play the end of a game
+//
+// an endgame here is just which cards are left and where. a lot of endgames are the same as far
+// as winning goes, so we only look at one of each:
+// - the piles are all alike, so their order doesn't matter
+// - only the top few minors of a suit can be left (the rest have gone home), so those are fixed
+// - the majors left are always a run between the two major foundations, and where the run starts
+//   doesn't matter, only how long it is. we always put it in the middle
+//
+// we find every endgame with the given number of cards left, then every endgame you can get to
+// from those, and work backwards from the won ones (the retrograde bit): a board's won in n moves
+// if one of its moves leads to a board won in n - 1. whatever's left over is lost
+
+const NUM_MAJORS: usize = MajorValue::last().0 as usize + 1;
+const KING: u8 = 13;
+// the ace starts on its foundation
+const NUM_MINORS_PER_SUIT: usize = KING as usize - 1;
+
+#[derive(Debug, Clone)]
+pub struct Lost {
+    pub board: Board,
+}
+
+impl Lost {
+    // on one line: the piles that aren't empty, bottom card first, then where the block and the
+    // foundations are (see Board::serialize)
+    pub fn serialize(&self) -> String {
+        let text = self.board.serialize();
+        let (piles, positions): (Vec<&str>, Vec<&str>) =
+            text.lines().partition(|line| !line.contains(':'));
+        let piles: Vec<&str> = piles.into_iter().filter(|pile| !pile.is_empty()).collect();
+        let mut line = piles.join(" | ");
+        for position in positions {
+            line.push(' ');
+            line.push_str(position);
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub num_cards: usize,
+    // different endgames with num_cards left
+    pub num_positions: usize,
+    // how many of those are lost
+    pub lost: Vec<Lost>,
+    // how many of the lost ones have a card on the block
+    pub num_lost_blocked: usize,
+    // the most moves it takes to win one that can be
+    pub longest_win: usize,
+}
+
+impl Analysis {
+    // a summary line, then one line per lost endgame
+    pub fn serialize(&self) -> String {
+        let mut s = format!(
+            "cards={} positions={} lost={} lost_blocked={} longest_win={}\n",
+            self.num_cards,
+            self.num_positions,
+            self.lost.len(),
+            self.num_lost_blocked,
+            self.longest_win
+        );
+        for lost in &self.lost {
+            s.push_str(&lost.serialize());
+            s.push('\n');
+        }
+        s
+    }
+}
+
+// the text (see Board::parse) of the one board we look at for all the boards like this one. doubles
+// as its key
+fn canonical(board: &Board) -> String {
+    let num_majors_home = board.major_lower_stack.len() + board.major_higher_stack.len();
+    let num_majors_left = NUM_MAJORS - num_majors_home;
+    let first_left = (NUM_MAJORS - num_majors_left) / 2;
+    // the lower foundation has 0 up to one under the first major left
+    let shift = first_left as i32 - board.major_lower_stack.len() as i32;
+    let card = |card: Card| match card {
+        Card::Major(value) => Card::Major(MajorValue((value.0 as i32 + shift) as u8)).serialize(),
+        minor => minor.serialize(),
+    };
+    let mut piles: Vec<String> = board
+        .playing_area
+        .iter()
+        .map(|pile| {
+            pile.iter()
+                .map(|&c| card(c.card()))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect();
+    piles.sort();
+    let mut lines = piles;
+    if let Some(blocked) = board.minor_collection_blocked {
+        lines.push(format!("BLOCK:{}", card(blocked.card())));
+    }
+    if first_left > 0 {
+        lines.push(format!(
+            "MAJ_LO:{}",
+            Card::Major(MajorValue(first_left as u8 - 1)).serialize()
+        ));
+    }
+    if first_left + num_majors_left < NUM_MAJORS {
+        lines.push(format!(
+            "MAJ_HI:{}",
+            Card::Major(MajorValue((first_left + num_majors_left) as u8)).serialize()
+        ));
+    }
+    for pile in &board.minor_collection_piles {
+        if pile.len() > 1 {
+            let top = pile.last().unwrap().card();
+            lines.push(format!("{}:{}", suit_of(top).serialize(), top.serialize()));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+fn suit_of(card: Card) -> Suit {
+    match card {
+        Card::Minor { suit, .. } => suit,
+        Card::Major(_) => unreachable!("majors don't go on the minor foundations"),
+    }
+}
+
+// every way to split this many cards between the majors and the four suits
+fn compositions(num_cards: usize) -> Vec<[usize; NUM_SUITS + 1]> {
+    let mut compositions = vec![];
+    let mut counts = [0; NUM_SUITS + 1];
+    fn go(
+        family: usize,
+        left: usize,
+        counts: &mut [usize; NUM_SUITS + 1],
+        out: &mut Vec<[usize; NUM_SUITS + 1]>,
+    ) {
+        let most = family_size(family);
+        if family == NUM_SUITS {
+            if left <= most {
+                counts[family] = left;
+                out.push(*counts);
+            }
+            return;
+        }
+        for count in 0..=left.min(most) {
+            counts[family] = count;
+            go(family + 1, left - count, counts, out);
+        }
+    }
+    go(0, num_cards, &mut counts, &mut compositions);
+    compositions
+}
+
+// every way to stack the cards into at most max_piles piles, where the order of the piles doesn't
+// matter but the order in each one does. each card goes anywhere in a pile we've started, or
+// starts a new one
+fn arrangements(cards: &[Card], max_piles: usize) -> Vec<Vec<Vec<Card>>> {
+    let Some((&card, rest)) = cards.split_last() else {
+        return vec![vec![]];
+    };
+    let mut out = vec![];
+    for piles in arrangements(rest, max_piles) {
+        for pile in 0..piles.len() {
+            for position in 0..=piles[pile].len() {
+                let mut piles = piles.clone();
+                piles[pile].insert(position, card);
+                out.push(piles);
+            }
+        }
+        if piles.len() < max_piles {
+            let mut piles = piles;
+            piles.push(vec![card]);
+            out.push(piles);
+        }
+    }
+    out
+}
+
+// how many cards of a family (the majors, then each suit) can still be out
+pub(crate) fn family_size(family: usize) -> usize {
+    if family == 0 {
+        NUM_MAJORS
+    } else {
+        NUM_MINORS_PER_SUIT
+    }
+}
+
+// the cards left when counts[0] majors and counts[1..] of each suit are still out, along with the
+// foundation lines (see Board::parse) for everything that's gone home
+pub(crate) fn endgame_cards(counts: &[usize; NUM_SUITS + 1]) -> (Vec<Card>, Vec<String>) {
+    let num_majors = counts[0];
+    let first_left = (NUM_MAJORS - num_majors) / 2;
+    let mut cards: Vec<Card> = (first_left..first_left + num_majors)
+        .map(|value| Card::Major(MajorValue(value as u8)))
+        .collect();
+    let mut foundations = vec![];
+    for (suit, &count) in Suit::ALL.iter().zip(&counts[1..]) {
+        let home = KING - count as u8;
+        cards.extend((home + 1..=KING).map(|value| Card::Minor {
+            suit: *suit,
+            value: MinorValue(value),
+        }));
+        if home > 1 {
+            let top = Card::Minor {
+                suit: *suit,
+                value: MinorValue(home),
+            };
+            foundations.push(format!("{}:{}", suit.serialize(), top.serialize()));
+        }
+    }
+    if first_left > 0 {
+        let top = Card::Major(MajorValue(first_left as u8 - 1));
+        foundations.push(format!("MAJ_LO:{}", top.serialize()));
+    }
+    if first_left + num_majors < NUM_MAJORS {
+        let top = Card::Major(MajorValue((first_left + num_majors) as u8));
+        foundations.push(format!("MAJ_HI:{}", top.serialize()));
+    }
+    (cards, foundations)
+}
+
+// the cards from endgame_cards laid out on a board, before anything's been sucked
+pub(crate) fn endgame_board(
+    piles: &[Vec<Card>],
+    blocked: Option<Card>,
+    foundations: &[String],
+) -> Board {
+    let mut lines: Vec<String> = piles
+        .iter()
+        .map(|pile| {
+            pile.iter()
+                .map(Card::serialize)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect();
+    lines.resize(NUM_PLAYING_STACKS, String::new());
+    if let Some(blocked) = blocked {
+        lines.push(format!("BLOCK:{}", blocked.serialize()));
+    }
+    lines.extend(foundations.iter().cloned());
+    Board::parse(&(lines.join("\n") + "\n"))
+}
+
+// the endgames with num_cards left, before they're made canonical
+fn endgames(num_cards: usize, rules: &Rules) -> Vec<Board> {
+    let mut boards = vec![];
+    for counts in compositions(num_cards) {
+        let (cards, foundations) = endgame_cards(&counts);
+        let blocks = [None].into_iter().chain((0..cards.len()).map(Some));
+        for block in blocks {
+            let mut on_table = cards.clone();
+            let blocked = block.map(|i| on_table.remove(i));
+            for piles in arrangements(&on_table, NUM_PLAYING_STACKS) {
+                let mut board = endgame_board(&piles, blocked, &foundations);
+                // a card that'd go home by itself can't be left lying around
+                board.auto_sucks(rules);
+                boards.push(board);
+            }
+        }
+    }
+    boards
+}
+
+pub fn analyze(num_cards: usize, rules: &Rules) -> Analysis {
+    // every endgame we've come across, by its canonical text
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut boards: Vec<Board> = vec![];
+    let mut intern = |board: &Board, boards: &mut Vec<Board>| -> (usize, bool) {
+        let key = canonical(board);
+        if let Some(&i) = index.get(&key) {
+            return (i, false);
+        }
+        index.insert(key.clone(), boards.len());
+        boards.push(Board::parse(&key));
+        (boards.len() - 1, true)
+    };
+
+    let mut class = vec![];
+    let mut to_expand = VecDeque::new();
+    for board in endgames(num_cards, rules) {
+        let (i, new) = intern(&board, &mut boards);
+        if new {
+            to_expand.push_back(i);
+            if boards[i].num_cards_remaining() == num_cards {
+                class.push(i);
+            }
+        }
+    }
+
+    // which boards each board can be reached from
+    let mut predecessors: Vec<Vec<usize>> = vec![];
+    let mut won = VecDeque::new();
+    while let Some(i) = to_expand.pop_front() {
+        if search::wrap_up(&boards[i], Heuristic::RelaxedLowerBound, rules).is_some() {
+            won.push_back(i);
+            continue;
+        }
+        for (next_board, _) in boards[i].next_boards(rules) {
+            let (next, new) = intern(&next_board, &mut boards);
+            predecessors.resize(boards.len(), vec![]);
+            predecessors[next].push(i);
+            if new {
+                to_expand.push_back(next);
+            }
+        }
+    }
+    predecessors.resize(boards.len(), vec![]);
+    let mut won_in: Vec<Option<usize>> = vec![None; boards.len()];
+    for &i in &won {
+        won_in[i] = Some(0);
+    }
+
+    // back from the won boards, a move at a time, so each board gets the fewest moves it's won in
+    while let Some(i) = won.pop_front() {
+        let moves = won_in[i].unwrap() + 1;
+        for &before in &predecessors[i] {
+            if won_in[before].is_none() {
+                won_in[before] = Some(moves);
+                won.push_back(before);
+            }
+        }
+    }
+
+    let lost: Vec<Lost> = class
+        .iter()
+        .filter(|&&i| won_in[i].is_none())
+        .map(|&i| Lost {
+            board: boards[i].clone(),
+        })
+        .collect();
+    Analysis {
+        num_cards,
+        num_positions: class.len(),
+        num_lost_blocked: lost
+            .iter()
+            .filter(|lost| lost.board.minor_collection_blocked.is_some())
+            .count(),
+        lost,
+        longest_win: class.iter().filter_map(|&i| won_in[i]).max().unwrap_or(0),
+    }
+}
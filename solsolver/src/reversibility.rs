@@ -0,0 +1,52 @@
+use crate::board::{Board, Move, Solution};
+use crate::rules::Rules;
+use alloc::vec::Vec;
+
+// how easily a move could be taken back, so a driver can take extra care (look at the screen again,
+// say) before the ones it can't undo
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reversibility {
+    // the card can go straight back where it came from, leaving the board as it was
+    Reversible,
+    // nothing went home, but getting back to where we were takes more than the one move back
+    HardToReverse,
+    // cards went home, and they never come back
+    Irreversible,
+}
+
+impl Reversibility {
+    pub fn serialize(&self) -> &'static str {
+        match self {
+            Reversibility::Reversible => "reversible",
+            Reversibility::HardToReverse => "hard_to_reverse",
+            Reversibility::Irreversible => "irreversible",
+        }
+    }
+}
+
+// the move's reversibility, played on before (where it leaves after)
+pub fn reversibility(before: &Board, after: &Board, moov: &Move, rules: &Rules) -> Reversibility {
+    if moov.num_cards_home() > 0 {
+        return Reversibility::Irreversible;
+    }
+    match after.play(moov.to, moov.from, rules) {
+        Some((back, _)) if back == *before => Reversibility::Reversible,
+        _ => Reversibility::HardToReverse,
+    }
+}
+
+// one per move of the solution
+pub fn annotate(board: &Board, solution: &Solution, rules: &Rules) -> Vec<Reversibility> {
+    let mut board = board.clone();
+    solution
+        .moves
+        .iter()
+        .map(|moov| {
+            let (after, _) = board.play(moov.from, moov.to, rules).unwrap();
+            let reversibility = reversibility(&board, &after, moov, rules);
+            board = after;
+            reversibility
+        })
+        .collect()
+}
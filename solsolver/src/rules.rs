@@ -0,0 +1,28 @@
+// bumped whenever the moves the generator allows change for the same switches (a rule we had wrong,
+// say), so a solution from before can be told apart, see provenance.rs
+pub const RULES_VERSION: u32 = 1;
+
+// switches for the variations on the game's rules that the move generator knows about. the
+// defaults are the rules of the real game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rules {
+    // let a card be moved straight onto a foundation, instead of only ever getting there by being
+    // sucked. the only time this makes a difference is when the block is taken: the card on the
+    // block can then go home by hand, freeing the minor piles without needing a space in the
+    // playing area
+    pub explicit_foundation_moves: bool,
+    // the game clears the board by itself once there's nothing left to decide (see
+    // Board::is_trivially_won), so the solution can stop there instead of spelling out the wrap-up
+    pub game_auto_completes: bool,
+    // never put a card on the block, for play styles that don't allow it (and to see how often it's
+    // really needed). a card that's already there can still be moved off
+    pub no_block: bool,
+    // for versions of the game (or mods) that let you turn down auto-collection: nothing's sucked
+    // after a move, instead sending a card that would have been sucked is a move of its own, which
+    // the search can choose to make or not. that's a lot more moves to look at
+    pub optional_sucks: bool,
+    // the same, but only for the majors: the minors are still sucked after every move, a major
+    // that could go home stays put until it's sent. for the games that let you hold a major back,
+    // in case sending it early ever traps a minor the rest of the solution needs
+    pub optional_major_sucks: bool,
+}
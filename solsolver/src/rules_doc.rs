@@ -0,0 +1,191 @@
+use crate::board::NUM_PLAYING_STACKS;
+use crate::card::{Card, CardSet, MajorValue, MinorValue, Suit};
+use crate::rules::Rules;
+
+// the rules the move generator plays by, written out for people, for when the solver and the game
+// don't agree about what's allowed. as much of it as we can is worked out from the same code the
+// move generator uses (the deck, which cards go on which), so it can't drift from what the solver
+// really does. the rest follows the switches in Rules
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Text,
+}
+
+struct Doc {
+    format: Format,
+    out: String,
+}
+
+impl Doc {
+    fn heading(&mut self, heading: &str) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        match self.format {
+            Format::Markdown => self.out.push_str(&format!("## {}\n\n", heading)),
+            Format::Text => {
+                self.out
+                    .push_str(&format!("{}\n{}\n", heading, "-".repeat(heading.len())));
+            }
+        }
+    }
+
+    fn item(&mut self, item: &str) {
+        self.out.push_str(&format!("- {}\n", item));
+    }
+
+    fn code(&self, s: &str) -> String {
+        match self.format {
+            Format::Markdown => format!("`{}`", s),
+            Format::Text => s.to_string(),
+        }
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+// the cards from a few that a card can go on, by Card::is_next_or_prev
+fn goes_on(card: Card, candidates: &[Card]) -> Vec<String> {
+    candidates
+        .iter()
+        .filter(|candidate| candidate.is_next_or_prev(card))
+        .map(Card::serialize)
+        .collect()
+}
+
+pub fn document(rules: &Rules, format: Format) -> String {
+    let mut doc = Doc {
+        format,
+        out: String::new(),
+    };
+    let majors = CardSet::DECK.iter().filter(|card| card.is_major()).count();
+
+    doc.heading("Rules in effect");
+    for (name, on) in [
+        ("explicit_foundation_moves", rules.explicit_foundation_moves),
+        ("game_auto_completes", rules.game_auto_completes),
+        ("no_block", rules.no_block),
+        ("optional_sucks", rules.optional_sucks),
+        ("optional_major_sucks", rules.optional_major_sucks),
+    ] {
+        let line = format!("{}: {}", doc.code(name), on_off(on));
+        doc.item(&line);
+    }
+
+    doc.heading("Deck");
+    let line = format!(
+        "{} cards: {} majors, {} up to {}, and {} of each suit, ace to king",
+        CardSet::DECK.len(),
+        majors,
+        doc.code(&Card::Major(MajorValue::first()).serialize()),
+        doc.code(&Card::Major(MajorValue::last()).serialize()),
+        (CardSet::DECK.len() - majors) / Suit::ALL.len(),
+    );
+    doc.item(&line);
+    let suits: Vec<String> = Suit::ALL
+        .iter()
+        .map(|suit| doc.code(suit.serialize()))
+        .collect();
+    doc.item(&format!("the suits are {}", suits.join(", ")));
+    doc.item("the aces start the game on their foundations, everything else is dealt");
+    doc.item(&format!("{} piles", NUM_PLAYING_STACKS));
+
+    doc.heading("Placements");
+    doc.item("only the top card of a pile moves, one card at a time");
+    doc.item("a card goes on a card one higher or one lower: a major on a major, a minor on a minor of its own suit");
+    let minor = |suit, value| Card::Minor {
+        suit,
+        value: MinorValue(value),
+    };
+    let examples = [
+        (
+            minor(Suit::Sword, 7),
+            vec![
+                minor(Suit::Sword, 6),
+                minor(Suit::Sword, 8),
+                minor(Suit::Sword, 9),
+                minor(Suit::Wand, 6),
+                Card::Major(MajorValue(7)),
+            ],
+        ),
+        (
+            Card::Major(MajorValue(10)),
+            vec![
+                Card::Major(MajorValue(9)),
+                Card::Major(MajorValue(11)),
+                Card::Major(MajorValue(12)),
+                minor(Suit::Cup, 10),
+            ],
+        ),
+    ];
+    for (card, candidates) in examples {
+        let takers: Vec<String> = goes_on(card, &candidates)
+            .iter()
+            .map(|taker| doc.code(taker))
+            .collect();
+        let others: Vec<String> = candidates
+            .iter()
+            .map(Card::serialize)
+            .filter(|candidate| !goes_on(card, &candidates).contains(candidate))
+            .map(|candidate| doc.code(&candidate))
+            .collect();
+        let line = format!(
+            "e.g. {} goes on {}, not {}",
+            doc.code(&card.serialize()),
+            takers.join(" or "),
+            others.join(", ")
+        );
+        doc.item(&line);
+    }
+    doc.item("any card goes in an empty pile");
+    if rules.no_block {
+        doc.item("nothing can be put on the block. a card that's already there can still go back on a pile");
+    } else {
+        doc.item("any one card can go on the block, which covers the minor foundations: nothing goes onto them until it's moved off again (back onto a pile, or home if it's a major)");
+    }
+    if rules.explicit_foundation_moves {
+        doc.item("a card can be moved onto its foundation by hand, including the card on the block onto a minor foundation");
+    } else {
+        doc.item("cards only get onto the foundations by being sucked, never by hand");
+    }
+
+    doc.heading("Sucks");
+    if rules.optional_sucks {
+        doc.item("nothing's sucked by itself. sending a card that could be sucked is a move of its own, and one you don't have to make");
+    } else if rules.optional_major_sucks {
+        doc.item("after every move, every top minor that can go home does, over and over until none can. a major that could go home is only sent by a move of its own, and one you don't have to make");
+    } else {
+        doc.item(
+            "after every move, every top card that can go home does, over and over until none can",
+        );
+    }
+    let line = format!(
+        "a minor goes onto its suit's foundation when it's the next card up, as long as the block's empty. the foundations go {} to {}",
+        doc.code(&minor(Suit::Sword, 2).serialize()),
+        doc.code(&minor(Suit::Sword, 13).serialize()),
+    );
+    doc.item(&line);
+    let line = format!(
+        "the majors have two foundations, one going up from {} and one going down from {}, and a major goes on either when it's next. that includes the card on the block",
+        doc.code(&Card::Major(MajorValue::first()).serialize()),
+        doc.code(&Card::Major(MajorValue::last()).serialize()),
+    );
+    doc.item(&line);
+
+    doc.heading("Winning");
+    doc.item("the game's won when every pile is empty");
+    if rules.game_auto_completes {
+        doc.item("the game plays the rest out by itself once every pile is a single run and there's a way to clear them where every move sucks something, so the solution stops there");
+    } else {
+        doc.item("every move is spelled out to the end, even when the rest is forced");
+    }
+    doc.out
+}
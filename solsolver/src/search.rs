@@ -0,0 +1,1271 @@
+use crate::board::{Board, Move, Solution, SUCK_NANOS};
+use crate::card::MajorValue;
+use crate::certificate::Certificate;
+use crate::closed::{self, Closed};
+use crate::decompose::{independent_groups, solve_decomposed};
+use crate::layout;
+use crate::memo::{self, Known};
+use crate::nogoods;
+use crate::ordering;
+use crate::race::{self, Cancels, Event};
+use crate::relaxed::RelaxedBounds;
+use crate::rules::Rules;
+use crate::tablebase;
+use crate::unknown;
+use crate::warnings::{warn, Warning};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const NUM_MAJOR_CARDS: usize = MajorValue::last().0 as usize + 1;
+
+// HAX: OLD is used to indicate that we don't limit the num_prev_moves, and instead use what we were using before: no limit to prune the search tree. SOMETIMES that was producing better results
+pub const OLD: usize = 0;
+pub const NUM_PREV_MOVES_TO_CONSIDERS: [usize; 4] = [5, 10, 15, OLD];
+
+// the last_n_moves pruning: we give up on a line once the last num_prev_moves moves played to get
+// here have between them got no more than this many cards home
+pub(crate) const MINIMUM_AMT_OF_PROGRESS: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    // search straight for an empty playing area
+    Full,
+    // first search for a plan that completes both major foundations, then plan the minors from
+    // wherever that left us. the combined plan is usually longer than what Full finds, but each
+    // half is a much smaller search, so this sometimes finishes when Full doesn't
+    TwoPhase,
+    // split the piles into groups that never interact and clear them one after another, see
+    // decompose.rs. the same as Full when everything's connected, which is most of the game
+    Decompose,
+    // Full over and over, breaking ties between equally good boards at random and starting again
+    // (with a new seed) once a run's used up its share of a budget on the Luby sequence. how long
+    // the same search takes can depend wildly on which of a tie it happens to pick, and this gets
+    // the deals where it picked badly out of the long tail
+    Restarts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    // every move is free and we head for whatever has the fewest cards left. finds a solution
+    // quickly, but not the shortest one
+    CardsRemaining,
+    // every move costs 1, and we use the length of a relaxed solution (see relaxed.rs) as an
+    // admissible estimate, so the solution found is as short as the pruning allows
+    RelaxedLowerBound,
+}
+
+// what we're searching for. usually that's a cleared board, but sometimes a position only needs
+// unsticking, or we're after some progress for the game's achievements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    ClearBoard,
+    // get this many more cards onto the foundations
+    ClearCards(usize),
+    // get both major foundations done, never mind the minors
+    MajorsOnly,
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    // clear, cards:N or majors-only
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("cards", n)) => n
+                .parse()
+                .map(Goal::ClearCards)
+                .map_err(|e| format!("bad number of cards {:?}: {}", n, e)),
+            None if s == "clear" => Ok(Goal::ClearBoard),
+            None if s == "majors-only" => Ok(Goal::MajorsOnly),
+            _ => Err(format!(
+                "unknown goal {:?}, expected clear, cards:N or majors-only",
+                s
+            )),
+        }
+    }
+}
+
+// one member of the portfolio we race against each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variant {
+    pub strategy: Strategy,
+    pub num_prev_moves: usize,
+    pub heuristic: Heuristic,
+}
+
+impl Variant {
+    // STRATEGY/NUM_PREV_MOVES/HEURISTIC, e.g. Full/5/CardsRemaining
+    pub fn serialize(&self) -> String {
+        format!(
+            "{:?}/{}/{:?}",
+            self.strategy, self.num_prev_moves, self.heuristic
+        )
+    }
+}
+
+pub fn default_portfolio(
+    two_phase: bool,
+    decompose: bool,
+    restarts: bool,
+    heuristic: Heuristic,
+) -> Vec<Variant> {
+    let mut strategies = vec![if decompose {
+        Strategy::Decompose
+    } else {
+        Strategy::Full
+    }];
+    if two_phase {
+        strategies.push(Strategy::TwoPhase);
+    }
+    if restarts {
+        strategies.push(Strategy::Restarts);
+    }
+    strategies
+        .into_iter()
+        .flat_map(|strategy| {
+            NUM_PREV_MOVES_TO_CONSIDERS.map(|num_prev_moves| Variant {
+                strategy,
+                num_prev_moves,
+                heuristic,
+            })
+        })
+        .collect()
+}
+
+// runs every variant (in parallel, with the parallel feature), and keeps the shortest solution any
+// of them found. see solve_all for the threads
+pub fn solve(
+    board: &Board,
+    portfolio: &[Variant],
+    rules: &Rules,
+    goal: Goal,
+    num_threads: usize,
+) -> Option<Solution> {
+    shortest(solve_all(board, portfolio, rules, goal, num_threads))
+}
+
+// the shortest of solve_all's solutions
+pub fn shortest(results: Vec<(Variant, Option<Solution>)>) -> Option<Solution> {
+    let (variant, solution) = results
+        .into_iter()
+        .filter_map(|(variant, solution)| Some((variant, solution?)))
+        .min_by_key(|(_, solution)| solution.moves.len())?;
+    if variant.num_prev_moves != OLD {
+        warn(Warning::PruningMayHaveRemovedOptimal {
+            num_prev_moves: variant.num_prev_moves,
+        });
+    }
+    Some(solution)
+}
+
+// whether a solution the variant finds is as short as they come, so nothing else in the race can
+// do better
+fn finds_shortest(variant: &Variant, goal: Goal) -> bool {
+    let move_cost = match variant.heuristic {
+        Heuristic::CardsRemaining => 0,
+        Heuristic::RelaxedLowerBound => 1,
+    };
+    goal == Goal::ClearBoard
+        && variant.strategy == Strategy::Full
+        && is_exact(variant.num_prev_moves, move_cost)
+}
+
+// what every variant made of the board, in portfolio order, racing them on this many threads (0 for
+// one per core), see race.rs. a variant that panicked didn't make anything of it, and once one's
+// found the shortest solution there is, the rest are called off and come back with nothing. the
+// Restarts ones are only there for when the rest get unlucky, and they'd go on restarting for as
+// long as there's a board left, so they're called off as soon as anything finds a solution
+pub fn solve_all(
+    board: &Board,
+    portfolio: &[Variant],
+    rules: &Rules,
+    goal: Goal,
+    num_threads: usize,
+) -> Vec<(Variant, Option<Solution>)> {
+    let mut on_event = |event: &Event, cancels: &Cancels| {
+        if let Event::Finished {
+            index,
+            solution: Some(_),
+        } = event
+        {
+            if finds_shortest(&portfolio[*index], goal) {
+                cancels.all.cancel();
+            }
+            for (variant, cancel) in portfolio.iter().zip(&cancels.variants) {
+                if variant.strategy == Strategy::Restarts {
+                    cancel.cancel();
+                }
+            }
+        }
+    };
+    race::race(board, portfolio, rules, goal, num_threads, &mut on_event)
+        .into_iter()
+        .map(|(variant, outcome)| (variant, outcome.unwrap_or(None)))
+        .collect()
+}
+
+pub fn solve_variant(
+    board: &Board,
+    variant: Variant,
+    rules: &Rules,
+    goal: Goal,
+) -> Option<Solution> {
+    let relaxed_bounds = RelaxedBounds::new();
+    let (heuristic, move_cost) = heuristic(variant.heuristic, &relaxed_bounds);
+    let heuristic = &heuristic;
+
+    let successors = |b: &Board| b.next_boards(rules);
+    let endgame_successors = |b: &Board| successors_past_endgame(b, rules);
+    let is_goal = |b: &Board| finish_from(b, variant.heuristic, rules).is_some();
+    let finish = |(mut solution, board): (Solution, Board)| {
+        solution
+            .moves
+            .extend(finish_from(&board, variant.heuristic, rules).unwrap());
+        solution
+    };
+
+    // the partial goals are always a quick greedy search, the strategies and heuristics are all
+    // about clearing the whole board
+    match goal {
+        Goal::ClearBoard => {}
+        Goal::ClearCards(num_cards) => {
+            let target = board.num_cards_remaining().saturating_sub(num_cards);
+            let (solution, _) = search(
+                board,
+                variant.num_prev_moves,
+                0,
+                &successors,
+                &|b| b.num_cards_remaining().saturating_sub(target),
+                |b| b.num_cards_remaining() <= target,
+            )?;
+            return Some(solution);
+        }
+        Goal::MajorsOnly => {
+            let (solution, _) = search(
+                board,
+                variant.num_prev_moves,
+                0,
+                &successors,
+                &num_majors_remaining,
+                |b| num_majors_remaining(b) == 0,
+            )?;
+            return Some(solution);
+        }
+    }
+
+    if memo::is_dead(board, rules) {
+        return None;
+    }
+    let full = || {
+        search(
+            board,
+            variant.num_prev_moves,
+            move_cost,
+            &endgame_successors,
+            heuristic,
+            is_goal,
+        )
+        .map(finish)
+    };
+    let solution = match variant.strategy {
+        // clearing one group at a time can paint itself into a corner a search of the whole board
+        // wouldn't, so that's what it falls back on
+        Strategy::Decompose if independent_groups(board).len() > 1 => {
+            solve_decomposed(board, variant.num_prev_moves, rules).or_else(full)
+        }
+        Strategy::Full | Strategy::Decompose => full(),
+        Strategy::Restarts => {
+            let mut run = 0;
+            loop {
+                run += 1;
+                let restart = Restart {
+                    seed: unknown::seed().wrapping_add(run),
+                    max_expansions: RESTART_EXPANSIONS * luby(run) as usize,
+                };
+                match search_with_restart(
+                    board,
+                    variant.num_prev_moves,
+                    move_cost,
+                    &endgame_successors,
+                    heuristic,
+                    is_goal,
+                    Some(&restart),
+                ) {
+                    Searched::Found(found) => break Some(finish(*found)),
+                    Searched::Exhausted | Searched::Cancelled => break None,
+                    Searched::OutOfBudget => {}
+                }
+            }
+        }
+        Strategy::TwoPhase => {
+            let (mut majors, board) = search(
+                board,
+                variant.num_prev_moves,
+                0,
+                &successors,
+                &num_majors_remaining,
+                |b| num_majors_remaining(b) == 0,
+            )?;
+            let minors = finish(search(
+                &board,
+                variant.num_prev_moves,
+                move_cost,
+                &endgame_successors,
+                heuristic,
+                is_goal,
+            )?);
+            majors.moves.extend(minors.moves);
+            Some(majors)
+        }
+    };
+    match &solution {
+        Some(solution) => {
+            memo::remember_solution(board, solution, rules);
+            if variant.strategy == Strategy::Full && is_bounded(variant.num_prev_moves, move_cost) {
+                let mut bounded = BOUNDED.lock().unwrap();
+                let length = bounded.entry(board.fingerprint()).or_insert(usize::MAX);
+                *length = solution.moves.len().min(*length);
+            }
+        }
+        // only a search that looked at everything under the board says it's dead
+        None if matches!(variant.strategy, Strategy::Full | Strategy::Restarts)
+            && proves_dead(variant.num_prev_moves, move_cost)
+            && !race::cancelled() =>
+        {
+            memo::remember_dead(board, rules);
+            PROVEN_DEAD.lock().unwrap().insert(board.fingerprint());
+        }
+        None => {}
+    }
+    solution
+}
+
+// the boards solve_variant has gone through everything from without finding a way through, by
+// fingerprint, so that a deal that can't be won can be told apart from one we gave up on (see
+// dedup.rs)
+static PROVEN_DEAD: Mutex<BTreeSet<[u8; 16]>> = Mutex::new(BTreeSet::new());
+
+pub fn proven_dead(board: &Board) -> bool {
+    PROVEN_DEAD.lock().unwrap().contains(&board.fingerprint())
+}
+
+// whether a search that comes back with nothing has gone through every board there is from where it
+// started, rather than only the ones the pruning, --upper-bound or --safe-mode forbid let it (or,
+// in solve_variant, only the first phase's). a closed set of fingerprints without the collision
+// check can skip a board it's never seen (see closed.rs), so that doesn't prove anything either
+fn proves_dead(num_prev_moves: usize, move_cost: usize) -> bool {
+    let bounded = move_cost > 0 && UPPER_BOUND.load(AtomicOrdering::Relaxed) != usize::MAX;
+    let lossy = closed::HASH_ONLY.load(AtomicOrdering::Relaxed)
+        && !closed::CHECK_COLLISIONS.load(AtomicOrdering::Relaxed);
+    num_prev_moves == OLD && !bounded && !lossy && !layout::forbidding()
+}
+
+// the estimate of the cost left for a heuristic, and what each move costs alongside it
+fn heuristic(
+    heuristic: Heuristic,
+    relaxed_bounds: &RelaxedBounds,
+) -> (impl Fn(&Board) -> usize + '_, usize) {
+    let move_cost = match heuristic {
+        Heuristic::CardsRemaining => 0,
+        Heuristic::RelaxedLowerBound => 1,
+    };
+    let estimate = move |b: &Board| match heuristic {
+        Heuristic::CardsRemaining => b.num_cards_remaining(),
+        Heuristic::RelaxedLowerBound => relaxed_bounds.lower_bound(b),
+    };
+    (estimate, move_cost)
+}
+
+// the moves the search for a whole board looks at. a board the tablebase covers but can't finish
+// is a dead end (if it could, it'd be a goal). the search never asks for the moves from a goal, so
+// anything left with none is dead, see nogoods.rs
+fn successors_past_endgame(board: &Board, rules: &Rules) -> Vec<(Board, Move)> {
+    match tablebase::covering(board, rules) {
+        Some(_) => nogoods::prune(board, vec![], rules),
+        None => nogoods::prune(board, live_boards(board, rules), rules),
+    }
+}
+
+// the moves from a board, less the ones to boards memo.rs knows are dead
+fn live_boards(board: &Board, rules: &Rules) -> Vec<(Board, Move)> {
+    let next_boards = board.next_boards(rules);
+    if !memo::enabled() {
+        return next_boards;
+    }
+    next_boards
+        .into_iter()
+        .filter(|(next, _)| !memo::is_dead(next, rules))
+        .collect()
+}
+
+// if the search can stop at this board, the moves left to play after it. when we're not after the
+// shortest solution anyway (or the moves are free because the game makes them for us), we stop as
+// soon as the rest is forced, rather than searching our way through the endgame
+pub(crate) fn wrap_up(board: &Board, heuristic: Heuristic, rules: &Rules) -> Option<Vec<Move>> {
+    if heuristic == Heuristic::CardsRemaining || rules.game_auto_completes {
+        let (rest, _) = board.auto_finish(rules)?;
+        Some(if rules.game_auto_completes {
+            vec![]
+        } else {
+            rest
+        })
+    } else {
+        board.is_done().then(Vec::new)
+    }
+}
+
+// wrap_up, unless the board's far enough into the endgame for the tablebase, which has the best
+// finish there is (or knows there isn't one)
+fn finish_from(board: &Board, heuristic: Heuristic, rules: &Rules) -> Option<Vec<Move>> {
+    match tablebase::covering(board, rules) {
+        Some(tablebase) => tablebase.finish(board),
+        None => wrap_up(board, heuristic, rules).or_else(|| remembered(board, heuristic, rules)),
+    }
+}
+
+// the rest of a solution we found from this board before, see memo.rs. not for the searches after
+// the shortest solution, it's only ever the rest of whatever we found
+fn remembered(board: &Board, heuristic: Heuristic, rules: &Rules) -> Option<Vec<Move>> {
+    if heuristic != Heuristic::CardsRemaining {
+        return None;
+    }
+    match memo::lookup(board, rules)? {
+        Known::Solved(rest) => Some(rest),
+        Known::Dead => None,
+    }
+}
+
+// the heuristic's estimate before and after each move of a solution. real deals need plenty of
+// moves that look like they're going backwards, and this shows where they are
+pub fn trajectory(
+    board: &Board,
+    solution: &Solution,
+    heuristic: Heuristic,
+    rules: &Rules,
+) -> Vec<(usize, usize)> {
+    let relaxed_bounds = RelaxedBounds::new();
+    let estimate = |b: &Board| match heuristic {
+        Heuristic::CardsRemaining => b.num_cards_remaining(),
+        Heuristic::RelaxedLowerBound => relaxed_bounds.lower_bound(b),
+    };
+    let mut board = board.clone();
+    let mut before = estimate(&board);
+    solution
+        .moves
+        .iter()
+        .map(|moov| {
+            board = board.play(moov.from, moov.to, rules).unwrap().0;
+            let after = estimate(&board);
+            let step = (before, after);
+            before = after;
+            step
+        })
+        .collect()
+}
+
+fn num_majors_remaining(board: &Board) -> usize {
+    NUM_MAJOR_CARDS - board.major_lower_stack.len() - board.major_higher_stack.len()
+}
+
+// is the line that got us to nodes[index] going nowhere? this depends on the path and not just the
+// board, which is why it lives here rather than in Board::next_boards: the same board can be
+// reached along a line that's making progress and along one that isn't. we only ever keep the
+// first (or cheapest) way we found to a board, so that's the line that counts
+//
+// this means two searches that see the same boards in a different order can prune differently, and
+// with the pruning on, running out of boards doesn't prove there's no solution
+fn is_stalled(nodes: &[Node], mut index: usize, num_prev_moves: usize) -> bool {
+    if num_prev_moves == OLD {
+        return false;
+    }
+    let mut num_moves = 0;
+    let mut num_cards_home = 0;
+    while let Some((parent, moov)) = &nodes[index].parent {
+        if num_moves == num_prev_moves {
+            break;
+        }
+        num_moves += 1;
+        num_cards_home += moov.num_cards_home();
+        index = *parent;
+    }
+    num_moves == num_prev_moves && num_cards_home <= MINIMUM_AMT_OF_PROGRESS
+}
+
+fn line_to(nodes: &[Node], mut index: usize) -> Vec<Move> {
+    let mut line = vec![];
+    while let Some((parent, moov)) = &nodes[index].parent {
+        line.push(moov.clone());
+        index = *parent;
+    }
+    line.reverse();
+    line
+}
+
+// turns on SearchProfile collection in search, for --profile-search. off, it costs next to nothing
+pub static PROFILING: AtomicBool = AtomicBool::new(false);
+
+// for --upper-bound: when moves cost something (Heuristic::RelaxedLowerBound), a board whose moves
+// so far plus the heuristic's estimate of the rest come to more than this can't be on a solution
+// this short, so it never goes in the open list. usize::MAX is no bound
+pub static UPPER_BOUND: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+fn over_upper_bound(move_cost: usize, g: usize, h: usize) -> bool {
+    move_cost > 0 && g + h > UPPER_BOUND.load(AtomicOrdering::Relaxed)
+}
+
+// for --epsilon: when moves cost something, the open list goes by g + (1 + epsilon) * h rather than
+// g + h (weighted A*). with h never overestimating, the first solution that turns up is at most
+// 1 + epsilon times as long as the shortest, and a lot less of the tree gets looked at on the way.
+// it only holds for the search without pruning, like the shortest solution does. the upper bound
+// still goes by the plain g + h, that's the one that's never over
+static EPSILON: OnceLock<f64> = OnceLock::new();
+
+// only the first call counts
+pub fn set_epsilon(epsilon: f64) {
+    let _ = EPSILON.set(epsilon);
+}
+
+// 0 unless set_epsilon's been called
+pub fn epsilon() -> f64 {
+    EPSILON.get().copied().unwrap_or(0.0)
+}
+
+// h as the open list sees it, see EPSILON
+fn weighted(move_cost: usize, h: usize) -> usize {
+    if move_cost == 0 {
+        return h;
+    }
+    (h as f64 * (1.0 + epsilon())).ceil() as usize
+}
+
+// every search's profile since the last take_profiles, in the order the searches finished
+static PROFILES: Mutex<Vec<SearchProfile>> = Mutex::new(Vec::new());
+
+// where a search spent its time, to tell whether it blew up going deep (lots of boards at each
+// depth) or wandered around a plateau (lots of boards with the same estimate)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchProfile {
+    pub num_prev_moves: usize,
+    // boards expanded at each depth (moves from the start)
+    pub expanded_per_depth: Vec<usize>,
+    // boards expanded with each estimate of the cost left
+    pub expanded_per_estimate: BTreeMap<usize, usize>,
+    // successors generated, over all the expanded boards
+    pub num_successors: usize,
+    pub max_successors: usize,
+    pub phases: Phases,
+}
+
+// how long (in nanoseconds) a search spent on each part of expanding boards. successors doesn't
+// include sucks, which happen while the successors are being generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Phases {
+    pub successors: u64,
+    pub sucks: u64,
+    // looking boards up in (and adding them to) the table of boards seen
+    pub hashing: u64,
+    pub heuristic: u64,
+    // checking whether we're done yet
+    pub goal: u64,
+    // pushing onto and popping off the open list
+    pub queue: u64,
+}
+
+// runs f, adding how long it took to *nanos if we're profiling
+fn timed<T>(profiling: bool, nanos: &mut u64, f: impl FnOnce() -> T) -> T {
+    if !profiling {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    *nanos += start.elapsed().as_nanos() as u64;
+    result
+}
+
+impl SearchProfile {
+    fn new(num_prev_moves: usize) -> Self {
+        Self {
+            num_prev_moves,
+            expanded_per_depth: vec![],
+            expanded_per_estimate: BTreeMap::new(),
+            num_successors: 0,
+            max_successors: 0,
+            phases: Phases::default(),
+        }
+    }
+
+    fn record(&mut self, depth: usize, estimate: usize, num_successors: usize) {
+        if self.expanded_per_depth.len() <= depth {
+            self.expanded_per_depth.resize(depth + 1, 0);
+        }
+        self.expanded_per_depth[depth] += 1;
+        *self.expanded_per_estimate.entry(estimate).or_default() += 1;
+        self.num_successors += num_successors;
+        self.max_successors = self.max_successors.max(num_successors);
+    }
+
+    pub fn num_expanded(&self) -> usize {
+        self.expanded_per_depth.iter().sum()
+    }
+
+    // a few lines: a header, then depth:count pairs, estimate:count pairs, the branching factor and
+    // how long each phase took
+    pub fn serialize(&self) -> String {
+        let pairs = |counts: &mut dyn Iterator<Item = (usize, usize)>| {
+            counts
+                .map(|(key, count)| format!("{}:{}", key, count))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let ms = |nanos: u64| nanos as f64 / 1e6;
+        let num_expanded = self.num_expanded();
+        format!(
+            "search num_prev_moves={} expanded={}\ndepth {}\nestimate {}\nbranching mean={:.2} max={}\n\
+             phases successors={:.1}ms sucks={:.1}ms hashing={:.1}ms heuristic={:.1}ms goal={:.1}ms \
+             queue={:.1}ms\n",
+            self.num_prev_moves,
+            num_expanded,
+            pairs(&mut self.expanded_per_depth.iter().copied().enumerate()),
+            pairs(&mut self.expanded_per_estimate.iter().map(|(&h, &n)| (h, n))),
+            self.num_successors as f64 / num_expanded.max(1) as f64,
+            self.max_successors,
+            ms(self.phases.successors),
+            ms(self.phases.sucks),
+            ms(self.phases.hashing),
+            ms(self.phases.heuristic),
+            ms(self.phases.goal),
+            ms(self.phases.queue),
+        )
+    }
+}
+
+pub fn take_profiles() -> Vec<SearchProfile> {
+    std::mem::take(&mut *PROFILES.lock().unwrap())
+}
+
+// for --trace-expansions: each search writes down the first this many boards it expands, with
+// their g, h and f and what happened to each of their successors. 0 is none
+pub static TRACE_EXPANSIONS: AtomicUsize = AtomicUsize::new(0);
+
+// every search's trace since the last take_traces, in the order the searches finished
+static TRACES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn take_traces() -> Vec<String> {
+    std::mem::take(&mut *TRACES.lock().unwrap())
+}
+
+// turns on Certificate collection in search, for --certificate
+pub static CERTIFYING: AtomicBool = AtomicBool::new(false);
+
+// the certificate of every exact search that found a solution since the last take_certificates
+static CERTIFICATES: Mutex<Vec<Certificate>> = Mutex::new(Vec::new());
+
+pub fn take_certificates() -> Vec<Certificate> {
+    std::mem::take(&mut *CERTIFICATES.lock().unwrap())
+}
+
+// whether a search with these settings only stops at a solution when there's none more than
+// 1 + epsilon times shorter
+fn is_bounded(num_prev_moves: usize, move_cost: usize) -> bool {
+    move_cost > 0
+        && num_prev_moves == OLD
+        && !closed::HASH_ONLY.load(AtomicOrdering::Relaxed)
+        && !layout::installed()
+}
+
+// whether a search with these settings only stops at a solution when there's none shorter, see
+// certificate.rs
+fn is_exact(num_prev_moves: usize, move_cost: usize) -> bool {
+    is_bounded(num_prev_moves, move_cost) && epsilon() == 0.0
+}
+
+// the shortest solution a bounded search (see is_bounded) has found from each board, by
+// fingerprint
+static BOUNDED: Mutex<BTreeMap<[u8; 16], usize>> = Mutex::new(BTreeMap::new());
+
+// how long the shortest solution from the board is at least, going by what the bounded searches
+// found: at least 1 / (1 + epsilon) of theirs. None if none of them finished from it (it was cut
+// short, or everything was pruned, decomposed or remembered)
+pub fn bounded_shortest(board: &Board) -> Option<usize> {
+    let length = *BOUNDED.lock().unwrap().get(&board.fingerprint())?;
+    Some((length as f64 / (1.0 + epsilon())).ceil() as usize)
+}
+
+// an entry in search's open list. lowest estimated total first, then furthest along, then the move
+// that set up the most sucks (see Move::lookahead), and any ties after that are left to however the
+// heap shakes out. that's what we got from pathfinding's astar, and breaking ties any other way
+// (oldest or newest first) makes some deals a lot slower. with --move-order, ties go to the move
+// the policies like best first (see ordering.rs), and with a Restart, whatever's still tied is
+// broken at random
+struct Queued {
+    estimate: usize,
+    cost: usize,
+    // 0 for the board we start from
+    lookahead: usize,
+    // always 0 without --move-order
+    rank: u32,
+    // always 0 without a Restart
+    tie_break: u64,
+    index: usize,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then(self.cost.cmp(&other.cost))
+            .then(self.lookahead.cmp(&other.lookahead))
+            .then(other.rank.cmp(&self.rank))
+            .then(self.tie_break.cmp(&other.tie_break))
+    }
+}
+
+// how many boards the first run of Strategy::Restarts gets to expand. the later runs get a multiple
+// of this, see luby
+const RESTART_EXPANSIONS: usize = 10_000;
+
+// the i'th (from 1) term of 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ..., the sequence of run
+// lengths that's within a constant factor of the best restart schedule when we don't know anything
+// about how long runs take (Luby, Sinclair and Zuckerman)
+fn luby(i: u64) -> u64 {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if i == (1 << k) - 1 {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+// one run of Strategy::Restarts: ties broken by an rng with this seed, and giving up after this
+// many boards
+pub(crate) struct Restart {
+    seed: u64,
+    max_expansions: usize,
+}
+
+pub(crate) enum Searched {
+    // the solution, and the board it ends on
+    Found(Box<(Solution, Board)>),
+    // there's nothing left to look at
+    Exhausted,
+    // the Restart's run is over, or Search's slice is up
+    OutOfBudget,
+    // the race called it off, see race::cancelled
+    Cancelled,
+}
+
+// plain A*. returns the solution along with the board it ends on
+pub(crate) fn search(
+    board: &Board,
+    num_prev_moves: usize,
+    move_cost: usize,
+    successors: &dyn Fn(&Board) -> Vec<(Board, Move)>,
+    heuristic: &dyn Fn(&Board) -> usize,
+    is_goal: impl Fn(&Board) -> bool,
+) -> Option<(Solution, Board)> {
+    match search_with_restart(
+        board,
+        num_prev_moves,
+        move_cost,
+        successors,
+        heuristic,
+        is_goal,
+        None,
+    ) {
+        Searched::Found(found) => Some(*found),
+        Searched::Exhausted | Searched::OutOfBudget | Searched::Cancelled => None,
+    }
+}
+
+pub(crate) fn search_with_restart(
+    board: &Board,
+    num_prev_moves: usize,
+    move_cost: usize,
+    successors: &dyn Fn(&Board) -> Vec<(Board, Move)>,
+    heuristic: &dyn Fn(&Board) -> usize,
+    is_goal: impl Fn(&Board) -> bool,
+    restart: Option<&Restart>,
+) -> Searched {
+    let mut astar = Astar::new(
+        board,
+        num_prev_moves,
+        move_cost,
+        heuristic,
+        restart.map(|restart| restart.seed),
+    );
+    let searched = astar.run(successors, heuristic, is_goal, |num_expanded| {
+        restart.is_some_and(|restart| num_expanded >= restart.max_expansions)
+    });
+    astar.report();
+    searched
+}
+
+// everything search_with_restart keeps from one board to the next, so that Search can stop it
+// when its slice is up and pick up again where it left off
+struct Astar {
+    num_prev_moves: usize,
+    move_cost: usize,
+    // only with a Restart
+    rng: Option<StdRng>,
+    // every board we've reached, and how we got there
+    nodes: Vec<Node>,
+    // the node we'd use for each board, which is the cheapest way we've found to it
+    best_node: Closed<usize>,
+    open: BinaryHeap<Queued>,
+    // the board that was next when the budget ran out. it's where we carry on from, rather than
+    // going back on the open list, where it could come back out behind a tie and change the search
+    paused: Option<Queued>,
+    num_expanded: usize,
+    // (cards left, index into nodes) of the board closest to being cleared that's come off the
+    // open list, see Search::best_line
+    closest: (usize, usize),
+    profile: Option<SearchProfile>,
+    phases: Phases,
+    max_traced: usize,
+    trace: Option<String>,
+}
+
+impl Astar {
+    fn new(
+        board: &Board,
+        num_prev_moves: usize,
+        move_cost: usize,
+        heuristic: &dyn Fn(&Board) -> usize,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut best_node = Closed::new();
+        best_node.insert(board.clone(), 0);
+        let max_traced = TRACE_EXPANSIONS.load(AtomicOrdering::Relaxed);
+        Self {
+            num_prev_moves,
+            move_cost,
+            rng: seed.map(StdRng::seed_from_u64),
+            nodes: vec![Node {
+                board: board.clone(),
+                parent: None,
+                cost: 0,
+                depth: 0,
+            }],
+            best_node,
+            open: BinaryHeap::from([Queued {
+                estimate: weighted(move_cost, heuristic(board)),
+                cost: 0,
+                lookahead: 0,
+                rank: 0,
+                tie_break: 0,
+                index: 0,
+            }]),
+            paused: None,
+            num_expanded: 0,
+            closest: (board.num_cards_remaining(), 0),
+            profile: PROFILING
+                .load(AtomicOrdering::Relaxed)
+                .then(|| SearchProfile::new(num_prev_moves)),
+            phases: Phases::default(),
+            max_traced,
+            trace: (max_traced > 0).then(|| {
+                format!(
+                    "trace num_prev_moves={} move_cost={}\n",
+                    num_prev_moves, move_cost
+                )
+            }),
+        }
+    }
+
+    fn start(&self) -> &Board {
+        &self.nodes[0].board
+    }
+
+    // plain A* until a goal, nothing left, or out_of_budget says the number of boards expanded so
+    // far is enough. the successors, heuristic and goal have to be the same every time
+    fn run(
+        &mut self,
+        successors: &dyn Fn(&Board) -> Vec<(Board, Move)>,
+        heuristic: &dyn Fn(&Board) -> usize,
+        is_goal: impl Fn(&Board) -> bool,
+        out_of_budget: impl Fn(usize) -> bool,
+    ) -> Searched {
+        let Self {
+            num_prev_moves,
+            move_cost,
+            rng,
+            nodes,
+            best_node,
+            open,
+            paused,
+            num_expanded,
+            closest,
+            profile,
+            phases,
+            max_traced,
+            trace,
+        } = self;
+        let (num_prev_moves, move_cost, max_traced) = (*num_prev_moves, *move_cost, *max_traced);
+        let mut tie_break = || rng.as_mut().map_or(0, |rng| rng.random());
+        let profiling = profile.is_some();
+        let suck_nanos_before = SUCK_NANOS.with(Cell::get);
+        let mut solution = Searched::Exhausted;
+        while let Some(queued) = paused
+            .take()
+            .or_else(|| timed(profiling, &mut phases.queue, || open.pop()))
+        {
+            let Queued {
+                index,
+                estimate,
+                cost,
+                ..
+            } = queued;
+            let node = &nodes[index];
+            // we've found a cheaper way to this board since this one was queued
+            let best = timed(profiling, &mut phases.hashing, || {
+                best_node.get(&node.board).copied()
+            });
+            if best != Some(index) {
+                continue;
+            }
+            if node.board.num_cards_remaining() < closest.0 {
+                *closest = (node.board.num_cards_remaining(), index);
+            }
+            if timed(profiling, &mut phases.goal, || is_goal(&node.board)) {
+                if let Some(trace) = trace.as_mut().filter(|_| *num_expanded < max_traced) {
+                    trace.push_str(&format!("goal: depth={} g={}\n", node.depth, cost));
+                }
+                if CERTIFYING.load(AtomicOrdering::Relaxed) && is_exact(num_prev_moves, move_cost) {
+                    CERTIFICATES.lock().unwrap().push(Certificate {
+                        fingerprint: nodes[0].board.fingerprint_hex(),
+                        num_expanded: *num_expanded,
+                        num_closed: best_node.len(),
+                        lower_bound: cost + heuristic(&node.board),
+                    });
+                }
+                solution = Searched::Found(Box::new((
+                    Solution {
+                        moves: line_to(nodes, index),
+                    },
+                    node.board.clone(),
+                )));
+                break;
+            }
+            if is_stalled(nodes, index, num_prev_moves) {
+                continue;
+            }
+            if out_of_budget(*num_expanded) {
+                *paused = Some(queued);
+                solution = Searched::OutOfBudget;
+                break;
+            }
+            if race::cancelled() {
+                *paused = Some(queued);
+                solution = Searched::Cancelled;
+                break;
+            }
+            *num_expanded += 1;
+            // h here is as the open list sees it, so with --epsilon it's already weighted
+            let mut trace = trace.as_mut().filter(|_| *num_expanded <= max_traced);
+            if let Some(trace) = trace.as_mut() {
+                trace.push_str(&format!(
+                    "expand {}: depth={} g={} h={} f={}",
+                    num_expanded,
+                    node.depth,
+                    cost,
+                    estimate - cost,
+                    estimate
+                ));
+                if let Some((_, moov)) = &node.parent {
+                    trace.push_str(&format!(" after {}", moov));
+                }
+                trace.push('\n');
+            }
+
+            let g = node.cost + move_cost;
+            let depth = node.depth + 1;
+            let next_boards = timed(profiling, &mut phases.successors, || {
+                successors(&node.board)
+            });
+            if let Some(profile) = profile.as_mut() {
+                profile.record(node.depth, estimate - cost, next_boards.len());
+            }
+            for (next_board, moov) in next_boards {
+                // see layout.rs
+                let Some(penalty) = layout::penalty(&moov) else {
+                    if let Some(trace) = trace.as_mut() {
+                        trace.push_str(&format!("  {}: too close to a button\n", moov));
+                    }
+                    continue;
+                };
+                let g = g + penalty;
+                let other = timed(profiling, &mut phases.hashing, || {
+                    best_node.get(&next_board).copied()
+                });
+                if let Some(other) = other {
+                    if nodes[other].cost <= g {
+                        if let Some(trace) = trace.as_mut() {
+                            trace.push_str(&format!(
+                                "  {}: seen before with g={}\n",
+                                moov, nodes[other].cost
+                            ));
+                        }
+                        continue;
+                    }
+                }
+                let h = timed(profiling, &mut phases.heuristic, || heuristic(&next_board));
+                if over_upper_bound(move_cost, g, h) {
+                    if let Some(trace) = trace.as_mut() {
+                        trace.push_str(&format!(
+                            "  {}: g={} h={} over the upper bound\n",
+                            moov, g, h
+                        ));
+                    }
+                    continue;
+                }
+                if let Some(trace) = trace.as_mut() {
+                    trace.push_str(&format!(
+                        "  {}: g={} h={} f={} queued\n",
+                        moov,
+                        g,
+                        h,
+                        g + weighted(move_cost, h)
+                    ));
+                }
+                timed(profiling, &mut phases.hashing, || {
+                    best_node.insert(next_board.clone(), nodes.len())
+                });
+                let queued = Queued {
+                    estimate: g + weighted(move_cost, h),
+                    cost: g,
+                    lookahead: moov.lookahead(&next_board),
+                    rank: ordering::rank(&nodes[index].board, &next_board, &moov),
+                    tie_break: tie_break(),
+                    index: nodes.len(),
+                };
+                timed(profiling, &mut phases.queue, || open.push(queued));
+                nodes.push(Node {
+                    board: next_board,
+                    parent: Some((index, moov)),
+                    cost: g,
+                    depth,
+                });
+            }
+        }
+        phases.sucks += SUCK_NANOS.with(Cell::get) - suck_nanos_before;
+        solution
+    }
+
+    // hands the trace and the profile over to take_traces and take_profiles, once the search is
+    // over
+    fn report(&mut self) {
+        if let Some(trace) = self.trace.take() {
+            TRACES.lock().unwrap().push(trace);
+        }
+        if let Some(mut profile) = self.profile.take() {
+            let mut phases = self.phases;
+            phases.successors = phases.successors.saturating_sub(phases.sucks);
+            profile.phases = phases;
+            PROFILES.lock().unwrap().push(profile);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub num_prev_moves: usize,
+    pub heuristic: Heuristic,
+    pub rules: Rules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchStatus {
+    // the budget ran out, call step again to keep going
+    InProgress,
+    Solved(Solution),
+    // there's nothing left to look at, so (as far as the pruning lets us see) there's no solution
+    Exhausted,
+}
+
+// checking the clock is slow compared to expanding a node, so only do it every so often
+const EXPANSIONS_PER_CLOCK_CHECK: usize = 256;
+
+struct Node {
+    board: Board,
+    // (index of the parent, move played from the parent)
+    parent: Option<(usize, Move)>,
+    cost: usize,
+    // number of moves from the start
+    depth: usize,
+}
+
+// the same search as solve_variant, but one that can be paused and resumed, so that something with
+// its own event loop (a GUI, the WASM build) can run it a slice at a time and stay responsive
+pub struct Search {
+    options: SearchOptions,
+    relaxed_bounds: RelaxedBounds,
+    astar: Astar,
+    status: SearchStatus,
+}
+
+impl Search {
+    pub fn new(board: &Board, options: SearchOptions) -> Self {
+        let mut board = board.clone();
+        board.suck_readies_into_receptacles();
+        let relaxed_bounds = RelaxedBounds::new();
+        let astar = {
+            let (heuristic, move_cost) = heuristic(options.heuristic, &relaxed_bounds);
+            Astar::new(&board, options.num_prev_moves, move_cost, &heuristic, None)
+        };
+        let status = match memo::is_dead(&board, &options.rules) {
+            true => SearchStatus::Exhausted,
+            false => SearchStatus::InProgress,
+        };
+        Self {
+            options,
+            relaxed_bounds,
+            astar,
+            status,
+        }
+    }
+
+    pub fn step(&mut self, budget_ms: u64) -> SearchStatus {
+        let deadline = Instant::now() + Duration::from_millis(budget_ms);
+        self.run(|num_expanded| {
+            num_expanded > 0
+                && num_expanded % EXPANSIONS_PER_CLOCK_CHECK == 0
+                && Instant::now() >= deadline
+        })
+    }
+
+    // like step, but the budget is a number of boards to expand rather than a time, so the result
+    // doesn't depend on how fast the machine is
+    pub fn step_expansions(&mut self, max_expansions: usize) -> SearchStatus {
+        self.run(|num_expanded| num_expanded >= max_expansions)
+    }
+
+    // out_of_budget goes by the boards expanded this time round
+    fn run(&mut self, out_of_budget: impl Fn(usize) -> bool) -> SearchStatus {
+        if self.status != SearchStatus::InProgress {
+            return self.status.clone();
+        }
+
+        let SearchOptions {
+            heuristic, rules, ..
+        } = self.options;
+        let (estimate, _) = self::heuristic(heuristic, &self.relaxed_bounds);
+        let before = self.astar.num_expanded;
+        let searched = self.astar.run(
+            &|b| successors_past_endgame(b, &rules),
+            &estimate,
+            |b| finish_from(b, heuristic, &rules).is_some(),
+            |num_expanded| out_of_budget(num_expanded - before),
+        );
+        match searched {
+            Searched::Found(found) => {
+                let (mut solution, board) = *found;
+                solution
+                    .moves
+                    .extend(finish_from(&board, heuristic, &rules).unwrap());
+                memo::remember_solution(self.astar.start(), &solution, &rules);
+                self.status = SearchStatus::Solved(solution);
+            }
+            Searched::Exhausted => {
+                if self.proves_dead() {
+                    memo::remember_dead(self.astar.start(), &rules);
+                }
+                self.status = SearchStatus::Exhausted;
+            }
+            Searched::OutOfBudget | Searched::Cancelled => return SearchStatus::InProgress,
+        }
+        self.astar.report();
+        self.status.clone()
+    }
+
+    // whether coming back Exhausted means there's no way through from the start at all, rather
+    // than none the pruning, --upper-bound or --safe-mode forbid let us look at
+    pub fn proves_dead(&self) -> bool {
+        proves_dead(self.options.num_prev_moves, self.astar.move_cost)
+    }
+
+    // the moves to the board with the fewest cards left that we've seen so far, and how many cards
+    // that board has left
+    pub fn best_line(&self) -> (Vec<Move>, usize) {
+        let (num_cards_left, index) = self.astar.closest;
+        (line_to(&self.astar.nodes, index), num_cards_left)
+    }
+
+    // the board best_line gets to
+    pub fn best_board(&self) -> &Board {
+        &self.astar.nodes[self.astar.closest.1].board
+    }
+
+    pub fn num_expanded(&self) -> usize {
+        self.astar.num_expanded
+    }
+}
+
+// gives a search per pruning level a slice at a time until one of them solves the board or the
+// budget runs out. either the solution, or the line to the board with the fewest cards left that
+// any of them got to, which is the best guess at how to start if we need a move right now
+pub fn anytime(
+    board: &Board,
+    heuristic: Heuristic,
+    rules: &Rules,
+    budget_ms: u64,
+) -> Result<Solution, Vec<Move>> {
+    const SLICE_MS: u64 = 20;
+    let deadline = Instant::now() + Duration::from_millis(budget_ms);
+    let mut searches: Vec<Search> = NUM_PREV_MOVES_TO_CONSIDERS
+        .iter()
+        .map(|&num_prev_moves| {
+            let options = SearchOptions {
+                num_prev_moves,
+                heuristic,
+                rules: *rules,
+            };
+            Search::new(board, options)
+        })
+        .collect();
+    while Instant::now() < deadline {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let slice_ms = SLICE_MS.min(left.as_millis() as u64);
+        let mut all_exhausted = true;
+        for search in &mut searches {
+            match search.step(slice_ms) {
+                SearchStatus::Solved(solution) => return Ok(solution),
+                SearchStatus::InProgress => all_exhausted = false,
+                SearchStatus::Exhausted => {}
+            }
+        }
+        if all_exhausted {
+            break;
+        }
+    }
+    let (best_line, _) = searches
+        .iter()
+        .map(Search::best_line)
+        .min_by_key(|(_, num_cards_left)| *num_cards_left)
+        .unwrap();
+    Err(best_line)
+}
@@ -0,0 +1,97 @@
+use crate::board::Board;
+use crate::provenance;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+// every deal a session's been dealt, by Board::fingerprint, and what came of it. kept in a file
+// that's only ever appended to, a line per deal: FINGERPRINT_HEX OUTCOME VERSION, where VERSION is
+// the build that had the outcome (see provenance.rs, and it's not there on older lines). when the
+// same deal comes round again, one we've already proven can't be won isn't worth the budget a
+// second time. one that only ran out of time gets another go, since it might have had a smaller
+// budget then (or a slower build)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Solved,
+    Unsolvable,
+    TimedOut,
+}
+
+impl Outcome {
+    pub fn serialize(&self) -> &'static str {
+        match self {
+            Outcome::Solved => "solved",
+            Outcome::Unsolvable => "unsolvable",
+            Outcome::TimedOut => "timed_out",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "solved" => Outcome::Solved,
+            "unsolvable" => Outcome::Unsolvable,
+            "timed_out" => Outcome::TimedOut,
+            otherwise => panic!("Invalid outcome: {}", otherwise),
+        }
+    }
+
+    // whether it's not worth trying the deal again, see above
+    pub fn skip_next_time(&self) -> bool {
+        *self == Outcome::Unsolvable
+    }
+}
+
+pub struct SeenDeals {
+    file: File,
+    // the latest outcome for each deal
+    outcomes: HashMap<[u8; 16], Outcome>,
+}
+
+impl SeenDeals {
+    // starts a new file if there isn't one yet
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut outcomes = HashMap::new();
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                    let mut fields = line.split_whitespace();
+                    let (fingerprint, outcome) = (fields.next().unwrap(), fields.next().unwrap());
+                    outcomes.insert(
+                        Board::parse_fingerprint(fingerprint),
+                        Outcome::parse(outcome),
+                    );
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file, outcomes })
+    }
+
+    pub fn outcome(&self, deal: &Board) -> Option<Outcome> {
+        self.outcomes.get(&deal.fingerprint()).copied()
+    }
+
+    pub fn record(&mut self, deal: &Board, outcome: Outcome) -> io::Result<()> {
+        self.outcomes.insert(deal.fingerprint(), outcome);
+        writeln!(
+            self.file,
+            "{} {} {}",
+            deal.fingerprint_hex(),
+            outcome.serialize(),
+            provenance::version()
+        )?;
+        self.file.sync_data()
+    }
+
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+}
@@ -0,0 +1,491 @@
+use crate::board::{Board, Protocol, Solution};
+use crate::bundle;
+use crate::differential::{self, Answer};
+use crate::eval::Winnable;
+use crate::interactive::{parse_known, read_deal, send_moves, Pacing, Sent};
+use crate::memo;
+use crate::rules::Rules;
+use crate::search::{
+    Heuristic, Search, SearchOptions, SearchStatus, Strategy, Variant, NUM_PREV_MOVES_TO_CONSIDERS,
+    OLD,
+};
+use crate::seen::{Outcome, SeenDeals};
+use crate::triage;
+use crate::warnings::{warn, Warning};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// playing game after game: for each deal the driver sends (DEAL ... END, like in interactive.rs)
+// we either send a plan (MOVE lines, then DONE) or tell it to deal again with NEW_GAME and why
+// (lost_the_way if, with acks, the driver FAILs a move and we can't find a way on from there).
+// after each game there's a STATS line with how the session's gone so far
+//
+// the point of deciding here rather than in the driver is that we know why we gave up: a deal we
+// searched all the way through without pruning really is unsolvable, while one we ran out of time
+// on might not be
+//
+// a deal that's a board along the last plan (the driver stopped partway and read the screen again,
+// say) gets the rest of that plan straight back, and with pondering so does one a move off it
+//
+// with adaptive budgets, how long a deal gets depends on what triage makes of it: a deal eval's
+// already settled needs little more, a hard one gets a lot more. with a budget per hour as well,
+// the time spent searching in any hour never goes over it, so a night of it takes as long as it
+// says. a deal that comes in once the hour's budget is spent gets none, and times out straight
+// away. and a deal triage thinks has a small search tree gets the search without pruning and
+// nothing else
+//
+// with a SeenDeals, a deal we've found unsolvable before (in this session or an earlier one) gets
+// NEW_GAME seen_before straight away
+
+// how long each variant gets before we move on to the next one, round and round until the budget's
+// gone
+const SLICE_MS: u64 = 50;
+
+// what an easy deal's budget is divided by, and what a hard one's is multiplied by
+const EASY_DIVISOR: u64 = 4;
+const HARD_MULTIPLIER: u64 = 4;
+
+// with adaptive budgets, a deal triage thinks has a tree this small (see treesize.rs) gets just the
+// unpruned search, which is the only one that can say it's unsolvable. the estimate counts a board
+// once for every way to it, and with empty piles about there are a lot of ways, so it's orders of
+// magnitude over what the search really looks at: an endgame of a handful of cards comes out around
+// 10^7, a new deal 10^9 and up
+const SMALL_TREE: u64 = 100_000_000;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub num_games: usize,
+    pub num_solved: usize,
+    // searched every position without pruning and there's no way to win
+    pub num_unsolvable: usize,
+    // ran out of budget
+    pub num_timed_out: usize,
+    // found unsolvable before, so we didn't try again
+    pub num_repeats: usize,
+    pub total_moves: usize,
+}
+
+impl Stats {
+    // with memo.rs on, how it's doing goes on the end
+    pub fn serialize(&self) -> String {
+        let s = format!(
+            "games={} solved={} unsolvable={} timed_out={} repeats={} moves={}",
+            self.num_games,
+            self.num_solved,
+            self.num_unsolvable,
+            self.num_timed_out,
+            self.num_repeats,
+            self.total_moves
+        );
+        if !memo::enabled() {
+            return s;
+        }
+        format!(
+            "{} memo_{}",
+            s,
+            memo::serialize_hits().replace(' ', " memo_")
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Solved(Solution),
+    Unsolvable,
+    TimedOut,
+}
+
+// races a search per pruning level in levels (a slice at a time, so it's all on this thread) until
+// one finds a solution, the unpruned one runs out of positions (with nothing else, like
+// --upper-bound, leaving any out), or the budget runs out. one that
+// panics is dropped from the race (with a VariantFailed warning) and the rest carry on
+pub fn decide(
+    board: &Board,
+    heuristic: Heuristic,
+    rules: &Rules,
+    budget_ms: u64,
+    levels: &[usize],
+) -> Verdict {
+    let deadline = Instant::now() + Duration::from_millis(budget_ms);
+    let mut searches: Vec<(usize, Search)> = levels
+        .iter()
+        .map(|&num_prev_moves| {
+            let options = SearchOptions {
+                num_prev_moves,
+                heuristic,
+                rules: *rules,
+            };
+            (num_prev_moves, Search::new(board, options))
+        })
+        .collect();
+    while Instant::now() < deadline && !searches.is_empty() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let slice_ms = SLICE_MS.min(left.as_millis() as u64);
+        let mut i = 0;
+        while i < searches.len() {
+            let (num_prev_moves, search) = &mut searches[i];
+            let variant = Variant {
+                strategy: Strategy::Full,
+                num_prev_moves: *num_prev_moves,
+                heuristic,
+            };
+            let what = format!("variant {}", variant.serialize());
+            match bundle::contain(&what, || search.step(slice_ms)) {
+                Ok(SearchStatus::Solved(solution)) => return Verdict::Solved(solution),
+                Ok(SearchStatus::Exhausted) if search.proves_dead() => return Verdict::Unsolvable,
+                // pruned away everything (or bounded it away), which doesn't tell us anything
+                Ok(SearchStatus::Exhausted) => {
+                    searches.remove(i);
+                }
+                Ok(SearchStatus::InProgress) => i += 1,
+                Err(why) => {
+                    warn(Warning::VariantFailed {
+                        variant: variant.serialize(),
+                        fingerprint: board.fingerprint_hex(),
+                        why,
+                    });
+                    searches.remove(i);
+                }
+            }
+        }
+    }
+    Verdict::TimedOut
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionOptions {
+    pub heuristic: Heuristic,
+    pub rules: Rules,
+    pub protocol: Protocol,
+    // how long to spend deciding on each deal
+    pub budget_ms: u64,
+    // see interactive::send_moves
+    pub pacing: Pacing,
+    // work out what to do from boards just off the plan while the driver's playing it, see ponder
+    pub ponder: bool,
+    // scale budget_ms by how hard triage thinks the deal is, see deal_plan
+    pub adaptive: bool,
+    // the most time to spend on deals in any hour, see Allowance
+    pub hourly_budget_ms: Option<u64>,
+    // before giving up on a deal as unsolvable, look through up to this many boards a different way
+    // to make sure, see verify
+    pub verify_unsolvable: Option<usize>,
+}
+
+// how long to spend on a deal and which pruning levels to race. budget_ms and all of them, or with
+// SessionOptions::adaptive, a share of it or a multiple of it depending on the triage, and only the
+// unpruned search if the tree's small enough for it to go all the way through
+fn deal_plan(board: &Board, options: &SessionOptions) -> (u64, &'static [usize]) {
+    if !options.adaptive {
+        return (options.budget_ms, &NUM_PREV_MOVES_TO_CONSIDERS);
+    }
+    let triage = triage::triage(board);
+    let budget_ms = match (triage.winnable, triage.verdict) {
+        // eval's already found the way through, or found there isn't one
+        (Winnable::Likely | Winnable::Impossible, _) => options.budget_ms / EASY_DIVISOR,
+        (Winnable::Unknown, triage::Verdict::Attempt) => options.budget_ms,
+        (Winnable::Unknown, triage::Verdict::Redeal) => options.budget_ms * HARD_MULTIPLIER,
+    };
+    let levels: &[usize] = if triage::tree_size(board, &options.rules) <= SMALL_TREE {
+        &[OLD]
+    } else {
+        &NUM_PREV_MOVES_TO_CONSIDERS
+    };
+    (budget_ms, levels)
+}
+
+// how much of the hour's budget is left, going by what's been spent in the last hour
+#[derive(Debug, Default)]
+struct Allowance {
+    per_hour_ms: Option<u64>,
+    // when each deal finished, and how long it took
+    spent: VecDeque<(Instant, u64)>,
+}
+
+impl Allowance {
+    fn left_ms(&mut self) -> u64 {
+        let Some(per_hour_ms) = self.per_hour_ms else {
+            return u64::MAX;
+        };
+        while let Some(&(when, _)) = self.spent.front() {
+            if when.elapsed() < HOUR {
+                break;
+            }
+            self.spent.pop_front();
+        }
+        let spent_ms: u64 = self.spent.iter().map(|&(_, ms)| ms).sum();
+        per_hour_ms.saturating_sub(spent_ms)
+    }
+
+    fn spend(&mut self, since: Instant) {
+        self.spent
+            .push_back((Instant::now(), since.elapsed().as_millis() as u64));
+    }
+}
+
+// solutions we've worked out ahead of time, by the fingerprint of the board they start from. shared
+// with the pondering thread
+type Cache = Arc<Mutex<HashMap<[u8; 16], Solution>>>;
+
+// how many boards into a plan we ponder the ways off it, and how long each one gets. the further
+// in, the less likely we'll ever be asked
+const PONDER_DEPTH: usize = 5;
+const PONDER_BUDGET_MS: u64 = 500;
+
+// the rest of the plan from each board along it. this plays every move, so it also checks the plan
+// really does go where we think it does
+fn suffixes(board: &Board, solution: &Solution, rules: &Rules) -> Vec<(Board, Solution)> {
+    let mut board = board.clone();
+    let mut suffixes = vec![];
+    for (i, moov) in solution.moves.iter().enumerate() {
+        let rest = Solution {
+            moves: solution.moves[i..].to_vec(),
+        };
+        let (next, _) = board.play(moov.from, moov.to, rules).unwrap();
+        suffixes.push((board, rest));
+        board = next;
+    }
+    suffixes
+}
+
+// in the background, solves the boards one move off the start of the plan (the driver misclicking,
+// or a card flying somewhere we didn't expect), so if the driver comes back with one of them we've
+// already got the answer. gives up at the next board once stop is set
+fn ponder(
+    board: &Board,
+    solution: &Solution,
+    options: &SessionOptions,
+    cache: &Cache,
+    stop: &Arc<AtomicBool>,
+) {
+    let plan = suffixes(board, solution, &options.rules);
+    let (cache, stop, options) = (cache.clone(), stop.clone(), *options);
+    thread::spawn(move || {
+        for (board, rest) in plan.iter().take(PONDER_DEPTH) {
+            let on_plan = &rest.moves[0];
+            for (off_plan, moov) in board.next_boards(&options.rules) {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                if moov.from.same_place(&on_plan.from) && moov.to.same_place(&on_plan.to) {
+                    continue;
+                }
+                let key = off_plan.fingerprint();
+                if cache.lock().unwrap().contains_key(&key) {
+                    continue;
+                }
+                let verdict = decide(
+                    &off_plan,
+                    options.heuristic,
+                    &options.rules,
+                    PONDER_BUDGET_MS,
+                    &NUM_PREV_MOVES_TO_CONSIDERS,
+                );
+                if let Verdict::Solved(solution) = verdict {
+                    cache.lock().unwrap().insert(key, solution);
+                }
+            }
+        }
+    });
+}
+
+// a plan from a board the driver got to instead of the one we expected: what we'd worked out ahead
+// of time if we have it, otherwise the usual search from there, out of the hour's budget like any
+// other
+fn replan(
+    board: &Board,
+    options: &SessionOptions,
+    cache: &Cache,
+    allowance: &mut Allowance,
+) -> Option<Solution> {
+    if let Some(solution) = cache.lock().unwrap().get(&board.fingerprint()) {
+        return Some(solution.clone());
+    }
+    let start = Instant::now();
+    let (budget_ms, levels) = deal_plan(board, options);
+    let budget_ms = budget_ms.min(allowance.left_ms());
+    let verdict = decide(board, options.heuristic, &options.rules, budget_ms, levels);
+    allowance.spend(start);
+    match verdict {
+        Verdict::Solved(solution) => Some(solution),
+        Verdict::Unsolvable | Verdict::TimedOut => None,
+    }
+}
+
+// a second opinion on an unsolvable verdict before we forfeit the deal, from differential.rs's
+// depth first search, which shares nothing with Search but the move generator. it runs out of
+// boards on most real deals, and then the verdict stands: the search did go through everything. if
+// it finds a way through, the search has a bug that's just cost us a winnable deal, so that's a
+// warning and a bug report with the deal in, and we play the way it found
+fn verify(board: &Board, rules: &Rules, max_boards: usize) -> Verdict {
+    match differential::dfs(board, rules, max_boards) {
+        Answer::Solved(solution) => {
+            warn(Warning::UnsolvableDisputed {
+                fingerprint: board.fingerprint_hex(),
+            });
+            bundle::report("the search said unsolvable, but depth first search won it");
+            Verdict::Solved(solution)
+        }
+        Answer::Unsolvable | Answer::OutOfBudget => Verdict::Unsolvable,
+    }
+}
+
+// what run is up to, for whoever's watching (see overlay.rs), along with the session's stats
+pub enum Progress<'a> {
+    // a deal's come in and we're deciding what to do with it
+    Thinking(&'a Board),
+    // what we made of it, just before the driver hears
+    Decided(&'a Board, &'a Verdict),
+    // we've found it unsolvable before, so we're not trying again
+    SeenBefore(&'a Board),
+}
+
+// reload is asked before each deal for new options, if they've changed (see config::Watcher). the
+// rules, protocol and pacing stay as they were, the driver's counting on them and the plans
+// we've cached were made with them. observe hears about each deal as it comes in, and again once
+// it's decided
+pub fn run(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    options: &SessionOptions,
+    mut seen: Option<&mut SeenDeals>,
+    reload: &mut dyn FnMut() -> Option<SessionOptions>,
+    observe: &mut dyn FnMut(Progress, &Stats),
+) -> io::Result<Stats> {
+    let mut options = *options;
+    let mut allowance = Allowance {
+        per_hour_ms: options.hourly_budget_ms,
+        ..Allowance::default()
+    };
+    let mut stats = Stats::default();
+    let cache = Cache::default();
+    let mut stop_pondering = Arc::new(AtomicBool::new(false));
+    while let Some(deal) = read_deal(input)? {
+        stop_pondering.store(true, Ordering::Relaxed);
+        if let Some(reloaded) = reload() {
+            options = SessionOptions {
+                rules: options.rules,
+                protocol: options.protocol,
+                pacing: options.pacing,
+                ..reloaded
+            };
+            allowance.per_hour_ms = options.hourly_budget_ms;
+        }
+        bundle::note_deal(&deal);
+        let board = parse_known(&deal)?;
+        // a board we've seen coming is the same game, partway through, and it's already been
+        // counted. anything else is a new one, and what we worked out for the last one is no use
+        let cached = cache.lock().unwrap().get(&board.fingerprint()).cloned();
+        let same_game = cached.is_some();
+        if !same_game {
+            cache.lock().unwrap().clear();
+            stats.num_games += 1;
+        }
+        observe(Progress::Thinking(&board), &stats);
+        let unsolvable_before = match (&cached, &seen) {
+            (None, Some(seen)) => seen.outcome(&board).filter(Outcome::skip_next_time),
+            _ => None,
+        };
+        if let Some(outcome) = unsolvable_before {
+            warn(Warning::RepeatedDeal {
+                fingerprint: board.fingerprint_hex(),
+                outcome: outcome.serialize().to_string(),
+            });
+            stats.num_repeats += 1;
+            observe(Progress::SeenBefore(&board), &stats);
+            writeln!(output, "NEW_GAME seen_before")?;
+            writeln!(output, "STATS {}", stats.serialize())?;
+            output.flush()?;
+            continue;
+        }
+        let verdict = match cached {
+            Some(solution) => Verdict::Solved(solution),
+            None => {
+                let start = Instant::now();
+                let (budget_ms, levels) = deal_plan(&board, &options);
+                let budget_ms = budget_ms.min(allowance.left_ms());
+                let mut verdict =
+                    decide(&board, options.heuristic, &options.rules, budget_ms, levels);
+                if let (Verdict::Unsolvable, Some(max_boards)) =
+                    (&verdict, options.verify_unsolvable)
+                {
+                    verdict = verify(&board, &options.rules, max_boards);
+                }
+                allowance.spend(start);
+                // with none of the hour's budget left it never got a look, so it's not a failure
+                if let (Some(seen), true) = (&mut seen, budget_ms > 0) {
+                    let outcome = match verdict {
+                        Verdict::Solved(_) => Outcome::Solved,
+                        Verdict::Unsolvable => Outcome::Unsolvable,
+                        Verdict::TimedOut => Outcome::TimedOut,
+                    };
+                    seen.record(&board, outcome)?;
+                }
+                verdict
+            }
+        };
+        match &verdict {
+            _ if same_game => {}
+            Verdict::Solved(solution) => {
+                stats.num_solved += 1;
+                stats.total_moves += solution.moves.len();
+            }
+            Verdict::Unsolvable => stats.num_unsolvable += 1,
+            Verdict::TimedOut => stats.num_timed_out += 1,
+        }
+        observe(Progress::Decided(&board, &verdict), &stats);
+        match verdict {
+            Verdict::Solved(solution) => {
+                cache.lock().unwrap().extend(
+                    suffixes(&board, &solution, &options.rules)
+                        .into_iter()
+                        .map(|(board, rest)| (board.fingerprint(), rest)),
+                );
+                if options.ponder {
+                    stop_pondering = Arc::new(AtomicBool::new(false));
+                    ponder(&board, &solution, &options, &cache, &stop_pondering);
+                }
+                let (rules, protocol, pacing) = (&options.rules, options.protocol, options.pacing);
+                let (mut board, mut solution) = (board, solution);
+                // with acks, the driver tells us as soon as a move goes wrong, and we carry on
+                // from there (see replan) rather than have it deal the board to us again
+                loop {
+                    match send_moves(input, output, &board, &solution, rules, protocol, pacing)? {
+                        Sent::Done => break,
+                        Sent::Quit => return Ok(stats),
+                        Sent::Diverged(observed) => {
+                            let observed = parse_known(&observed)?;
+                            match replan(&observed, &options, &cache, &mut allowance) {
+                                Some(replanned) => (board, solution) = (observed, replanned),
+                                None => {
+                                    writeln!(output, "NEW_GAME lost_the_way")?;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Verdict::Unsolvable => {
+                writeln!(output, "NEW_GAME unsolvable")?;
+            }
+            Verdict::TimedOut => {
+                // running out of the hour's budget isn't anything to look into
+                if allowance.left_ms() > 0 {
+                    bundle::report("timed out");
+                }
+                writeln!(output, "NEW_GAME timed_out")?;
+            }
+        }
+        bundle::note_stats(&stats.serialize());
+        writeln!(output, "STATS {}", stats.serialize())?;
+        output.flush()?;
+    }
+    Ok(stats)
+}
@@ -0,0 +1,65 @@
+use crate::board::{Board, Move, MoveLocation, Solution};
+use crate::rules::Rules;
+
+// reorders a solution so it's easier to follow by hand: moves working on the same pile get pulled
+// together, so you finish digging out one pile before starting on the next. only ever swaps two
+// neighbouring moves that can be played either way round and end up in the same place, so the
+// solution stays the same length and everything after the swap still works
+pub fn humanize(board: &Board, solution: &Solution, rules: &Rules) -> Solution {
+    let mut moves = solution.moves.clone();
+    let mut improved = true;
+    while improved {
+        improved = false;
+        // the board just before moves[i]
+        let mut board = board.clone();
+        for i in 0..moves.len() {
+            if i + 1 < moves.len() {
+                if let Some(swapped) = swap(&board, &moves[i], &moves[i + 1], rules) {
+                    let before = i.checked_sub(1).map(|j| &moves[j]);
+                    let after = moves.get(i + 2);
+                    let old = [before, Some(&moves[i]), Some(&moves[i + 1]), after];
+                    let new = [before, Some(&swapped.0), Some(&swapped.1), after];
+                    // strictly better, or we'd swap back and forth forever
+                    if num_pile_switches(&new) < num_pile_switches(&old) {
+                        (moves[i], moves[i + 1]) = swapped;
+                        improved = true;
+                    }
+                }
+            }
+            board = board.play(moves[i].from, moves[i].to, rules).unwrap().0;
+        }
+    }
+    Solution { moves }
+}
+
+// the pile a move is working on: the one it digs into, or for a card coming off the block, the one
+// it lands on
+fn pile(moov: &Move) -> Option<usize> {
+    match (moov.from, moov.to) {
+        (MoveLocation::PlayingArea { pile, .. }, _)
+        | (_, MoveLocation::PlayingArea { pile, .. }) => Some(pile),
+        _ => None,
+    }
+}
+
+fn num_pile_switches(moves: &[Option<&Move>]) -> usize {
+    moves
+        .windows(2)
+        .filter(|pair| match pair {
+            [Some(a), Some(b)] => pile(a) != pile(b),
+            _ => false,
+        })
+        .count()
+}
+
+// first and second played the other way round, if that works and leaves the board the same
+fn swap(board: &Board, first: &Move, second: &Move, rules: &Rules) -> Option<(Move, Move)> {
+    let (expected, _) =
+        board
+            .play(first.from, first.to, rules)?
+            .0
+            .play(second.from, second.to, rules)?;
+    let (middle, new_first) = board.play(second.from, second.to, rules)?;
+    let (end, new_second) = middle.play(first.from, first.to, rules)?;
+    (end == expected).then_some((new_first, new_second))
+}
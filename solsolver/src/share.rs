@@ -0,0 +1,175 @@
+use crate::board::{Board, Solution};
+use crate::rules::Rules;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// a solution short enough to paste into a chat, for swapping lines for a deal with other people.
+// it's the deal's fingerprint and, for each move, where it comes in the list of moves
+// Board::next_boards gives at that point, so it only means anything next to the deal it was made
+// for (which the other person has on their screen anyway). as bytes:
+// - the format version in the high nibble, and the first four rules in the low one (see rules_bits)
+// - from version 2, a byte for the rest of the rules
+// - the deal's fingerprint, 16 bytes
+// - each move's index as a LEB128 varint, which is one byte for all but the busiest boards
+// and then base64 (the URL safe alphabet, no padding), so it survives being pasted anywhere
+//
+// don't change the order next_boards lists moves in without bumping VERSION, or every code out
+// there will play something else
+
+const VERSION: u8 = 2;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareCode {
+    pub rules: Rules,
+    pub fingerprint: [u8; 16],
+    pub moves: Vec<usize>,
+}
+
+fn rules_bits(rules: &Rules) -> u8 {
+    rules.explicit_foundation_moves as u8
+        | (rules.game_auto_completes as u8) << 1
+        | (rules.no_block as u8) << 2
+        | (rules.optional_sucks as u8) << 3
+        | (rules.optional_major_sucks as u8) << 4
+}
+
+fn rules_from_bits(bits: u8) -> Rules {
+    Rules {
+        explicit_foundation_moves: bits & 1 != 0,
+        game_auto_completes: bits & 2 != 0,
+        no_block: bits & 4 != 0,
+        optional_sucks: bits & 8 != 0,
+        optional_major_sucks: bits & 16 != 0,
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            s.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    s
+}
+
+fn decode(s: &str) -> Vec<u8> {
+    let sextets: Vec<u32> = s
+        .trim()
+        .bytes()
+        .map(|c| match ALPHABET.iter().position(|&a| a == c) {
+            Some(sextet) => sextet as u32,
+            None => panic!("Invalid character in share code: {:?}", c as char),
+        })
+        .collect();
+    let mut bytes = Vec::new();
+    for chunk in sextets.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &sextet)| n | sextet << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    bytes
+}
+
+impl ShareCode {
+    // the board is the deal as it starts, with the sucks that happen before the first move done
+    pub fn new(board: &Board, solution: &Solution, rules: &Rules) -> Self {
+        let fingerprint = board.fingerprint();
+        let mut board = board.clone();
+        let moves = solution
+            .moves
+            .iter()
+            .map(|moov| {
+                let (index, (next, _)) = board
+                    .next_boards(rules)
+                    .into_iter()
+                    .enumerate()
+                    .find(|(_, (_, m))| m.from.same_place(&moov.from) && m.to.same_place(&moov.to))
+                    .unwrap();
+                board = next;
+                index
+            })
+            .collect();
+        Self {
+            rules: *rules,
+            fingerprint,
+            moves,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let rules = rules_bits(&self.rules);
+        let mut bytes = Vec::from([VERSION << 4 | rules & 0xf, rules >> 4]);
+        bytes.extend(self.fingerprint);
+        for &index in &self.moves {
+            let mut index = index;
+            loop {
+                let byte = (index & 0x7f) as u8;
+                index >>= 7;
+                if index == 0 {
+                    bytes.push(byte);
+                    break;
+                }
+                bytes.push(byte | 0x80);
+            }
+        }
+        encode(&bytes)
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let bytes = decode(s);
+        // version 1 codes are from before there were more rules than fit in the first byte
+        let header = match bytes.first().map(|byte| byte >> 4) {
+            Some(1) => 1,
+            Some(VERSION) => 2,
+            _ => panic!("Invalid share code: {}", s),
+        };
+        if bytes.len() < header + 16 {
+            panic!("Invalid share code: {}", s);
+        }
+        let rest = if header == 2 { bytes[1] } else { 0 };
+        let rules = bytes[0] & 0xf | rest << 4;
+        let mut moves = Vec::new();
+        let mut index = 0;
+        let mut shift = 0;
+        for &byte in &bytes[header + 16..] {
+            index |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                moves.push(index);
+                index = 0;
+                shift = 0;
+            }
+        }
+        Self {
+            rules: rules_from_bits(rules),
+            fingerprint: bytes[header..header + 16].try_into().unwrap(),
+            moves,
+        }
+    }
+
+    // the moves, played out on the deal. None if the code's for another deal, or doesn't play out
+    // on this one
+    pub fn replay(&self, board: &Board) -> Option<Solution> {
+        if board.fingerprint() != self.fingerprint {
+            return None;
+        }
+        let mut board = board.clone();
+        let mut moves = Vec::new();
+        for &index in &self.moves {
+            let (next, moov) = board.next_boards(&self.rules).into_iter().nth(index)?;
+            board = next;
+            moves.push(moov);
+        }
+        Some(Solution { moves })
+    }
+}
@@ -0,0 +1,76 @@
+use crate::board::{Board, Move, Solution};
+use crate::rules::Rules;
+use crate::search::{self, Heuristic};
+use std::collections::HashMap;
+
+// a shorter solution than one we already have, for keeping the lengths in the regression corpus
+// honest: if the solver gets better and the recorded length doesn't, the corpus can't tell when it
+// gets worse again. depth first branch and bound, with the solution we have as the first bound and
+// the first line we try, so we're never worse off than we started. there's nothing random in here,
+// and next_boards always lists moves in the same order, so the same budget gets the same answer
+// every time
+//
+// the bound is just the length (the relaxed bound's too slow to work out for every board we go
+// through), so with a real deal it's a lot of searching to get a few moves off. the moves we find
+// near the end of the line are the cheap ones
+
+// the moves from board, with the one the best solution so far plays here last, so it comes off
+// first
+fn ordered(board: &Board, rules: &Rules, seed: Option<&Move>) -> Vec<(Board, Move)> {
+    let mut moves = board.next_boards(rules);
+    if let Some(seed) = seed {
+        if let Some(i) = moves
+            .iter()
+            .position(|(_, m)| m.from.same_place(&seed.from) && m.to.same_place(&seed.to))
+        {
+            let seeded = moves.remove(i);
+            moves.push(seeded);
+        }
+    }
+    moves
+}
+
+// the shortest solution we come across in max_expansions boards, which is the one we started with
+// if there's nothing shorter (or we didn't get to it)
+pub fn shorten(
+    board: &Board,
+    solution: &Solution,
+    rules: &Rules,
+    max_expansions: usize,
+) -> Solution {
+    let mut board = board.clone();
+    board.suck_readies_into_receptacles();
+    let mut best = solution.moves.clone();
+    // the fewest moves we've got to each board in. getting there again in as many is no use
+    let mut reached: HashMap<Board, usize> = HashMap::from([(board.clone(), 0)]);
+    let mut line: Vec<Move> = vec![];
+    let mut untried = vec![ordered(&board, rules, best.first())];
+    let mut num_expanded = 1;
+    while let Some(moves) = untried.last_mut() {
+        let Some((next, moov)) = moves.pop() else {
+            untried.pop();
+            line.pop();
+            continue;
+        };
+        let depth = line.len() + 1;
+        // even if this move finished the game, it'd be no shorter
+        if depth >= best.len() {
+            continue;
+        }
+        if reached.get(&next).is_some_and(|&fewest| fewest <= depth) {
+            continue;
+        }
+        reached.insert(next.clone(), depth);
+        if let Some(rest) = search::wrap_up(&next, Heuristic::RelaxedLowerBound, rules) {
+            best = line.iter().cloned().chain([moov]).chain(rest).collect();
+            continue;
+        }
+        if num_expanded >= max_expansions {
+            break;
+        }
+        num_expanded += 1;
+        line.push(moov);
+        untried.push(ordered(&next, rules, best.get(depth)));
+    }
+    Solution { moves: best }
+}
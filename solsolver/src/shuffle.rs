@@ -0,0 +1,65 @@
+use crate::board::{Board, NUM_PLAYING_STACKS};
+use crate::card::{Card, MajorValue, MinorValue, Suit};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+// deals from a number, so a deal can be passed around (in the corpus, a bug report, an issue) as
+// SEED 1234 rather than seventy cards. this is our own shuffle, NOT the game's: nobody's worked out
+// how the game shuffles, so a seed here doesn't give you the deal the game would for it. if that
+// changes, the game's shuffle goes in deal and everything that takes a seed follows.
+//
+// the same seed gives the same deal as long as rand's StdRng doesn't change, which rand only
+// promises within a major version. a seed from before a rand upgrade might not give the same deal
+// after it
+
+// how a deal that's given by its seed starts, e.g. SEED 1234
+pub const SEED_PREFIX: &str = "SEED ";
+
+// cards per pile in a new deal
+const DEAL_DEPTH: usize = 7;
+
+// the pile in the middle starts empty
+const EMPTY_AT_START: usize = NUM_PLAYING_STACKS / 2;
+
+// a new game: every card but the aces (they start on the minor foundations), seven to a pile
+pub fn deal(seed: u64) -> Board {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut cards: Vec<Card> = (MajorValue::first().0..=MajorValue::last().0)
+        .map(|value| Card::Major(MajorValue(value)))
+        .chain(Suit::ALL.into_iter().flat_map(|suit| {
+            (2..=13).map(move |value| Card::Minor {
+                suit,
+                value: MinorValue(value),
+            })
+        }))
+        .collect();
+    cards.shuffle(&mut rng);
+    let mut chunks = cards.chunks(DEAL_DEPTH);
+    let lines: Vec<String> = (0..NUM_PLAYING_STACKS)
+        .map(|pile| match pile {
+            EMPTY_AT_START => String::new(),
+            _ => chunks
+                .next()
+                .unwrap()
+                .iter()
+                .map(Card::serialize)
+                .collect::<Vec<_>>()
+                .join(","),
+        })
+        .collect();
+    Board::parse(&(lines.join("\n") + "\n"))
+}
+
+// the seed, if the deal's text is just SEED N
+pub fn parse_seed(s: &str) -> Option<u64> {
+    s.trim().strip_prefix(SEED_PREFIX)?.trim().parse().ok()
+}
+
+// the deal's text with a SEED N swapped for the cards it stands for. anything else is left as is
+pub fn expand(s: &str) -> String {
+    match parse_seed(s) {
+        Some(seed) => deal(seed).serialize(),
+        None => s.to_string(),
+    }
+}
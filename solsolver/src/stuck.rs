@@ -0,0 +1,99 @@
+use crate::board::{Board, Solution};
+use crate::rules::Rules;
+use crate::search::{Heuristic, Search, SearchOptions, SearchStatus, OLD};
+
+// a second opinion on the game's own "no more moves" call. the game only says whether you're stuck,
+// and it's been seen saying so about positions that look winnable, so this says whether there's a
+// move at all and, looking at everything from here without pruning, whether any line wins
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    // there isn't a single legal move
+    Stuck,
+    // there's a way to clear the board (the game's wrong if it says stuck)
+    Winnable(Solution),
+    // there are moves, but we looked at everything they lead to and none of it wins
+    Lost,
+    // ran out of expansions before we could say either way
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StuckCheck {
+    // the moves the solver considers, see Board::next_boards
+    pub num_moves: usize,
+    // legal moves next_boards leaves out because they can't ever help, see pointless_moves
+    pub num_pointless_moves: usize,
+    pub verdict: Verdict,
+    pub num_expanded: usize,
+}
+
+impl StuckCheck {
+    pub fn serialize(&self) -> String {
+        let verdict = match &self.verdict {
+            Verdict::Stuck => "STUCK".to_string(),
+            Verdict::Winnable(solution) => format!("WINNABLE in={}", solution.moves.len()),
+            Verdict::Lost => "LOST".to_string(),
+            Verdict::Unknown => "UNKNOWN".to_string(),
+        };
+        format!(
+            "{} moves={} pointless_moves={} expanded={}",
+            verdict, self.num_moves, self.num_pointless_moves, self.num_expanded
+        )
+    }
+}
+
+// the legal moves next_boards doesn't bother with: a pile's only card onto an empty pile (which
+// leaves the board just as it was), and a pile's only card onto the block
+fn pointless_moves(board: &Board, rules: &Rules) -> usize {
+    let singles = board
+        .playing_area
+        .iter()
+        .filter(|pile| pile.len() == 1)
+        .count();
+    let empties = board
+        .playing_area
+        .iter()
+        .filter(|pile| pile.is_empty())
+        .count();
+    let onto_empty = singles * empties;
+    let onto_block = if board.minor_collection_blocked.is_none() && !rules.no_block {
+        singles
+    } else {
+        0
+    };
+    onto_empty + onto_block
+}
+
+pub fn check(board: &Board, rules: &Rules, max_expansions: usize) -> StuckCheck {
+    let num_moves = board.next_boards(rules).len();
+    let num_pointless_moves = pointless_moves(board, rules);
+    if num_moves + num_pointless_moves == 0 {
+        return StuckCheck {
+            num_moves,
+            num_pointless_moves,
+            verdict: Verdict::Stuck,
+            num_expanded: 0,
+        };
+    }
+    let mut search = Search::new(
+        board,
+        SearchOptions {
+            num_prev_moves: OLD,
+            heuristic: Heuristic::CardsRemaining,
+            rules: *rules,
+        },
+    );
+    let verdict = match search.step_expansions(max_expansions) {
+        SearchStatus::Solved(solution) => Verdict::Winnable(solution),
+        SearchStatus::Exhausted if search.proves_dead() => Verdict::Lost,
+        // left some boards out, so it could still be won from one of them
+        SearchStatus::Exhausted | SearchStatus::InProgress => Verdict::Unknown,
+    };
+    StuckCheck {
+        num_moves,
+        num_pointless_moves,
+        verdict,
+        num_expanded: search.num_expanded(),
+    }
+}
@@ -0,0 +1,226 @@
+use crate::board::{Board, Move, MoveLocation};
+use crate::provenance;
+use crate::relaxed::RelaxedBounds;
+use crate::rules::Rules;
+use crate::search::{self, Heuristic, OLD};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+// the fewest moves to clear every board with only a few cards left, worked out the first time we
+// get there and kept, so the search can stop as soon as it's into the endgame instead of searching
+// its way through it (and gets the best finish there is, not whichever one it tripped over first).
+// a board we've found can't be cleared from is a dead end, and nothing under it gets looked at
+//
+// enumerating every endgame up front isn't on, there are far too many ways to leave a few cards
+// lying around, so they're filled in as we meet them. save and load let them carry over from run to
+// run. on disk, a line per board:
+//   rules <explicit_foundation_moves><game_auto_completes><no_block><optional_sucks>
+//         <optional_major_sucks>   (as 0 or 1, all on one line)
+//   version <the build that last saved it, see provenance.rs>
+//   <fingerprint hex><tab><from-to from-to ...>   or   <fingerprint hex><tab>LOST
+// the rules line comes first, finishes for one set of rules are no good for another. files from
+// before there was a version line don't have one, and files from before optional_major_sucks have
+// one switch fewer, which was off
+
+const VERSION: &str = "version ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    // where each move of the finish goes from and to, which is all it takes to play them again
+    Won(Vec<(MoveLocation, MoveLocation)>),
+    Lost,
+}
+
+pub struct Tablebase {
+    // boards with more cards left than this are none of our business
+    max_cards: usize,
+    rules: Rules,
+    // by Board::fingerprint
+    entries: Mutex<HashMap<[u8; 16], Entry>>,
+}
+
+static INSTALLED: OnceLock<Tablebase> = OnceLock::new();
+
+// makes the searches use the tablebase from now on, for --tablebase
+pub fn install(tablebase: Tablebase) {
+    if INSTALLED.set(tablebase).is_err() {
+        panic!("there's already a tablebase installed");
+    }
+}
+
+pub fn installed() -> Option<&'static Tablebase> {
+    INSTALLED.get()
+}
+
+// the installed tablebase, if it has anything to say about boards like this under these rules
+pub fn covering(board: &Board, rules: &Rules) -> Option<&'static Tablebase> {
+    installed().filter(|tablebase| tablebase.covers(board, rules))
+}
+
+fn serialize_rules(rules: &Rules) -> String {
+    [
+        rules.explicit_foundation_moves,
+        rules.game_auto_completes,
+        rules.no_block,
+        rules.optional_sucks,
+        rules.optional_major_sucks,
+    ]
+    .iter()
+    .map(|&on| if on { '1' } else { '0' })
+    .collect()
+}
+
+impl Tablebase {
+    pub fn new(max_cards: usize, rules: &Rules) -> Self {
+        Self {
+            max_cards,
+            rules: *rules,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // a missing file is an empty tablebase, we'll be writing it out afterwards
+    pub fn load(path: &Path, max_cards: usize, rules: &Rules) -> io::Result<Self> {
+        let tablebase = Self::new(max_cards, rules);
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(tablebase),
+            Err(e) => return Err(e),
+        };
+        let mut lines = contents.lines();
+        let file_rules = lines
+            .next()
+            .and_then(|line| line.strip_prefix("rules "))
+            .map(|file_rules| match file_rules.len() {
+                4 => format!("{}0", file_rules),
+                _ => file_rules.to_string(),
+            });
+        if file_rules.as_deref() != Some(&serialize_rules(rules)) {
+            panic!(
+                "{} was built for different rules ({:?}), delete it to start again",
+                path.display(),
+                file_rules
+            );
+        }
+        let mut entries = tablebase.entries.lock().unwrap();
+        let lines = lines.filter(|line| !line.trim().is_empty() && !line.starts_with(VERSION));
+        for line in lines {
+            let (fingerprint, finish) = line.split_once('\t').unwrap();
+            let entry = match finish {
+                "LOST" => Entry::Lost,
+                finish => Entry::Won(
+                    finish
+                        .split_whitespace()
+                        .map(Move::parse_locations)
+                        .collect(),
+                ),
+            };
+            entries.insert(Board::parse_fingerprint(fingerprint), entry);
+        }
+        drop(entries);
+        Ok(tablebase)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut lines = vec![
+            format!("rules {}", serialize_rules(&self.rules)),
+            format!("{}{}", VERSION, provenance::version()),
+        ];
+        // sorted so the file doesn't churn from run to run
+        let mut entries: Vec<_> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(fingerprint, entry)| (*fingerprint, entry.clone()))
+            .collect();
+        entries.sort_by_key(|(fingerprint, _)| *fingerprint);
+        for (fingerprint, entry) in entries {
+            let hex: String = fingerprint.iter().map(|b| format!("{:02x}", b)).collect();
+            let finish = match entry {
+                Entry::Won(finish) => finish
+                    .iter()
+                    .map(|(from, to)| format!("{}-{}", from.serialize(), to.serialize()))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                Entry::Lost => "LOST".to_string(),
+            };
+            lines.push(format!("{}\t{}", hex, finish));
+        }
+        fs::write(path, lines.join("\n") + "\n")
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn covers(&self, board: &Board, rules: &Rules) -> bool {
+        *rules == self.rules && board.num_cards_remaining() <= self.max_cards
+    }
+
+    // the fewest moves that clear the board, or None if it can't be cleared. the board has to be
+    // one we cover
+    pub fn finish(&self, board: &Board) -> Option<Vec<Move>> {
+        let fingerprint = board.fingerprint();
+        // not held while solving, the other searches in the portfolio want it too
+        let known = self.entries.lock().unwrap().get(&fingerprint).cloned();
+        let entry = known.unwrap_or_else(|| {
+            let entry = self.solve(board);
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(fingerprint, entry.clone());
+            entry
+        });
+        match entry {
+            Entry::Won(finish) => {
+                let mut board = board.clone();
+                let moves = finish
+                    .iter()
+                    .map(|&(from, to)| {
+                        let (next, moov) = board.play(from, to, &self.rules).unwrap();
+                        board = next;
+                        moov
+                    })
+                    .collect();
+                Some(moves)
+            }
+            Entry::Lost => None,
+        }
+    }
+
+    // plain A* with nothing pruned, so running out of boards really does mean there's no way. with
+    // Rules::game_auto_completes the game can take over before the relaxed bound thinks we're
+    // done, so the bound isn't a bound any more and we go without
+    fn solve(&self, board: &Board) -> Entry {
+        let relaxed_bounds = RelaxedBounds::new();
+        let rules = &self.rules;
+        let estimate = |b: &Board| {
+            if rules.game_auto_completes {
+                0
+            } else {
+                relaxed_bounds.lower_bound(b)
+            }
+        };
+        let found = search::search(board, OLD, 1, &|b| b.next_boards(rules), &estimate, |b| {
+            search::wrap_up(b, Heuristic::RelaxedLowerBound, rules).is_some()
+        });
+        match found {
+            Some((solution, _)) => Entry::Won(
+                solution
+                    .moves
+                    .iter()
+                    .map(|moov| (moov.from, moov.to))
+                    .collect(),
+            ),
+            None => Entry::Lost,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::board::{Move, Protocol, Solution};
+use crate::encoding::FIELD_SEPARATOR;
+use std::fs;
+use std::path::Path;
+
+// how long the game takes to animate moves, which changes between game versions and speed
+// settings. the profiles live in a toml file (see timing.toml), one table per profile
+//
+// rather than timing the animations by hand, a profile can be fitted to how long the driver really
+// took over a few games, see fit
+
+// the most cards we'd expect to fly home in one tick, for fit to try
+const MAX_SUCKS_PER_TICK: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingProfile {
+    // dragging the card and letting go
+    pub move_ms: u64,
+    // each tick of cards flying to the foundations after a move
+    pub suck_ms: u64,
+    // how many cards fly home per tick
+    pub sucks_per_tick: usize,
+}
+
+impl TimingProfile {
+    pub fn load(path: &Path, name: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text, name).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(s: &str, name: &str) -> Result<Self, String> {
+        let table: toml::Table = s.parse().map_err(|e| format!("{}", e))?;
+        let profile = table
+            .get(name)
+            .and_then(toml::Value::as_table)
+            .ok_or_else(|| format!("no timing profile {:?}", name))?;
+        let field = |key: &str| {
+            profile
+                .get(key)
+                .and_then(toml::Value::as_integer)
+                .and_then(|value| u64::try_from(value).ok())
+                .ok_or_else(|| format!("timing profile {:?} needs {} (a number)", name, key))
+        };
+        let profile = TimingProfile {
+            move_ms: field("move_ms")?,
+            suck_ms: field("suck_ms")?,
+            sucks_per_tick: field("sucks_per_tick")? as usize,
+        };
+        if profile.sucks_per_tick == 0 {
+            return Err(format!("timing profile {:?} has no sucks per tick", name));
+        }
+        Ok(profile)
+    }
+
+    // how long the game takes to play out the move, sucks and all
+    pub fn move_ms(&self, moov: &Move) -> u64 {
+        let num_ticks = moov.num_sucks().div_ceil(self.sucks_per_tick);
+        self.move_ms + self.suck_ms * num_ticks as u64
+    }
+
+    pub fn solution_ms(&self, solution: &Solution) -> u64 {
+        solution.moves.iter().map(|moov| self.move_ms(moov)).sum()
+    }
+
+    // the solution as a recording to play back without watching the screen: a line per move,
+    // AT_MS<tab>MOVE, where AT_MS is when (from the start) to begin the move, if every move before
+    // it took as long as this profile says, and MOVE is in the newest protocol. then
+    // END<tab>TOTAL_MS. the game doesn't have a recording format of its own to play these back
+    // natively, so this is for a driver (or a converter to whatever one does) to go by
+    pub fn recording(&self, solution: &Solution) -> String {
+        let mut at_ms = 0;
+        let mut recording = String::new();
+        for moov in &solution.moves {
+            recording.push_str(&format!("{}\t{}\n", at_ms, moov.serialize(Protocol::V3)));
+            at_ms += self.move_ms(moov);
+        }
+        recording.push_str(&format!("END\t{}\n", at_ms));
+        recording
+    }
+
+    // the profile as a table for the timing file
+    pub fn serialize(&self, name: &str) -> String {
+        format!(
+            "[{}]\nmove_ms = {}\nsuck_ms = {}\nsucks_per_tick = {}\n",
+            name, self.move_ms, self.suck_ms, self.sucks_per_tick
+        )
+    }
+}
+
+// one move the driver played: how many cards it sucked and how long it took, start to finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Played {
+    pub num_sucks: usize,
+    pub ms: u64,
+}
+
+// the driver's log of how long each move took, a line per move, MOVE<tab>MS, where MOVE is as we
+// printed it (any protocol, they all start FROM-TO@NUM_SUCKS). blank lines and #s are skipped
+pub fn parse_log(s: &str) -> Result<Vec<Played>, String> {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let bad = || format!("line {}: expected MOVE<tab>MS, got {:?}", i + 1, line);
+            let (moov, ms) = line.rsplit_once('\t').ok_or_else(bad)?;
+            let num_sucks = moov
+                .split(FIELD_SEPARATOR)
+                .nth(1)
+                .and_then(|num_sucks| num_sucks.parse().ok())
+                .ok_or_else(bad)?;
+            let ms = ms.trim().parse().map_err(|_| bad())?;
+            Ok(Played { num_sucks, ms })
+        })
+        .collect()
+}
+
+// the profile that best explains the moves (least squares), along with how far off it is on a
+// typical move (the root mean square error, in ms). sucks_per_tick has to be a whole number, so we
+// fit move_ms and suck_ms for each one up to MAX_SUCKS_PER_TICK and keep whichever fits best. it
+// needs moves with different numbers of ticks to tell the two apart
+pub fn fit(played: &[Played]) -> Result<(TimingProfile, f64), String> {
+    if played.is_empty() {
+        return Err("no moves to fit to".to_string());
+    }
+    (1..=MAX_SUCKS_PER_TICK)
+        .filter_map(|sucks_per_tick| {
+            let points: Vec<(f64, f64)> = played
+                .iter()
+                .map(|played| {
+                    let num_ticks = played.num_sucks.div_ceil(sucks_per_tick);
+                    (num_ticks as f64, played.ms as f64)
+                })
+                .collect();
+            let n = points.len() as f64;
+            let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+            let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+            let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+            let sxy: f64 = points
+                .iter()
+                .map(|(x, y)| (x - mean_x) * (y - mean_y))
+                .sum();
+            if sxx == 0.0 {
+                return None;
+            }
+            // neither can really be negative, so a fit that says so gets pinned at 0
+            let suck_ms = (sxy / sxx).max(0.0).round();
+            let move_ms = (mean_y - suck_ms * mean_x).max(0.0).round();
+            let mean_square = points
+                .iter()
+                .map(|(x, y)| (move_ms + suck_ms * x - y).powi(2))
+                .sum::<f64>()
+                / n;
+            let profile = TimingProfile {
+                move_ms: move_ms as u64,
+                suck_ms: suck_ms as u64,
+                sucks_per_tick,
+            };
+            Some((profile, mean_square.sqrt()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .ok_or_else(|| {
+            "every move in the log took the same number of ticks, so there's no telling the move \
+             from the sucks"
+                .to_string()
+        })
+}
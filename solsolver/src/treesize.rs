@@ -0,0 +1,83 @@
+use crate::board::Board;
+use crate::rules::Rules;
+use crate::search::{MINIMUM_AMT_OF_PROGRESS, OLD};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::SeedableRng;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+// how many boards a search would go through, worked out without going through them (Knuth, 1975).
+// a probe walks down from the start taking a move at random each time. if the boards it passes
+// have d1, d2, d3... moves, then 1 + d1 + d1*d2 + d1*d2*d3 + ... is how big the tree would be if
+// every board like it had as many moves, and the average over enough probes is how big it is.
+// each probe's cheap, so we get a few hundred in a tenth of a second
+//
+// the catch is that it's a tree, not the graph the search sees: a board we can get to two ways
+// counts twice, where the search would only look at it once. so it's an overestimate, often a big
+// one, and best taken as an order of magnitude. a probe stops where the search would (cleared,
+// stuck, or stalled for num_prev_moves, see search::is_stalled), or if it comes back to a board
+// it's already been through
+
+// a probe that gets this deep is going round in circles, however it's managing it
+const MAX_DEPTH: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeSize {
+    pub num_boards: f64,
+    pub num_probes: usize,
+}
+
+fn probe(board: &Board, rules: &Rules, num_prev_moves: usize, rng: &mut StdRng) -> f64 {
+    let mut board = board.clone();
+    let mut path = HashSet::from([board.clone()]);
+    // how many cards went home with each move
+    let mut homes = vec![];
+    let (mut num_boards, mut width) = (1.0, 1.0);
+    while homes.len() < MAX_DEPTH && !board.is_done() {
+        let stalled = num_prev_moves != OLD
+            && homes.len() >= num_prev_moves
+            && homes[homes.len() - num_prev_moves..].iter().sum::<usize>()
+                <= MINIMUM_AMT_OF_PROGRESS;
+        if stalled {
+            break;
+        }
+        let next_boards: Vec<_> = board
+            .next_boards(rules)
+            .into_iter()
+            .filter(|(next, _)| !path.contains(next))
+            .collect();
+        let Some((next, moov)) = next_boards.choose(rng) else {
+            break;
+        };
+        width *= next_boards.len() as f64;
+        num_boards += width;
+        homes.push(moov.num_cards_home());
+        path.insert(next.clone());
+        board = next.clone();
+    }
+    num_boards
+}
+
+// as many probes as fit in budget_ms (at least one)
+pub fn estimate(
+    board: &Board,
+    rules: &Rules,
+    num_prev_moves: usize,
+    budget_ms: u64,
+    seed: u64,
+) -> TreeSize {
+    let deadline = Instant::now() + Duration::from_millis(budget_ms);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = board.clone();
+    board.suck_readies_into_receptacles();
+    let (mut total, mut num_probes) = (0.0, 0);
+    while num_probes == 0 || Instant::now() < deadline {
+        total += probe(&board, rules, num_prev_moves, &mut rng);
+        num_probes += 1;
+    }
+    TreeSize {
+        num_boards: total / num_probes as f64,
+        num_probes,
+    }
+}
@@ -0,0 +1,135 @@
+use crate::board::Board;
+use crate::card::{Card, CardId};
+use crate::eval::{self, Winnable};
+use crate::relaxed::RelaxedBounds;
+use crate::rules::Rules;
+use crate::search::OLD;
+use crate::treesize;
+use crate::unknown;
+
+// a quick (well under a second, ~100ms) call on whether a deal's worth the full search, so the
+// driver can deal again straight away instead of spending minutes finding out it's hopeless
+//
+// first we give eval a short look, which settles the easy ones either way. if that doesn't, we fall
+// back on a few things about the deal that make it hard, weighted by hand. the weights were picked
+// by eye against the example deals, not fitted to anything, so treat the score as a rough guide
+//
+// tree_size guesses how big the search would be (see treesize.rs). that doesn't go into the
+// verdict, it's for picking how to search, see session::deal_plan, and it's kept out of triage so
+// that the callers that don't want it (the heatmap triages every deal in a run) don't pay for it
+
+// how long eval gets before we go by the features alone
+const EVAL_MS: u64 = 60;
+
+// how long we spend guessing how big the search would be, see treesize.rs
+const PROBE_MS: u64 = 40;
+
+// scores above this aren't worth attempting
+const REDEAL_ABOVE: i32 = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    // pairs of minors of the same suit in the same pile with the higher one on top, each one's a
+    // card that has to be moved out of the way before the lower one can go home
+    pub buried_minors: usize,
+    pub empty_piles: usize,
+    // see relaxed.rs
+    pub relaxed_bound: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Attempt,
+    Redeal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Triage {
+    pub verdict: Verdict,
+    // higher is more hopeless
+    pub score: i32,
+    pub features: Features,
+    // what eval made of it, if that's what decided
+    pub winnable: Winnable,
+}
+
+impl Triage {
+    pub fn serialize(&self) -> String {
+        let verdict = match self.verdict {
+            Verdict::Attempt => "ATTEMPT",
+            Verdict::Redeal => "REDEAL",
+        };
+        format!(
+            "{} score={} buried={} empty={} relaxed={} eval={:?}",
+            verdict,
+            self.score,
+            self.features.buried_minors,
+            self.features.empty_piles,
+            self.features.relaxed_bound,
+            self.winnable
+        )
+    }
+}
+
+fn minor_value(card: CardId) -> Option<(usize, u8)> {
+    match card.card() {
+        Card::Minor { suit, value } => Some((suit as usize, value.0)),
+        Card::Major(_) => None,
+    }
+}
+
+// see Features::buried_minors
+pub fn num_buried_minors(board: &Board) -> usize {
+    let mut buried_minors = 0;
+    for stack in &board.playing_area {
+        for (depth, &card) in stack.iter().enumerate() {
+            let Some((suit, value)) = minor_value(card) else {
+                continue;
+            };
+            buried_minors += stack[depth + 1..]
+                .iter()
+                .filter_map(|&above| minor_value(above))
+                .filter(|&(above_suit, above_value)| above_suit == suit && above_value > value)
+                .count();
+        }
+    }
+    buried_minors
+}
+
+pub fn features(board: &Board) -> Features {
+    Features {
+        buried_minors: num_buried_minors(board),
+        empty_piles: board.playing_area.iter().filter(|s| s.is_empty()).count(),
+        relaxed_bound: RelaxedBounds::new().lower_bound(board),
+    }
+}
+
+// the example deals (solvable or not) all come out between 30 and 60
+pub fn score(features: &Features) -> i32 {
+    4 * features.buried_minors as i32 + 2 * features.relaxed_bound as i32
+        - 10 * features.empty_piles as i32
+}
+
+pub fn triage(board: &Board) -> Triage {
+    let features = features(board);
+    let score = score(&features);
+    let winnable = eval::evaluate(board, EVAL_MS).winnable;
+    let verdict = match winnable {
+        Winnable::Likely => Verdict::Attempt,
+        Winnable::Impossible => Verdict::Redeal,
+        Winnable::Unknown if score > REDEAL_ABOVE => Verdict::Redeal,
+        Winnable::Unknown => Verdict::Attempt,
+    };
+    Triage {
+        verdict,
+        score,
+        features,
+        winnable,
+    }
+}
+
+// roughly how many boards the search without pruning would go through under these rules, see
+// treesize.rs
+pub fn tree_size(board: &Board, rules: &Rules) -> u64 {
+    treesize::estimate(board, rules, OLD, PROBE_MS, unknown::seed()).num_boards as u64
+}
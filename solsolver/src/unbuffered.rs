@@ -0,0 +1,37 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// with --unbuffered, every line on stdout goes out as soon as it's written. stdout in rust is
+// already line buffered, but only up to the last newline in each write, and only when it's stdout
+// itself we're writing to: anything that wraps it, or writes half a line and the rest later, can
+// sit in a buffer while we think. behind a pty or a pipe that looks to the driver like we've hung
+pub static UNBUFFERED: AtomicBool = AtomicBool::new(false);
+
+// a writer that flushes after every line if UNBUFFERED is set, and is just the writer if not
+pub struct Unbuffered<W: Write>(pub W);
+
+impl<W: Write> Write for Unbuffered<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.0.write(buf)?;
+        if UNBUFFERED.load(Ordering::Relaxed) && buf[..written].contains(&b'\n') {
+            self.0.flush()?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+
+    // a writeln! goes to the writer in one piece rather than a bit at a time, so with stdout nothing
+    // else can get in partway through the line (see heartbeat.rs)
+    fn write_fmt(&mut self, args: fmt::Arguments) -> io::Result<()> {
+        let text = args.to_string();
+        self.0.write_all(text.as_bytes())?;
+        if UNBUFFERED.load(Ordering::Relaxed) && text.contains('\n') {
+            self.0.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+use crate::board::{pile_lines, Board, Move, Solution};
+use crate::card::{Card, CardId};
+use crate::rules::Rules;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::sync::OnceLock;
+
+// a card the screen reader couldn't make out (face down, or covered by something) can be given as
+// ? instead. it has to be one of the cards that's missing from the rest of the deal, so we try it
+// as each of them
+pub const UNKNOWN: &str = "?";
+
+// the screen reader can only make out so many cards from the top of a pile, the ones further down
+// being too covered up to read. those are given as a run, ?N for the N cards at the bottom of the
+// pile (so it has to come first in the line). they're unknowns like any other, but we know that any
+// move that takes the pile below the height it was read at brings one of them into view, so we stop
+// there and ask for the board again, see plan
+fn hidden_run(token: &str) -> Option<usize> {
+    token.strip_prefix(UNKNOWN)?.parse().ok()
+}
+
+fn serialize_hidden_run(num_cards: usize) -> String {
+    format!("{}{}", UNKNOWN, num_cards)
+}
+
+// past this many ways of filling in the unknowns, we don't try them all (see completions)
+const MAX_COMPLETIONS: usize = 24;
+
+fn tokens(line: &str) -> impl Iterator<Item = &str> {
+    line.trim()
+        .split_terminator(',')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+// the tokens with each hidden run spread out into an unknown per card
+fn spread(line: &str) -> impl Iterator<Item = &str> {
+    tokens(line).flat_map(|token| match hidden_run(token) {
+        Some(num_cards) => vec![UNKNOWN; num_cards],
+        None => vec![token],
+    })
+}
+
+// which piles have cards too far down to read
+fn has_hidden(s: &str) -> Vec<bool> {
+    pile_lines(s)
+        .map(|line| tokens(line).any(|token| hidden_run(token).is_some()))
+        .collect()
+}
+
+// (pile, depth) of every unknown card
+pub fn unknown_positions(s: &str) -> Vec<(usize, usize)> {
+    pile_lines(s)
+        .enumerate()
+        .flat_map(|(pile, line)| {
+            spread(line)
+                .enumerate()
+                .filter(|(_, token)| *token == UNKNOWN)
+                .map(move |(depth, _)| (pile, depth))
+        })
+        .collect()
+}
+
+// the deal with each unknown replaced by the next card, or dropped if we run out
+fn fill(s: &str, cards: &[Card]) -> String {
+    let mut cards = cards.iter();
+    s.lines()
+        .map(|line| {
+            spread(line)
+                .filter_map(|token| match token {
+                    UNKNOWN => cards.next().map(Card::serialize),
+                    _ => Some(token.to_string()),
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// the deal with the unknown at (pile, depth) replaced by the card it turned out to be. if it was in
+// a hidden run, the cards either side of it stay hidden
+pub fn resolve(s: &str, position: (usize, usize), card: Card) -> String {
+    let index = unknown_positions(s)
+        .iter()
+        .position(|&other| other == position)
+        .unwrap_or_else(|| panic!("{:?} isn't an unknown card", position));
+    let mut seen = 0;
+    s.lines()
+        .map(|line| {
+            tokens(line)
+                .flat_map(|token| {
+                    let num_cards = match token {
+                        UNKNOWN => 1,
+                        _ => match hidden_run(token) {
+                            Some(num_cards) => num_cards,
+                            None => return vec![token.to_string()],
+                        },
+                    };
+                    let first = seen;
+                    seen += num_cards;
+                    if !(first..seen).contains(&index) {
+                        return vec![token.to_string()];
+                    }
+                    if token == UNKNOWN {
+                        return vec![card.serialize()];
+                    }
+                    let (below, above) = (index - first, seen - index - 1);
+                    (below > 0)
+                        .then(|| serialize_hidden_run(below))
+                        .into_iter()
+                        .chain([card.serialize()])
+                        .chain((above > 0).then(|| serialize_hidden_run(above)))
+                        .collect()
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// the cards the unknowns could be
+pub fn candidates(s: &str) -> Vec<CardId> {
+    Board::parse(&fill(s, &[])).missing_cards().iter().collect()
+}
+
+// whether completions tries every way of filling in the unknowns
+fn tries_every_completion(s: &str) -> bool {
+    let num_unknowns = unknown_positions(s).len();
+    let num_candidates = candidates(s).len();
+    assert!(
+        num_candidates >= num_unknowns,
+        "{} unknown cards but only {} missing from the deal",
+        num_unknowns,
+        num_candidates
+    );
+    let num_completions = (num_candidates - num_unknowns + 1..=num_candidates)
+        .try_fold(1usize, |product, n| product.checked_mul(n))
+        .unwrap_or(usize::MAX);
+    num_completions <= MAX_COMPLETIONS
+}
+
+// boards the deal could really be. when there aren't too many, that's every way of filling in the
+// unknowns. otherwise it's one per candidate, rotating the candidates through the unknowns, so that
+// every unknown has been every card at least once
+pub fn completions(s: &str) -> Vec<Board> {
+    let num_unknowns = unknown_positions(s).len();
+    let candidates: Vec<Card> = candidates(s).into_iter().map(CardId::card).collect();
+    let assignments = if tries_every_completion(s) {
+        arrangements(&candidates, num_unknowns)
+    } else {
+        (0..candidates.len())
+            .map(|rotation| {
+                (0..num_unknowns)
+                    .map(|i| candidates[(i + rotation) % candidates.len()])
+                    .collect()
+            })
+            .collect()
+    };
+    assignments
+        .iter()
+        .map(|cards| Board::parse(&fill(s, cards)))
+        .collect()
+}
+
+// every ordered choice of n of the cards
+fn arrangements(cards: &[Card], n: usize) -> Vec<Vec<Card>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut all = vec![];
+    for (i, &card) in cards.iter().enumerate() {
+        let mut rest = cards.to_vec();
+        rest.remove(i);
+        for mut arrangement in arrangements(&rest, n - 1) {
+            arrangement.insert(0, card);
+            all.push(arrangement);
+        }
+    }
+    all
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    // one of the completions, any of them plays the moves the same way
+    pub board: Board,
+    pub solution: Solution,
+    // if the solution stops short, where the cards are that we need to see before going on. empty
+    // if it's a full solution
+    pub reread: Vec<(usize, usize)>,
+}
+
+fn same_move(a: &Move, b: &Move) -> bool {
+    a.from.same_place(&b.from) && a.to.same_place(&b.to) && a.card == b.card
+}
+
+// solves every completion, and plays as far as their solutions agree. that's as far as we can go
+// without knowing what the unknowns are. with hidden runs, it's also no further than the first move
+// that brings a hidden card into view: past that the completions only agree by luck when there are
+// too many to try them all, and the driver can just read it. for the same reason, when there are
+// too many, it's no further than the first move that leaves an unknown card on top of a pile (see
+// uncovered). a completion that can't be solved disagrees with all the rest, since playing on
+// could lose us the deal if it's the real one, so then nothing's played until we know more. None if
+// none of the completions can be solved
+pub fn plan(s: &str, solve: &dyn Fn(&Board) -> Option<Solution>, rules: &Rules) -> Option<Plan> {
+    let completions = completions(s);
+    let num_completions = completions.len();
+    let solved: Vec<(Board, Solution)> = completions
+        .into_iter()
+        .filter_map(|mut board| {
+            board.suck_readies_into_receptacles();
+            let solution = solve(&board)?;
+            Some((board, solution))
+        })
+        .collect();
+    let (first_board, first_solution) = solved.first()?;
+    if solved.len() < num_completions {
+        return Some(Plan {
+            board: first_board.clone(),
+            solution: Solution { moves: vec![] },
+            reread: unknown_positions(s),
+        });
+    }
+    let agreed = (0..first_solution.moves.len())
+        .take_while(|&i| {
+            solved.iter().all(|(_, solution)| {
+                solution
+                    .moves
+                    .get(i)
+                    .is_some_and(|moov| same_move(moov, &first_solution.moves[i]))
+            })
+        })
+        .count();
+    let mut agreed = agreed.min(horizon(
+        s,
+        first_board,
+        &first_solution.moves[..agreed],
+        rules,
+    ));
+    if !tries_every_completion(s) {
+        agreed = agreed.min(uncovered(
+            s,
+            first_board,
+            &first_solution.moves[..agreed],
+            rules,
+        ));
+    }
+
+    // the piles after the moves we agree on, in every completion
+    let ends: Vec<Board> = solved
+        .iter()
+        .map(|(board, solution)| {
+            let mut board = board.clone();
+            for moov in &solution.moves[..agreed] {
+                board = board.play(moov.from, moov.to, rules).unwrap().0;
+            }
+            board
+        })
+        .collect();
+    // out to the tallest each pile is in any of them, since a card that got sucked in in one
+    // completion can still be sitting there in another
+    let mut reread = vec![];
+    for pile in 0..ends[0].playing_area.len() {
+        let height = ends
+            .iter()
+            .map(|end| end.playing_area[pile].len())
+            .max()
+            .unwrap_or(0);
+        for depth in 0..height {
+            let card = ends[0].playing_area[pile].get(depth);
+            if ends
+                .iter()
+                .any(|end| end.playing_area[pile].get(depth) != card)
+            {
+                reread.push((pile, depth));
+            }
+        }
+    }
+
+    // nothing left that we can't see, so any of them will do
+    if reread.is_empty() {
+        return Some(Plan {
+            board: first_board.clone(),
+            solution: first_solution.clone(),
+            reread,
+        });
+    }
+    Some(Plan {
+        board: first_board.clone(),
+        solution: Solution {
+            moves: first_solution.moves[..agreed].to_vec(),
+        },
+        reread,
+    })
+}
+
+// how many of the moves we can play before one of them takes a pile with hidden cards below the
+// height it started at, counting that move
+fn horizon(s: &str, board: &Board, moves: &[Move], rules: &Rules) -> usize {
+    let hidden = has_hidden(s);
+    let heights: Vec<usize> = board.playing_area.iter().map(Vec::len).collect();
+    let mut board = board.clone();
+    for (i, moov) in moves.iter().enumerate() {
+        board = board.play(moov.from, moov.to, rules).unwrap().0;
+        let exposed = board
+            .playing_area
+            .iter()
+            .zip(&heights)
+            .zip(&hidden)
+            .any(|((stack, &height), &hidden)| hidden && stack.len() < height);
+        if exposed {
+            return i + 1;
+        }
+    }
+    moves.len()
+}
+
+// how many of the moves we can play before one of them leaves an unknown card on top of its pile,
+// counting that move, or 0 if there's one on top already. up to there the moves only ever touch
+// cards we can see, so they're legal whatever the unknowns turn out to be
+fn uncovered(s: &str, board: &Board, moves: &[Move], rules: &Rules) -> usize {
+    // the depth of the highest unknown in each pile
+    let mut highest = vec![None; board.playing_area.len()];
+    for (pile, depth) in unknown_positions(s) {
+        highest[pile] = highest[pile].max(Some(depth));
+    }
+    let exposed = |board: &Board| {
+        board
+            .playing_area
+            .iter()
+            .zip(&highest)
+            .any(|(stack, highest)| highest.is_some_and(|depth| stack.len() <= depth + 1))
+    };
+    if exposed(board) {
+        return 0;
+    }
+    let mut board = board.clone();
+    for (i, moov) in moves.iter().enumerate() {
+        board = board.play(moov.from, moov.to, rules).unwrap().0;
+        if exposed(&board) {
+            return i + 1;
+        }
+    }
+    moves.len()
+}
+
+// what sample_completions shuffles with. random unless --seed says otherwise, so a run that went
+// wrong can be run again the same way
+static SEED: OnceLock<u64> = OnceLock::new();
+
+pub fn set_seed(seed: u64) {
+    if SEED.set(seed).is_err() {
+        panic!("the seed's already been picked");
+    }
+}
+
+pub fn seed() -> u64 {
+    *SEED.get_or_init(rand::random)
+}
+
+// num_samples random ways the unknowns could be filled in, for when there are too many to try
+pub fn sample_completions(s: &str, num_samples: usize) -> Vec<Board> {
+    let num_unknowns = unknown_positions(s).len();
+    let mut candidates: Vec<Card> = candidates(s).into_iter().map(CardId::card).collect();
+    let mut rng = StdRng::seed_from_u64(seed());
+    (0..num_samples)
+        .map(|_| {
+            candidates.shuffle(&mut rng);
+            Board::parse(&fill(s, &candidates[..num_unknowns]))
+        })
+        .collect()
+}
+
+// for when we can't see enough to plan everything: the one move to play now that does best across
+// a sample of the boards the deal could be. every first move any of the samples' solutions starts
+// with gets tried on all the samples, and the winner is the one that leaves the fewest of them
+// unsolvable, and then the fewest moves on average. only moves of cards we can see count, so the
+// move plays the same whatever the unknowns are. returns the move along with one of the samples to
+// play it on
+pub fn best_first_move(
+    s: &str,
+    num_samples: usize,
+    solve: &dyn Fn(&Board) -> Option<Solution>,
+    rules: &Rules,
+) -> Option<(Board, Move)> {
+    let samples: Vec<Board> = sample_completions(s, num_samples)
+        .into_iter()
+        .map(|mut board| {
+            board.suck_readies_into_receptacles();
+            board
+        })
+        .collect();
+    let mut first_moves: Vec<Move> = vec![];
+    for board in &samples {
+        if let Some(moov) = solve(board).and_then(|solution| solution.moves.first().cloned()) {
+            if !first_moves.iter().any(|other| same_move(other, &moov)) {
+                first_moves.push(moov);
+            }
+        }
+    }
+    first_moves
+        .into_iter()
+        .filter_map(|moov| {
+            let mut num_unsolved = 0;
+            let mut total_moves = 0;
+            for board in &samples {
+                let after = board
+                    .play(moov.from, moov.to, rules)
+                    .filter(|(_, played)| played.card == moov.card);
+                // a different card on top, so the move depends on an unknown after all
+                let (after, _) = after?;
+                match solve(&after) {
+                    Some(solution) => total_moves += solution.moves.len() + 1,
+                    None => num_unsolved += 1,
+                }
+            }
+            let num_solved = (samples.len() - num_unsolved).max(1);
+            Some(((num_unsolved, total_moves / num_solved), moov))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, moov)| (samples[0].clone(), moov))
+}
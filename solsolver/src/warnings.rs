@@ -0,0 +1,197 @@
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::io::{stderr, Write};
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+// things that went a bit wrong but not wrong enough to stop. they go out as one JSON object per
+// line, away from stdout, so the orchestrator can log them without them getting mixed up with the
+// moves
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    // we had to fix something up in the board we were given
+    InputCorrected {
+        what: String,
+    },
+    // the solution came from a variant that prunes the search, so there might be a shorter one
+    PruningMayHaveRemovedOptimal {
+        num_prev_moves: usize,
+    },
+    // we couldn't solve the board (or it didn't play out like it should have), and this is one of
+    // the cards the screen reader was least sure of
+    LikelyMisread {
+        pile: usize,
+        depth: usize,
+        card: String,
+        confidence: f32,
+    },
+    // the game dealt a deal we've found unsolvable before (see seen.rs), so we're not trying it
+    // again
+    RepeatedDeal {
+        fingerprint: String,
+        outcome: String,
+    },
+    // a BoardError, just before we go down because of it
+    Internal {
+        what: String,
+        fingerprint: String,
+    },
+    // the config file changed under a session, but we couldn't use it, so we're carrying on with
+    // the settings we had
+    ConfigNotReloaded {
+        path: String,
+        why: String,
+    },
+    // we're getting close to the allocator's cap, and will fall over if we hit it
+    MemoryHigh {
+        allocated_bytes: usize,
+        limit_bytes: usize,
+    },
+    // the search went through every board and said there's no way to win, and the second opinion
+    // found one. one of them has a bug, and it's the worst kind: we'd have given up a winnable deal
+    UnsolvableDisputed {
+        fingerprint: String,
+    },
+    // with a closed set of fingerprints (see closed.rs), two different boards with the same one.
+    // the search would have skipped the second as already seen
+    HashCollision {
+        fingerprint: String,
+    },
+    // one of the variants panicked partway through a search. the rest carried on without it, and
+    // there's a bug report if they're on
+    VariantFailed {
+        variant: String,
+        fingerprint: String,
+        why: String,
+    },
+}
+
+impl Warning {
+    pub fn to_json(&self) -> String {
+        match self {
+            Warning::InputCorrected { what } => format!(
+                r#"{{"warning":"input_corrected","what":"{}"}}"#,
+                escape(what)
+            ),
+            Warning::PruningMayHaveRemovedOptimal { num_prev_moves } => format!(
+                r#"{{"warning":"pruning_may_have_removed_optimal","num_prev_moves":{}}}"#,
+                num_prev_moves
+            ),
+            Warning::LikelyMisread {
+                pile,
+                depth,
+                card,
+                confidence,
+            } => format!(
+                r#"{{"warning":"likely_misread","pile":{},"depth":{},"card":"{}","confidence":{}}}"#,
+                pile,
+                depth,
+                escape(card),
+                confidence
+            ),
+            Warning::RepeatedDeal {
+                fingerprint,
+                outcome,
+            } => format!(
+                r#"{{"warning":"repeated_deal","fingerprint":"{}","outcome":"{}"}}"#,
+                fingerprint, outcome
+            ),
+            Warning::Internal { what, fingerprint } => format!(
+                r#"{{"warning":"internal","what":"{}","fingerprint":"{}"}}"#,
+                escape(what),
+                fingerprint
+            ),
+            Warning::ConfigNotReloaded { path, why } => format!(
+                r#"{{"warning":"config_not_reloaded","path":"{}","why":"{}"}}"#,
+                escape(path),
+                escape(why)
+            ),
+            Warning::MemoryHigh {
+                allocated_bytes,
+                limit_bytes,
+            } => format!(
+                r#"{{"warning":"memory_high","allocated_bytes":{},"limit_bytes":{}}}"#,
+                allocated_bytes, limit_bytes
+            ),
+            Warning::UnsolvableDisputed { fingerprint } => format!(
+                r#"{{"warning":"unsolvable_disputed","fingerprint":"{}"}}"#,
+                fingerprint
+            ),
+            Warning::HashCollision { fingerprint } => format!(
+                r#"{{"warning":"hash_collision","fingerprint":"{}"}}"#,
+                fingerprint
+            ),
+            Warning::VariantFailed {
+                variant,
+                fingerprint,
+                why,
+            } => format!(
+                r#"{{"warning":"variant_failed","variant":"{}","fingerprint":"{}","why":"{}"}}"#,
+                escape(variant),
+                fingerprint,
+                escape(why)
+            ),
+        }
+    }
+}
+
+// for a string inside JSON quotes
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// where warnings go, stderr if nobody's set anything
+#[cfg(feature = "std")]
+static SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+#[cfg(feature = "std")]
+pub fn set_sink(sink: Box<dyn Write + Send>) {
+    *SINK.lock().unwrap() = Some(sink);
+}
+
+// the last few warnings, wherever they went, for bug reports (see bundle.rs)
+#[cfg(feature = "std")]
+const NUM_RECENT: usize = 100;
+#[cfg(feature = "std")]
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[cfg(feature = "std")]
+pub fn recent() -> Vec<String> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent.iter().cloned().collect()
+}
+
+#[cfg(feature = "std")]
+pub fn warn(warning: Warning) {
+    let line = warning.to_json();
+    {
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == NUM_RECENT {
+            recent.pop_front();
+        }
+        recent.push_back(line.clone());
+    }
+    let mut sink = SINK.lock().unwrap();
+    // a warning we couldn't write isn't worth falling over for
+    let _ = match sink.as_mut() {
+        Some(sink) => writeln!(sink, "{}", line).and_then(|_| sink.flush()),
+        None => writeln!(stderr(), "{}", line),
+    };
+}
+
+// without std there's nowhere to put them, so they're dropped
+#[cfg(not(feature = "std"))]
+pub fn warn(_warning: Warning) {}
@@ -0,0 +1,46 @@
+mod common;
+
+use solsolver::closed;
+use solsolver::rules::Rules;
+use solsolver::search::{Heuristic, Search, SearchOptions, SearchStatus, OLD};
+use std::sync::atomic::Ordering;
+
+// the closed set's settings are globals, so they're all in the one test to keep them from changing
+// under each other
+
+#[test]
+fn only_a_closed_set_that_can_tell_boards_apart_proves_a_deal_dead() {
+    // every pile's taken, the block's taken, and nothing goes on anything or home
+    let board = board! {
+        pile0: ["5_SWO"],
+        pile1: ["9_SWO"],
+        pile2: ["5_WAN"],
+        pile3: ["9_WAN"],
+        pile4: ["5_CUP"],
+        pile5: ["9_CUP"],
+        pile6: ["5_STA"],
+        pile7: ["9_STA"],
+        pile8: ["5_MAJ"],
+        pile9: ["9_MAJ"],
+        pile10: ["13_MAJ"],
+        block: "17_MAJ",
+    };
+    let proves_dead = || {
+        let mut search = Search::new(
+            &board,
+            SearchOptions {
+                num_prev_moves: OLD,
+                heuristic: Heuristic::CardsRemaining,
+                rules: Rules::default(),
+            },
+        );
+        assert_eq!(search.step_expansions(1000), SearchStatus::Exhausted);
+        search.proves_dead()
+    };
+
+    assert!(proves_dead());
+    closed::HASH_ONLY.store(true, Ordering::Relaxed);
+    assert!(!proves_dead());
+    closed::CHECK_COLLISIONS.store(true, Ordering::Relaxed);
+    assert!(proves_dead());
+}
@@ -0,0 +1,74 @@
+use solsolver::board::{Board, NUM_PLAYING_STACKS};
+use solsolver::encoding::{BLOCK, POSITION_SEPARATOR};
+
+// boards for tests without writing out all eleven piles:
+//
+//   board! { pile0: ["9_SWO", "4_CUP"], pile3: ["5_CUP"], block: "7_CUP", cup: "3_CUP" }
+//
+// each pile's listed bottom card first, like a deal, and the piles left out are empty. block is
+// the card on the block, and swo, wan, cup, sta, maj_lo and maj_hi are the top card of that
+// foundation (so everything under it is home too). the aces are home whatever, like in a deal
+//
+// it's the same board as Board::parse would make from the deal, readies not sucked in yet
+
+#[macro_export]
+macro_rules! board {
+    ($($key:ident : $value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut builder = $crate::common::BoardBuilder::default();
+        $(builder.set(stringify!($key), $crate::common::Cards::cards(&$value));)*
+        builder.build()
+    }};
+}
+
+// a value in board!: one card, or a pile of them
+pub trait Cards {
+    fn cards(&self) -> Vec<String>;
+}
+
+impl Cards for &str {
+    fn cards(&self) -> Vec<String> {
+        vec![self.to_string()]
+    }
+}
+
+impl<const N: usize> Cards for [&str; N] {
+    fn cards(&self) -> Vec<String> {
+        self.iter().map(|card| card.to_string()).collect()
+    }
+}
+
+#[derive(Default)]
+pub struct BoardBuilder {
+    piles: [Vec<String>; NUM_PLAYING_STACKS],
+    // BLOCK:CARD and FOUNDATION:CARD, see Board::serialize
+    positions: Vec<String>,
+}
+
+impl BoardBuilder {
+    pub fn set(&mut self, key: &str, cards: Vec<String>) {
+        if let Some(pile) = key.strip_prefix("pile") {
+            let pile: usize = pile.parse().unwrap_or_else(|_| panic!("no pile {:?}", key));
+            self.piles[pile] = cards;
+            return;
+        }
+        let [card] = &cards[..] else {
+            panic!("{} takes one card, not {:?}", key, cards)
+        };
+        let place = match key {
+            "block" => BLOCK.to_string(),
+            foundation => foundation.to_uppercase(),
+        };
+        self.positions
+            .push(format!("{}{}{}", place, POSITION_SEPARATOR, card));
+    }
+
+    pub fn build(&self) -> Board {
+        let lines = self
+            .piles
+            .iter()
+            .map(|pile| pile.join(","))
+            .chain(self.positions.iter().cloned());
+        Board::parse(&(lines.collect::<Vec<_>>().join("\n") + "\n"))
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use solsolver::card::Card;
+use solsolver::confidence::{self, Confidence};
+
+// a suspect card that's one of the readies gets sucked in before the solve, so it's not at the
+// depth the screen reader saw it at any more
+
+fn suspect(pile: usize, depth: usize, card: &str) -> Confidence {
+    Confidence {
+        pile,
+        depth,
+        card: Card::parse(card),
+        confidence: 0.3,
+    }
+}
+
+#[test]
+fn a_suspect_is_swapped_where_it_was_read() {
+    // 2_CUP's missing and 9_SWO's there twice
+    let board = board! {
+        pile0: ["9_SWO", "2_SWO"],
+        pile1: ["K_WAN", "9_SWO"],
+    };
+    let substitutions = confidence::substitutions(&board, &suspect(0, 1, "2_SWO"));
+    assert!(!substitutions.is_empty());
+    for (card, substituted) in substitutions {
+        assert_eq!(substituted.playing_area[0][1].card(), card);
+    }
+}
+
+#[test]
+fn a_suspect_that_was_sucked_in_has_nothing_to_swap() {
+    let mut board = board! {
+        pile0: ["9_SWO", "2_SWO"],
+        pile1: ["K_WAN", "9_SWO"],
+    };
+    board.suck_readies_into_receptacles();
+    assert_eq!(board.playing_area[0].len(), 1);
+    assert!(confidence::substitutions(&board, &suspect(0, 1, "2_SWO")).is_empty());
+}
@@ -0,0 +1,94 @@
+use solsolver::board::Board;
+use solsolver::corpus;
+use solsolver::rules::Rules;
+use solsolver::search::{
+    Heuristic, Search, SearchOptions, SearchStatus, NUM_PREV_MOVES_TO_CONSIDERS,
+};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// how many boards each variant gets to expand before we call it a failure
+const NODE_BUDGET: usize = 20_000;
+
+// how much longer than the recorded solution we'll put up with, in percent
+const LENGTH_SLACK_PERCENT: usize = 10;
+
+#[test]
+fn corpus_deals_still_solve() {
+    let entries =
+        corpus::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")).unwrap();
+    assert!(!entries.is_empty());
+
+    for entry in entries {
+        let board = Board::parse(&entry.deal);
+        let shortest = NUM_PREV_MOVES_TO_CONSIDERS
+            .iter()
+            .filter_map(|&num_prev_moves| {
+                let mut search = Search::new(
+                    &board,
+                    SearchOptions {
+                        num_prev_moves,
+                        heuristic: Heuristic::CardsRemaining,
+                        rules: Rules::default(),
+                    },
+                );
+                match search.step_expansions(NODE_BUDGET) {
+                    SearchStatus::Solved(solution) => Some(solution.moves.len()),
+                    _ => None,
+                }
+            })
+            .min();
+
+        let Some(shortest) = shortest else {
+            panic!("{} wasn't solved within {} nodes", entry.name, NODE_BUDGET);
+        };
+        assert!(
+            shortest * 100 <= entry.length * (100 + LENGTH_SLACK_PERCENT),
+            "{} took {} moves, recorded {}",
+            entry.name,
+            shortest,
+            entry.length
+        );
+    }
+}
+
+#[test]
+fn a_misread_deal_goes_in_as_it_was_solved() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let deal = fs::read_to_string(golden.join("majors.deal")).unwrap();
+    // the J_CUP read as a second K_CUP, and not very sure of it
+    let misread = deal.replace("11_MAJ,J_CUP", "11_MAJ,K_CUP:0.2");
+    let dir = env::temp_dir().join(format!("solsolver-corpus-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solsolver"))
+        .env_clear()
+        .args(["--threads", "1", "--try-substitutions", "--record-corpus"])
+        .arg(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(misread.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let num_moves = String::from_utf8(output.stdout).unwrap().lines().count();
+
+    let entries = corpus::load(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        Board::parse(&entries[0].deal).serialize(),
+        Board::parse(&deal).serialize()
+    );
+    assert_eq!(entries[0].length, num_moves);
+}
@@ -0,0 +1,53 @@
+use solsolver::board::Board;
+use solsolver::dedup::{self, Claim};
+use solsolver::rules::Rules;
+use solsolver::search::{self, Goal, Heuristic, Strategy, Variant, OLD};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+// solvers sharing a lock directory, played by this one process a claim at a time (a second claim
+// while we hold the lock would wait for ourselves)
+
+#[test]
+fn a_deal_is_solved_once_and_then_answered() {
+    let deal = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/separate_suits.deal"),
+    )
+    .unwrap();
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    let dir = env::temp_dir().join(format!("solsolver-dedup-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    // a solver that died holding the lock doesn't hold anyone up
+    fs::create_dir_all(&dir).unwrap();
+    let lock = dir.join(format!("{}.lock", board.fingerprint_hex()));
+    fs::write(&lock, "999999999\n").unwrap();
+    let Claim::Mine(mine) = dedup::claim(&dir, &board).unwrap() else {
+        panic!("the dead solver's deal wasn't taken over");
+    };
+    // and nor does one that gives up without an answer
+    drop(mine);
+    assert!(!lock.exists());
+    let Claim::Mine(mine) = dedup::claim(&dir, &board).unwrap() else {
+        panic!("the deal was answered without being solved");
+    };
+
+    let variant = Variant {
+        strategy: Strategy::Full,
+        num_prev_moves: OLD,
+        heuristic: Heuristic::CardsRemaining,
+    };
+    let solution =
+        search::solve(&board, &[variant], &Rules::default(), Goal::ClearBoard, 1).unwrap();
+    mine.finish(Some(&solution)).unwrap();
+
+    let claim = dedup::claim(&dir, &board).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let Claim::Answered(Some(answer)) = claim else {
+        panic!("the deal wasn't answered with its solution");
+    };
+    assert_eq!(answer.moves, solution.moves);
+}
@@ -0,0 +1,34 @@
+use solsolver::corpus;
+use solsolver::differential;
+use solsolver::rules::Rules;
+use std::env;
+use std::path::Path;
+
+const SEED: u64 = 1937;
+const NUM_BOARDS: usize = 200;
+const NUM_CARDS: usize = 12;
+
+// set DIFFERENTIAL_CORPUS to a directory to keep the boards the backends disagree on as corpus
+// entries, see corpus.rs
+#[test]
+fn backends_agree_on_small_boards() {
+    let disagreements = differential::run(SEED, NUM_BOARDS, NUM_CARDS, &Rules::default());
+    if let Ok(dir) = env::var("DIFFERENTIAL_CORPUS") {
+        for (i, disagreement) in disagreements.iter().enumerate() {
+            let entry = disagreement.corpus_entry(format!("differential-{}-{}", SEED, i));
+            corpus::append(Path::new(&dir), &entry).unwrap();
+        }
+    }
+    let boards: Vec<String> = disagreements
+        .iter()
+        .map(|disagreement| {
+            format!(
+                "a* {:?}, dfs {:?}:\n{}",
+                disagreement.a_star.solvable(),
+                disagreement.dfs.solvable(),
+                disagreement.board.serialize()
+            )
+        })
+        .collect();
+    assert!(boards.is_empty(), "{}", boards.join("\n"));
+}
@@ -0,0 +1,115 @@
+use solsolver::board::{Board, Foundation, Move, MoveLocation, Protocol, NUM_PLAYING_STACKS};
+use solsolver::card::{Card, CardId, CardSet, Suit};
+use solsolver::encoding::FIELD_SEPARATOR;
+use solsolver::rules::Rules;
+use std::path::Path;
+
+// everything we print, parsed back, should be what we started with. see encoding.rs
+
+// how far along a deal we walk, checking every move from every board on the way
+const WALK_LENGTH: usize = 60;
+
+const PROTOCOLS: [Protocol; 3] = [Protocol::V1, Protocol::V2, Protocol::V3];
+
+fn every_card() -> impl Iterator<Item = Card> {
+    CardSet::DECK.iter().map(CardId::card)
+}
+
+#[test]
+fn cards_round_trip() {
+    for card in every_card() {
+        assert_eq!(Card::parse(&card.serialize()), card);
+        assert_eq!(Card::parse_displayed(&card.to_string()), Some(card));
+        // the way --ascii draws it, without turning it on under the other tests' feet
+        if let Card::Minor { suit, value } = card {
+            let letters = format!("{}{}", value, suit.letter());
+            assert_eq!(Card::parse_displayed(&letters), Some(card));
+        }
+    }
+}
+
+#[test]
+fn locations_round_trip() {
+    let foundations = [Foundation::MajorLower, Foundation::MajorHigher]
+        .into_iter()
+        .chain(Suit::ALL.map(Foundation::Minor));
+    for foundation in foundations {
+        assert_eq!(Foundation::parse(&foundation.serialize()), Some(foundation));
+    }
+    let piles = (0..NUM_PLAYING_STACKS)
+        .flat_map(|pile| (0..30).map(move |depth| MoveLocation::PlayingArea { pile, depth }));
+    let others = [
+        MoveLocation::BlockMinorPiles,
+        MoveLocation::MajorLower,
+        MoveLocation::MajorHigher,
+    ]
+    .into_iter()
+    .chain(Suit::ALL.map(MoveLocation::MinorFoundation));
+    for location in piles.chain(others) {
+        assert_eq!(MoveLocation::parse(&location.serialize()), location);
+    }
+}
+
+// what parse can get back from a move in each protocol, see Move::parse
+fn as_sent(moov: &Move, protocol: Protocol) -> Move {
+    let mut moov = moov.clone();
+    match protocol {
+        Protocol::V1 => moov.sucks.clear(),
+        Protocol::V2 => moov.sucks.iter_mut().for_each(|suck| suck.depth = 0),
+        Protocol::V3 => {}
+    }
+    moov
+}
+
+fn check_moves(board: &Board, rules: &Rules) -> Vec<(Board, Move)> {
+    let next_boards = board.next_boards(rules);
+    for (_, moov) in &next_boards {
+        for protocol in PROTOCOLS {
+            let line = moov.serialize(protocol);
+            assert_eq!(
+                Move::parse(&line, protocol),
+                as_sent(moov, protocol),
+                "{}",
+                line
+            );
+            assert_eq!(
+                Move::parse_locations(&line),
+                (moov.from, moov.to),
+                "{}",
+                line
+            );
+            let fields = line.splitn(protocol.num_fields(), FIELD_SEPARATOR).count();
+            assert_eq!(fields, protocol.num_fields(), "{}", line);
+        }
+    }
+    next_boards
+}
+
+#[test]
+fn boards_and_moves_round_trip() {
+    let deal = std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/solvable.deal"),
+    )
+    .unwrap();
+    // with foundation moves, so there are moves onto the foundations to check as well
+    let rules = Rules {
+        explicit_foundation_moves: true,
+        ..Rules::default()
+    };
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    for step in 0..WALK_LENGTH {
+        assert_eq!(Board::parse(&board.serialize()), board);
+        let next_boards = check_moves(&board, &rules);
+        // the move that gets the most home, so the walk gets somewhere
+        let Some((next, _)) = next_boards
+            .into_iter()
+            .enumerate()
+            .max_by_key(|(i, (_, moov))| (moov.num_cards_home(), (i + step) % 3))
+            .map(|(_, next)| next)
+        else {
+            break;
+        };
+        board = next;
+    }
+}
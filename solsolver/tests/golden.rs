@@ -0,0 +1,81 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// the exact bytes the solver writes to stdout for a few tiny deals, in every protocol, checked
+// against tests/golden/NAME.v1 (and .v2, .v3, and .interactive for a DEAL through interactive).
+// the driver parses these byte for byte, so a change here has to be one we meant. when it is,
+// UPDATE_GOLDEN=1 cargo test --test golden writes the new ones, and the diff goes in with it
+//
+// the deals are small enough that there's only one sensible solution, and we solve on one thread
+// with nothing from the environment, so the output's the same every time
+
+const UPDATE: &str = "UPDATE_GOLDEN";
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+// stdout from running the solver with args and stdin
+fn solsolver(args: &[&str], stdin: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solsolver"))
+        .env_clear()
+        .current_dir(golden_dir())
+        .args(["--threads", "1"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "solsolver {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn check(path: &Path, actual: &str) {
+    if std::env::var_os(UPDATE).is_some() {
+        fs::write(path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("no {}, {}=1 to write it", path.display(), UPDATE));
+    assert_eq!(
+        actual,
+        expected,
+        "{} has changed, {}=1 to write the new one if it's meant to",
+        path.display(),
+        UPDATE
+    );
+}
+
+#[test]
+fn protocol_output_is_unchanged() {
+    let mut deals: Vec<PathBuf> = fs::read_dir(golden_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "deal")
+        })
+        .collect();
+    deals.sort();
+    assert!(!deals.is_empty());
+    for deal_path in deals {
+        let deal = fs::read_to_string(&deal_path).unwrap();
+        for protocol in ["1", "2", "3"] {
+            let stdout = solsolver(&["--protocol", protocol], &deal);
+            check(&deal_path.with_extension(format!("v{}", protocol)), &stdout);
+        }
+        let conversation = format!("DEAL\n{}END\nQUIT\n", deal);
+        let stdout = solsolver(&["--protocol", "3", "interactive"], &conversation);
+        check(&deal_path.with_extension("interactive"), &stdout);
+    }
+}
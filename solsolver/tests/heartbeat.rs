@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+// --heartbeat's THINKING lines, from the solver itself since they come from a thread of its own.
+// the driver drops them wherever they turn up, which only works if they're always a line of their
+// own
+
+const DEALS: usize = 5;
+
+#[test]
+fn thinking_only_ever_comes_between_whole_lines() {
+    let deal =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/majors.deal"))
+            .unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solsolver"))
+        .env_clear()
+        .args(["--threads", "1", "--unbuffered", "--heartbeat", "1"])
+        .arg("interactive")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    // a deal every half a second, so there are heartbeats among the moves
+    for _ in 0..DEALS {
+        write!(stdin, "DEAL\n{}END\n", deal).unwrap();
+        stdin.flush().unwrap();
+        thread::sleep(Duration::from_millis(500));
+    }
+    writeln!(stdin, "QUIT").unwrap();
+    let mut said = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut said)
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    assert!(said.ends_with('\n'));
+    let lines: Vec<&str> = said.lines().collect();
+    assert!(lines.contains(&"THINKING"), "{}", said);
+    for line in &lines {
+        assert!(
+            *line == "THINKING" || !line.contains("THINKING"),
+            "{:?} in {}",
+            line,
+            said
+        );
+    }
+    // and without them it's every deal's moves, whole
+    let moves: Vec<&str> = lines
+        .into_iter()
+        .filter(|line| *line != "THINKING")
+        .collect();
+    let one_deal = moves.len() / DEALS;
+    assert_eq!(moves.len(), one_deal * DEALS);
+    assert_eq!(moves[one_deal - 1], "DONE");
+    assert!(moves
+        .chunks(one_deal)
+        .all(|chunk| chunk == &moves[..one_deal]));
+}
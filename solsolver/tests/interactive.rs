@@ -0,0 +1,139 @@
+use solsolver::board::{Board, Protocol, Solution};
+use solsolver::interactive::{self, Pacing};
+use solsolver::rules::Rules;
+use std::io::Cursor;
+
+// conversations with the driver, with a stand-in for the solver so that the plans are up to the
+// test. see interactive.rs for the protocol
+
+// three unknowns under cards that can't go anywhere yet, which could be the cups from 6 up (but
+// the jack) or the 8 of majors
+const DEAL: &str = "?,9_MAJ\n12_MAJ,Q_CUP,10_MAJ\n?,11_MAJ\n?,J_CUP\n\n\n\n\n\n\n\n\
+                    SWO:K_SWO\nCUP:5_CUP\nWAN:K_WAN\nSTA:K_STA\nMAJ_LO:7_MAJ\nMAJ_HI:13_MAJ\n";
+
+// the deal with the unknowns filled in
+fn known() -> String {
+    DEAL.replacen('?', "6_CUP", 1)
+        .replacen('?', "7_CUP", 1)
+        .replacen('?', "8_CUP", 1)
+}
+
+// whatever the board, there's nothing to do
+fn nothing_to_do(_: &Board) -> Option<Solution> {
+    Some(Solution { moves: vec![] })
+}
+
+// whatever the board, the first move from it and then the first from there
+fn two_moves(board: &Board) -> Option<Solution> {
+    let (next, first) = board.next_boards(&Rules::default()).swap_remove(0);
+    let (_, second) = next.next_boards(&Rules::default()).swap_remove(0);
+    Some(Solution {
+        moves: vec![first, second],
+    })
+}
+
+// everything we said, given everything the driver says
+fn talk(driver: &str, solve: &dyn Fn(&Board) -> Option<Solution>, pacing: Pacing) -> String {
+    let mut output = vec![];
+    interactive::run(
+        &mut Cursor::new(driver),
+        &mut output,
+        solve,
+        &Rules::default(),
+        Protocol::V3,
+        pacing,
+    )
+    .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn the_cards_the_driver_reads_are_filled_in() {
+    let driver = format!(
+        "DEAL\n{}END\nCARD 6_CUP\nCARD 7_CUP\nCARD 8_CUP\nQUIT\n",
+        DEAL
+    );
+    let said = talk(&driver, &nothing_to_do, Pacing::All);
+    assert_eq!(said, "PROBE 0:0\nPROBE 2:0\nPROBE 3:0\nDONE\n");
+}
+
+#[test]
+fn a_card_the_driver_couldnt_read_isnt_asked_about_again() {
+    let driver = format!("DEAL\n{}END\nCARD ?\nCARD 6_CUP\nCARD ?\nQUIT\n", DEAL);
+    let said = talk(&driver, &nothing_to_do, Pacing::All);
+    let probes: Vec<&str> = said
+        .lines()
+        .filter(|line| line.starts_with("PROBE"))
+        .collect();
+    assert_eq!(probes, ["PROBE 0:0", "PROBE 2:0", "PROBE 3:0"]);
+    assert!(said.ends_with("REREAD 0:0,3:0\n"), "{}", said);
+}
+
+#[test]
+fn a_fail_with_cards_the_driver_cant_read_is_probed_like_a_deal() {
+    let known = known();
+    let mut known_board = Board::parse(&known);
+    known_board.suck_readies_into_receptacles();
+    // one move from the deal as it was, and nothing to do from anywhere else
+    let solve = |board: &Board| {
+        let moves = if *board == known_board {
+            vec![board.next_boards(&Rules::default())[0].1.clone()]
+        } else {
+            vec![]
+        };
+        Some(Solution { moves })
+    };
+    let driver = format!(
+        "DEAL\n{}END\nFAIL\n{}END\nCARD ?\nCARD ?\nCARD ?\nQUIT\n",
+        known, DEAL
+    );
+    let said = talk(&driver, &solve, Pacing::Ack);
+    let lines: Vec<&str> = said.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", said);
+    assert!(lines[0].starts_with("MOVE "));
+    assert_eq!(
+        lines[1..],
+        ["PROBE 0:0", "PROBE 2:0", "PROBE 3:0", "REREAD 0:0,2:0,3:0"]
+    );
+}
+
+#[test]
+fn with_acks_each_move_waits_for_an_ok() {
+    let moves = |said: &str| {
+        said.lines()
+            .filter(|line| line.starts_with("MOVE "))
+            .count()
+    };
+
+    // the driver goes away after the first OK
+    let said = talk(
+        &format!("DEAL\n{}END\nOK\n", known()),
+        &two_moves,
+        Pacing::Ack,
+    );
+    assert_eq!(moves(&said), 2);
+    assert!(!said.contains("DONE"), "{}", said);
+
+    let said = talk(
+        &format!("DEAL\n{}END\nOK\nOK\nQUIT\n", known()),
+        &two_moves,
+        Pacing::Ack,
+    );
+    assert_eq!(moves(&said), 2);
+    assert!(said.ends_with("DONE\n"), "{}", said);
+}
+
+#[test]
+fn a_chunk_ends_with_where_it_leaves_the_board_and_stop_drops_the_rest() {
+    let mut board = Board::parse(&known());
+    board.suck_readies_into_receptacles();
+    let first = two_moves(&board).unwrap().moves.swap_remove(0);
+    let (after, _) = board.play(first.from, first.to, &Rules::default()).unwrap();
+
+    let driver = format!("DEAL\n{}END\nSTOP\nQUIT\n", known());
+    let said = talk(&driver, &two_moves, Pacing::Chunks(1));
+    let lines: Vec<&str> = said.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", said);
+    assert!(lines[0].starts_with("MOVE "));
+    assert_eq!(lines[1], format!("CHUNK {}", after.fingerprint_hex()));
+}
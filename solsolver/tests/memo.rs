@@ -0,0 +1,44 @@
+use solsolver::board::Board;
+use solsolver::memo::{self, Known};
+use solsolver::rules::Rules;
+use solsolver::search::{self, Goal, Heuristic, Strategy, Variant, OLD};
+use std::fs;
+use std::path::Path;
+
+// the memo's a global, so it's all in the one test to keep it from changing under another
+
+// every pile's taken, the block's taken, and nothing goes on anything or home
+const STUCK: &str =
+    "5_SWO\n9_SWO\n5_WAN\n9_WAN\n5_CUP\n9_CUP\n5_STA\n9_STA\n5_MAJ\n9_MAJ\n13_MAJ\nBLOCK:17_MAJ\n";
+
+#[test]
+fn a_board_along_a_solution_has_the_rest_of_it_until_its_pushed_out() {
+    let deal = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/separate_suits.deal"),
+    )
+    .unwrap();
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    let rules = Rules::default();
+    let variant = Variant {
+        strategy: Strategy::Full,
+        num_prev_moves: OLD,
+        heuristic: Heuristic::CardsRemaining,
+    };
+
+    memo::set_capacity(100);
+    let solution = search::solve(&board, &[variant], &rules, Goal::ClearBoard, 1).unwrap();
+    let first = &solution.moves[0];
+    let (after, _) = board.play(first.from, first.to, &rules).unwrap();
+    assert_eq!(
+        memo::lookup(&after, &rules),
+        Some(Known::Solved(solution.moves[1..].to_vec()))
+    );
+
+    // with room for one board, the latest is the only one left
+    memo::set_capacity(1);
+    let stuck = Board::parse(STUCK);
+    memo::remember_dead(&stuck, &rules);
+    assert!(memo::is_dead(&stuck, &rules));
+    assert_eq!(memo::lookup(&after, &rules), None);
+}
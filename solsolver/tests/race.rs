@@ -0,0 +1,94 @@
+use solsolver::board::Board;
+use solsolver::race::{self, Event};
+use solsolver::rules::Rules;
+use solsolver::search::{
+    self, Goal, Heuristic, Strategy, Variant, NUM_PREV_MOVES_TO_CONSIDERS, OLD,
+};
+use std::fs;
+use std::path::Path;
+
+// the portfolio's variants racing on a deal small enough that every one of them solves it, and one
+// that some of them take a lot longer over
+
+fn board(path: &str) -> Board {
+    let deal = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap();
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    board
+}
+
+fn portfolio() -> Vec<Variant> {
+    NUM_PREV_MOVES_TO_CONSIDERS
+        .into_iter()
+        .map(|num_prev_moves| Variant {
+            strategy: Strategy::Full,
+            num_prev_moves,
+            heuristic: Heuristic::CardsRemaining,
+        })
+        .collect()
+}
+
+// the race's threads other than the first that are around right now. the race names them
+// solsolver-0 and up
+#[cfg(target_os = "linux")]
+fn num_extra_threads() -> usize {
+    fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|task| fs::read_to_string(task.unwrap().path().join("comm")).ok())
+        .filter(|name| name.starts_with("solsolver-") && name.trim_end() != "solsolver-0")
+        .count()
+}
+
+#[test]
+fn one_thread_runs_the_variants_one_after_another() {
+    let portfolio = portfolio();
+    let mut finished = vec![];
+    let outcomes = race::race(
+        &board("tests/golden/majors.deal"),
+        &portfolio,
+        &Rules::default(),
+        Goal::ClearBoard,
+        1,
+        &mut |event, _| {
+            if let Event::Finished { index, .. } = event {
+                finished.push(*index);
+            }
+            #[cfg(target_os = "linux")]
+            assert_eq!(num_extra_threads(), 0);
+        },
+    );
+    assert_eq!(finished, (0..portfolio.len()).collect::<Vec<_>>());
+    assert!(outcomes
+        .iter()
+        .all(|(_, outcome)| matches!(outcome, Ok(Some(_)))));
+}
+
+#[test]
+fn once_the_race_is_called_off_the_rest_come_back_with_nothing() {
+    let board = board("tests/corpus/solvable.deal");
+    // the pruned search solves it in no time, the unpruned one would be at it for minutes
+    let portfolio = [10, OLD].map(|num_prev_moves| Variant {
+        strategy: Strategy::Full,
+        num_prev_moves,
+        heuristic: Heuristic::CardsRemaining,
+    });
+    let outcomes = race::race(
+        &board,
+        &portfolio,
+        &Rules::default(),
+        Goal::ClearBoard,
+        1,
+        &mut |event, cancels| {
+            if let Event::Finished {
+                solution: Some(_), ..
+            } = event
+            {
+                cancels.all.cancel();
+            }
+        },
+    );
+    assert!(matches!(outcomes[0], (_, Ok(Some(_)))));
+    assert!(matches!(outcomes[1], (_, Ok(None))));
+    // giving up isn't finding there's no way through
+    assert!(!search::proven_dead(&board));
+}
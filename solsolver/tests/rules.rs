@@ -0,0 +1,207 @@
+mod common;
+
+use solsolver::board::Board;
+use solsolver::rules::Rules;
+
+// what the move generator will and won't let you do, a board at a time. see rules_doc.rs for the
+// rules in words. each move is FROM-TO as it is on the wire, e.g. 0:1-3:0 for the top card of a
+// two card pile 0 onto an empty pile 3
+//
+// the piles have a king or queen under the card that's being moved, since next_boards leaves out
+// some moves from a pile of one card that don't get anywhere (see Board::next_boards)
+
+struct Case {
+    name: &'static str,
+    board: Board,
+    rules: Rules,
+    allowed: &'static [&'static str],
+    not_allowed: &'static [&'static str],
+}
+
+fn with(rules: impl FnOnce(&mut Rules)) -> Rules {
+    let mut with = Rules::default();
+    rules(&mut with);
+    with
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case {
+            name: "a minor goes on the next one up or down in its suit",
+            board: board! {
+                pile0: ["K_SWO", "4_CUP"],
+                pile1: ["K_WAN", "5_CUP"],
+                pile2: ["K_STA", "3_CUP"],
+            },
+            rules: Rules::default(),
+            allowed: &["0:1-1:2", "0:1-2:2", "1:1-0:2", "2:1-0:2"],
+            not_allowed: &["1:1-2:2", "2:1-1:2"],
+        },
+        Case {
+            name: "a minor doesn't go on another suit, or a major",
+            board: board! {
+                pile0: ["K_SWO", "4_CUP"],
+                pile1: ["K_WAN", "5_SWO"],
+                pile2: ["K_STA", "5_MAJ"],
+                pile3: ["Q_SWO", "4_MAJ"],
+            },
+            rules: Rules::default(),
+            allowed: &["0:1-4:0"],
+            not_allowed: &["0:1-1:2", "0:1-2:2", "0:1-3:2", "2:1-0:2", "3:1-0:2"],
+        },
+        Case {
+            name: "a major goes on the next one up or down",
+            board: board! {
+                pile0: ["K_SWO", "10_MAJ"],
+                pile1: ["K_WAN", "11_MAJ"],
+                pile2: ["K_STA", "12_MAJ"],
+            },
+            rules: Rules::default(),
+            allowed: &["0:1-1:2", "1:1-0:2", "1:1-2:2", "2:1-1:2"],
+            not_allowed: &["0:1-2:2", "2:1-0:2"],
+        },
+        Case {
+            name: "only the top card moves",
+            board: board! { pile0: ["4_CUP", "9_SWO"], pile1: ["K_SWO", "5_CUP"] },
+            rules: Rules::default(),
+            allowed: &["0:1-2:0"],
+            not_allowed: &["0:0-1:2", "0:0-2:0"],
+        },
+        Case {
+            name: "anything goes in an empty pile",
+            board: board! { pile0: ["K_SWO", "K_STA"], pile1: ["K_WAN", "10_MAJ"] },
+            rules: Rules::default(),
+            allowed: &["0:1-2:0", "0:1-10:0", "1:1-2:0"],
+            not_allowed: &[],
+        },
+        Case {
+            name: "anything goes on the block",
+            board: board! { pile0: ["K_SWO", "K_STA"], pile1: ["K_WAN", "10_MAJ"] },
+            rules: Rules::default(),
+            allowed: &["0:1-BLOCK", "1:1-BLOCK"],
+            not_allowed: &[],
+        },
+        Case {
+            name: "nothing goes on the block with no_block",
+            board: board! { pile0: ["K_SWO", "K_STA"], pile1: ["K_WAN", "10_MAJ"] },
+            rules: with(|rules| rules.no_block = true),
+            allowed: &["0:1-2:0"],
+            not_allowed: &["0:1-BLOCK", "1:1-BLOCK"],
+        },
+        Case {
+            name: "nothing goes on the block when it's taken, but its card can come off",
+            board: board! {
+                pile0: ["K_SWO", "K_STA"],
+                pile1: ["K_WAN", "8_CUP"],
+                block: "7_CUP",
+            },
+            rules: Rules::default(),
+            allowed: &["BLOCK-1:2", "BLOCK-2:0"],
+            not_allowed: &["0:1-BLOCK", "BLOCK-0:2"],
+        },
+        Case {
+            name: "a card that's already on the block can come off with no_block",
+            board: board! { pile0: ["K_SWO", "K_STA"], block: "7_CUP" },
+            rules: with(|rules| rules.no_block = true),
+            allowed: &["BLOCK-1:0"],
+            not_allowed: &[],
+        },
+        Case {
+            name: "cards only go home by being sucked",
+            board: board! { pile0: ["K_SWO", "K_STA"], block: "2_CUP" },
+            rules: Rules::default(),
+            allowed: &["BLOCK-1:0"],
+            not_allowed: &["BLOCK-CUP"],
+        },
+        Case {
+            name: "with explicit_foundation_moves the card on the block goes home by hand",
+            board: board! { pile0: ["K_SWO", "K_STA"], block: "2_CUP" },
+            rules: with(|rules| rules.explicit_foundation_moves = true),
+            allowed: &["BLOCK-CUP"],
+            not_allowed: &[],
+        },
+        Case {
+            name: "with optional_major_sucks a major goes home by hand, but a minor doesn't",
+            board: board! {
+                pile0: ["K_SWO", "8_MAJ"],
+                pile1: ["K_WAN", "3_CUP"],
+                maj_lo: "7_MAJ",
+                cup: "2_CUP",
+            },
+            rules: with(|rules| rules.optional_major_sucks = true),
+            allowed: &["0:1-MAJ_LO", "0:1-2:0"],
+            not_allowed: &["1:1-CUP"],
+        },
+    ]
+}
+
+fn moves(board: &Board, rules: &Rules) -> Vec<String> {
+    board
+        .next_boards(rules)
+        .into_iter()
+        .map(|(_, moov)| format!("{}-{}", moov.from.serialize(), moov.to.serialize()))
+        .collect()
+}
+
+#[test]
+fn move_generation() {
+    for case in cases() {
+        let moves = moves(&case.board, &case.rules);
+        for allowed in case.allowed {
+            assert!(
+                moves.iter().any(|moov| moov == allowed),
+                "{}: {} isn't in {:?}",
+                case.name,
+                allowed,
+                moves
+            );
+        }
+        for not_allowed in case.not_allowed {
+            assert!(
+                !moves.iter().any(|moov| moov == not_allowed),
+                "{}: {} is in {:?}",
+                case.name,
+                not_allowed,
+                moves
+            );
+        }
+    }
+}
+
+#[test]
+fn with_optional_major_sucks_only_the_minors_go_by_themselves() {
+    // moving the 5_SWO off uncovers the 3_CUP, which goes home and uncovers the 8_MAJ
+    let board = board! {
+        pile0: ["8_MAJ", "3_CUP", "5_SWO"],
+        pile1: ["K_WAN", "6_SWO"],
+        maj_lo: "7_MAJ",
+        cup: "2_CUP",
+    };
+    let sucked = |rules: &Rules| {
+        let (next, moov) = board.next_boards(rules).into_iter().find(|(_, moov)| {
+            format!("{}-{}", moov.from.serialize(), moov.to.serialize()) == "0:2-1:2"
+        })?;
+        let sucks = moov.sucks.iter().map(|suck| suck.card.serialize());
+        Some((sucks.collect::<Vec<_>>(), next.playing_area[0].len()))
+    };
+    assert_eq!(
+        sucked(&Rules::default()),
+        Some((vec!["3_CUP".to_string(), "8_MAJ".to_string()], 0))
+    );
+    assert_eq!(
+        sucked(&with(|rules| rules.optional_major_sucks = true)),
+        Some((vec!["3_CUP".to_string()], 1))
+    );
+}
+
+#[test]
+fn board_macro_is_the_deal() {
+    let board = board! {
+        pile0: ["9_SWO", "4_CUP"],
+        pile3: ["5_CUP"],
+        block: "7_CUP",
+        cup: "3_CUP",
+    };
+    let deal = "9_SWO,4_CUP\n\n\n5_CUP\n\n\n\n\n\n\n\nBLOCK:7_CUP\nCUP:3_CUP\n";
+    assert_eq!(board, Board::parse(deal));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use solsolver::rules::Rules;
+use solsolver::search::{
+    self, Goal, Heuristic, Search, SearchOptions, SearchStatus, Strategy, Variant, OLD,
+};
+
+// how many boards Search gets to expand at a time, so it's stopped and started after every one
+const SLICE: usize = 1;
+
+// the same search as solve's, a slice at a time. it's the unpruned one after the shortest solution,
+// since that's the one whose length doesn't depend on the order the boards come in (or on which
+// dead ends the searches before it found, see nogoods.rs). the relaxed lower bound is slow, so
+// it's a board near the end
+#[test]
+fn a_search_run_a_slice_at_a_time_finds_what_solve_does() {
+    // six moves from the end of tests/corpus/solvable.deal
+    let board = board! {
+        pile0: ["Q_SWO", "J_SWO", "10_SWO"],
+        pile1: ["Q_CUP"],
+        pile3: ["5_SWO", "6_SWO"],
+        pile4: ["K_SWO"],
+        pile6: ["K_CUP", "7_CUP", "8_CUP", "9_CUP"],
+        pile7: ["10_CUP", "J_CUP"],
+        pile8: ["4_SWO", "K_WAN", "8_SWO", "9_SWO"],
+        pile10: ["7_SWO"],
+        maj_lo: "11_MAJ",
+        maj_hi: "12_MAJ",
+        swo: "3_SWO",
+        wan: "Q_WAN",
+        cup: "6_CUP",
+        sta: "K_STA",
+    };
+    let rules = Rules::default();
+    let variant = Variant {
+        strategy: Strategy::Full,
+        num_prev_moves: OLD,
+        heuristic: Heuristic::RelaxedLowerBound,
+    };
+    let solved = search::solve(&board, &[variant], &rules, Goal::ClearBoard, 1).unwrap();
+
+    let mut search = Search::new(
+        &board,
+        SearchOptions {
+            num_prev_moves: OLD,
+            heuristic: Heuristic::RelaxedLowerBound,
+            rules,
+        },
+    );
+    let mut num_slices = 0;
+    let searched = loop {
+        num_slices += 1;
+        match search.step_expansions(SLICE) {
+            SearchStatus::InProgress => {}
+            SearchStatus::Solved(solution) => break solution,
+            SearchStatus::Exhausted => panic!("no solution after {} slices", num_slices),
+        }
+    };
+    assert!(num_slices > 1);
+    assert_eq!(searched.moves.len(), solved.moves.len());
+}
@@ -0,0 +1,113 @@
+use solsolver::board::Protocol;
+use solsolver::interactive::Pacing;
+use solsolver::rules::Rules;
+use solsolver::search::Heuristic;
+use solsolver::seen::SeenDeals;
+use solsolver::session::{self, SessionOptions, Stats};
+use std::env;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::process;
+
+// whole sessions with the driver, see session.rs for the protocol. the deals are tiny, so every
+// search settles them well inside the budget
+
+fn deal(name: &str) -> String {
+    fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name),
+    )
+    .unwrap()
+}
+
+// every pile's taken, the block's taken, and nothing goes on anything or home
+const STUCK: &str =
+    "5_SWO\n9_SWO\n5_WAN\n9_WAN\n5_CUP\n9_CUP\n5_STA\n9_STA\n5_MAJ\n9_MAJ\n13_MAJ\nBLOCK:17_MAJ\n";
+
+fn options(pacing: Pacing) -> SessionOptions {
+    SessionOptions {
+        heuristic: Heuristic::CardsRemaining,
+        rules: Rules::default(),
+        protocol: Protocol::V3,
+        budget_ms: 5_000,
+        pacing,
+        ponder: false,
+        adaptive: false,
+        hourly_budget_ms: None,
+        verify_unsolvable: None,
+    }
+}
+
+// everything we said given everything the driver says, and the stats at the end
+fn talk(
+    driver: &str,
+    options: &SessionOptions,
+    seen: Option<&mut SeenDeals>,
+) -> io::Result<(String, Stats)> {
+    let mut output = vec![];
+    let stats = session::run(
+        &mut Cursor::new(driver),
+        &mut output,
+        options,
+        seen,
+        &mut || None,
+        &mut |_, _| {},
+    )?;
+    Ok((String::from_utf8(output).unwrap(), stats))
+}
+
+#[test]
+fn a_fail_with_cards_the_driver_cant_read_is_turned_away() {
+    let driver = format!(
+        "DEAL\n{}END\nFAIL\n?,9_MAJ\n12_MAJ,Q_CUP,10_MAJ\nEND\nQUIT\n",
+        deal("majors.deal")
+    );
+    let error = talk(&driver, &options(Pacing::Ack), None).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn each_game_ends_with_the_stats_so_far() {
+    let driver = format!(
+        "DEAL\n{}END\nDEAL\n{}END\nQUIT\n",
+        deal("majors.deal"),
+        STUCK
+    );
+    let (said, stats) = talk(&driver, &options(Pacing::All), None).unwrap();
+    let lines: Vec<&str> = said.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", said);
+    assert!(lines[0].starts_with("MOVE "));
+    assert_eq!(
+        lines[1..],
+        [
+            "DONE",
+            "STATS games=1 solved=1 unsolvable=0 timed_out=0 repeats=0 moves=1",
+            "NEW_GAME unsolvable",
+            "STATS games=2 solved=1 unsolvable=1 timed_out=0 repeats=0 moves=1",
+        ]
+    );
+    assert_eq!(stats.num_games, 2);
+}
+
+#[test]
+fn a_deal_found_unsolvable_in_an_earlier_session_isnt_tried_again() {
+    let path = env::temp_dir().join(format!("solsolver-seen-{}", process::id()));
+    let _ = fs::remove_file(&path);
+    let driver = format!("DEAL\n{}END\nQUIT\n", STUCK);
+
+    let mut seen = SeenDeals::open(&path).unwrap();
+    let (said, _) = talk(&driver, &options(Pacing::All), Some(&mut seen)).unwrap();
+    assert!(said.starts_with("NEW_GAME unsolvable\n"), "{}", said);
+    drop(seen);
+
+    let mut seen = SeenDeals::open(&path).unwrap();
+    let (said, stats) = talk(&driver, &options(Pacing::All), Some(&mut seen)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        said,
+        "NEW_GAME seen_before\nSTATS games=1 solved=0 unsolvable=0 timed_out=0 repeats=1 moves=0\n"
+    );
+    assert_eq!(stats.num_repeats, 1);
+}
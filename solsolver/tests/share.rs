@@ -0,0 +1,39 @@
+use solsolver::board::Board;
+use solsolver::rules::Rules;
+use solsolver::search::{self, Goal, Heuristic, Strategy, Variant, OLD};
+use solsolver::share::ShareCode;
+use std::fs;
+use std::path::Path;
+
+fn deal(name: &str) -> Board {
+    let deal = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name),
+    )
+    .unwrap();
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    board
+}
+
+#[test]
+fn a_share_code_plays_back_the_solution_on_its_own_deal_only() {
+    let board = deal("separate_suits.deal");
+    // rules that don't fit in the first byte, so they have to come through the second
+    let rules = Rules {
+        optional_major_sucks: true,
+        ..Rules::default()
+    };
+    let variant = Variant {
+        strategy: Strategy::Full,
+        num_prev_moves: OLD,
+        heuristic: Heuristic::CardsRemaining,
+    };
+    let solution = search::solve(&board, &[variant], &rules, Goal::ClearBoard, 1).unwrap();
+
+    let code = ShareCode::parse(&ShareCode::new(&board, &solution, &rules).serialize());
+    assert_eq!(code.rules, rules);
+    assert_eq!(code.replay(&board).unwrap().moves, solution.moves);
+    assert_eq!(code.replay(&deal("majors.deal")), None);
+}
@@ -0,0 +1,41 @@
+use solsolver::board::Board;
+use solsolver::rules::Rules;
+use solsolver::tablebase::Tablebase;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+// a tablebase that's never installed, so it's only ever asked about the boards we give it
+
+#[test]
+fn a_finish_is_worked_out_once_and_kept_from_run_to_run() {
+    let deal = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/separate_suits.deal"),
+    )
+    .unwrap();
+    let mut board = Board::parse(&deal);
+    board.suck_readies_into_receptacles();
+    let rules = Rules::default();
+    let path = env::temp_dir().join(format!("solsolver-tablebase-{}", process::id()));
+
+    let tablebase = Tablebase::new(board.num_cards_remaining(), &rules);
+    assert!(tablebase.covers(&board, &rules));
+    let finish = tablebase.finish(&board).unwrap();
+    let mut cleared = board.clone();
+    for moov in &finish {
+        cleared = cleared.play(moov.from, moov.to, &rules).unwrap().0;
+    }
+    assert!(cleared.is_done());
+    tablebase.save(&path).unwrap();
+
+    let loaded = Tablebase::load(&path, board.num_cards_remaining(), &rules).unwrap();
+    assert_eq!(loaded.len(), tablebase.len());
+    assert_eq!(loaded.finish(&board), Some(finish));
+
+    // from before there was a switch for optional_major_sucks
+    fs::write(&path, "rules 0000\n").unwrap();
+    let old = Tablebase::load(&path, board.num_cards_remaining(), &rules);
+    fs::remove_file(&path).unwrap();
+    assert!(old.unwrap().is_empty());
+}
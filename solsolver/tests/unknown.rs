@@ -0,0 +1,40 @@
+use solsolver::board::{Board, Solution};
+use solsolver::rules::Rules;
+use solsolver::unknown;
+
+// planning a deal with cards we can't see yet, with a stand-in for the solver so that which of the
+// ways it could be can be won is up to the test
+
+// two unknowns under cards that can't go anywhere yet, which could be the cups from 6 up or the 8
+// of majors
+const DEAL: &str = "?,9_MAJ\n12_MAJ,Q_CUP,10_MAJ\n?,11_MAJ\n\n\n\n\n\n\n\n\n\
+                    SWO:K_SWO\nCUP:5_CUP\nWAN:K_WAN\nSTA:K_STA\nMAJ_LO:7_MAJ\nMAJ_HI:13_MAJ";
+
+fn sucked(mut board: Board) -> Board {
+    board.suck_readies_into_receptacles();
+    board
+}
+
+#[test]
+fn every_way_winnable_still_rereads_where_they_differ() {
+    let solve = |_: &Board| Some(Solution { moves: vec![] });
+    let plan = unknown::plan(DEAL, &solve, &Rules::default()).unwrap();
+    assert!(plan.solution.moves.is_empty());
+    assert_eq!(plan.reread, [(0, 0), (2, 0)]);
+}
+
+#[test]
+fn a_way_it_could_be_that_cant_be_won_needs_the_cards_read() {
+    // only the first way it could be can be won
+    let winnable = sucked(unknown::completions(DEAL)[0].clone());
+    let solve = |board: &Board| (*board == winnable).then(|| Solution { moves: vec![] });
+    let plan = unknown::plan(DEAL, &solve, &Rules::default()).unwrap();
+    assert!(plan.solution.moves.is_empty());
+    assert_eq!(plan.reread, unknown::unknown_positions(DEAL));
+}
+
+#[test]
+fn no_way_it_could_be_can_be_won() {
+    let solve = |_: &Board| None;
+    assert!(unknown::plan(DEAL, &solve, &Rules::default()).is_none());
+}